@@ -0,0 +1,595 @@
+#![allow(dead_code)]
+use library::lexeme::Radix;
+use library::lexeme::Span;
+use std::cell::RefCell;
+use std::fmt;
+
+// Typed syntax tree built by the parser and consumed by the code generator.
+// Every node carries the position of the token it started at so later
+// passes can point back into the C/C++ source.
+
+// source span of the token a node started at
+pub type Pos = Span;
+
+// problem found in the source, reported to the user with its position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub msg: String,
+    pub pos: Pos,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: warning: {}", self.pos.line, self.pos.col, self.msg)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sign {
+    Plain,
+    Signed,
+    Unsigned,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Void,
+    Bool,
+    Char(Sign),
+    Short(Sign),
+    Int(Sign),
+    Long(Sign),
+    LongLong(Sign),
+    // usize, which Rust indexes with
+    Size,
+    Float,
+    Double,
+    Auto,
+    Str,
+    // typedef, struct, union, class or enum name
+    Named(String),
+    // standard class template and its arguments, `std::vector<int>`
+    Template(String, Vec<Type>),
+    Pointer(Box<Type>),
+    Array(Box<Type>, Option<Box<Expr>>),
+    // what a `const T *` points to, the pointer itself may change
+    Const(Box<Type>),
+}
+
+impl Type {
+    pub fn is_pointer(&self) -> bool {
+        matches!(*self, Type::Pointer(_))
+    }
+
+    // the element type of nested arrays
+    pub fn innermost(&self) -> &Type {
+        match *self {
+            Type::Array(ref inner, _) => inner.innermost(),
+            _ => self,
+        }
+    }
+
+    // the type without its `const`
+    pub fn unqualified(&self) -> &Type {
+        match *self {
+            Type::Const(ref inner) => inner,
+            _ => self,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    Plus,
+    Not,
+    BitNot,
+    AddrOf,
+    Deref,
+    PreInc,
+    PreDec,
+    PostInc,
+    PostDec,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    Shl,
+    Shr,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    BitAnd,
+    BitXor,
+    BitOr,
+    And,
+    Or,
+}
+
+impl BinOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::Lt => "<",
+            BinOp::Gt => ">",
+            BinOp::Le => "<=",
+            BinOp::Ge => ">=",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::BitAnd => "&",
+            BinOp::BitXor => "^",
+            BinOp::BitOr => "|",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+        }
+    }
+}
+
+// numeric literal split into its parts, `0x1Fu` has
+// digits `1F`, radix Hex and suffix `u`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumLit {
+    // digit separators are kept as `_`
+    pub digits: String,
+    pub radix: Radix,
+    pub suffix: String,
+}
+
+impl NumLit {
+    // value of an integer literal
+    pub fn value(&self) -> Option<u64> {
+        let digits: String = self.digits.chars().filter(|&c| c != '_').collect();
+        let radix = match self.radix {
+            Radix::Decimal => 10,
+            Radix::Hex => 16,
+            Radix::Octal => 8,
+            Radix::Binary => 2,
+        };
+        u64::from_str_radix(&digits, radix).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Ident(String),
+    IntLit(NumLit),
+    FloatLit(NumLit),
+    // char and string literals hold the text with escapes decoded,
+    // a char literal of more than one character is a multi-character constant
+    CharLit(String),
+    StrLit(String),
+    // a string literal whose bytes are not UTF-8
+    ByteStrLit(Vec<u8>),
+    BoolLit(bool),
+    Null,
+    // `this` inside a method
+    This,
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    // `None` for plain `=`, otherwise the operator of a compound assignment
+    Assign(Option<BinOp>, Box<Expr>, Box<Expr>),
+    // condition ? then : otherwise
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+    Cast(Type, Box<Expr>),
+    Call(Box<Expr>, Vec<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Member(Box<Expr>, String),
+    SizeofType(Type),
+    SizeofExpr(Box<Expr>),
+    InitList(Vec<Expr>),
+    Comma(Vec<Expr>),
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, pos: Pos) -> Expr {
+        Expr { kind, pos }
+    }
+
+    // checks whether pred holds for this expression or any subexpression
+    pub fn any<F: Fn(&Expr) -> bool>(&self, pred: &F) -> bool {
+        if pred(self) {
+            return true;
+        }
+        match self.kind {
+            ExprKind::Unary(_, ref e) | ExprKind::Cast(_, ref e) |
+            ExprKind::Member(ref e, _) | ExprKind::SizeofExpr(ref e) => e.any(pred),
+            ExprKind::Binary(_, ref l, ref r) | ExprKind::Assign(_, ref l, ref r) |
+            ExprKind::Index(ref l, ref r) => l.any(pred) || r.any(pred),
+            ExprKind::Cond(ref c, ref t, ref o) => c.any(pred) || t.any(pred) || o.any(pred),
+            ExprKind::Call(ref f, ref args) => f.any(pred) || args.iter().any(|a| a.any(pred)),
+            ExprKind::InitList(ref list) | ExprKind::Comma(ref list) => {
+                list.iter().any(|e| e.any(pred))
+            }
+            _ => false,
+        }
+    }
+
+    // adds the identifiers the expression refers to to names
+    pub fn names(&self, names: &mut Vec<String>) {
+        let found = RefCell::new(Vec::new());
+        self.any(&|e: &Expr| {
+            if let ExprKind::Ident(ref name) = e.kind {
+                found.borrow_mut().push(name.clone());
+            }
+            false
+        });
+        names.append(&mut found.into_inner());
+    }
+
+    // checks whether the expression may modify the variable name
+    pub fn modifies(&self, name: &str) -> bool {
+        let is_name = |e: &Expr| match e.kind {
+            ExprKind::Ident(ref n) => n == name,
+            _ => false,
+        };
+        self.any(&|e: &Expr| match e.kind {
+            ExprKind::Assign(_, ref target, _) => is_name(target),
+            ExprKind::Unary(UnOp::PreInc, ref target) |
+            ExprKind::Unary(UnOp::PreDec, ref target) |
+            ExprKind::Unary(UnOp::PostInc, ref target) |
+            ExprKind::Unary(UnOp::PostDec, ref target) |
+            ExprKind::Unary(UnOp::AddrOf, ref target) => is_name(target),
+            _ => false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    Decl(Decl),
+    Expr(Expr),
+    Block(Vec<Stmt>),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    While(Expr, Box<Stmt>),
+    DoWhile(Box<Stmt>, Expr),
+    // init, condition, update, body
+    For(Option<Box<Stmt>>, Option<Expr>, Option<Expr>, Box<Stmt>),
+    Switch(Expr, Vec<Case>),
+    Break,
+    Continue,
+    Return(Option<Expr>),
+    Comment(String),
+    Empty,
+    // source that could not be parsed, kept for manual translation
+    Verbatim(String),
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, pos: Pos) -> Stmt {
+        Stmt { kind, pos }
+    }
+
+    // checks whether pred holds for any expression inside the statement
+    pub fn any_expr<F: Fn(&Expr) -> bool>(&self, pred: &F) -> bool {
+        let opt = |e: &Option<Expr>| e.as_ref().is_some_and(|e| e.any(pred));
+        match self.kind {
+            StmtKind::Decl(Decl::Var(ref vars)) => vars.iter().any(|v| opt(&v.init)),
+            StmtKind::Expr(ref e) => e.any(pred),
+            StmtKind::Block(ref stmts) => stmts.iter().any(|s| s.any_expr(pred)),
+            StmtKind::If(ref c, ref t, ref o) => {
+                c.any(pred) || t.any_expr(pred) || o.as_ref().is_some_and(|o| o.any_expr(pred))
+            }
+            StmtKind::While(ref c, ref body) | StmtKind::DoWhile(ref body, ref c) => {
+                c.any(pred) || body.any_expr(pred)
+            }
+            StmtKind::For(ref init, ref c, ref u, ref body) => {
+                init.as_ref().is_some_and(|i| i.any_expr(pred)) || opt(c) || opt(u) ||
+                body.any_expr(pred)
+            }
+            StmtKind::Switch(ref c, ref cases) => {
+                c.any(pred) || cases.iter().any(|case| case.body.iter().any(|s| s.any_expr(pred)))
+            }
+            StmtKind::Return(ref e) => opt(e),
+            _ => false,
+        }
+    }
+
+    // adds the variables the statement declares and the identifiers
+    // it refers to to names
+    pub fn names(&self, names: &mut Vec<String>) {
+        match self.kind {
+            StmtKind::Decl(Decl::Var(ref vars)) => {
+                for var in vars {
+                    names.push(var.name.clone());
+                    if let Some(ref init) = var.init {
+                        init.names(names);
+                    }
+                }
+            }
+            StmtKind::Expr(ref e) | StmtKind::Return(Some(ref e)) => e.names(names),
+            StmtKind::Block(ref stmts) => {
+                for s in stmts {
+                    s.names(names);
+                }
+            }
+            StmtKind::If(ref c, ref t, ref o) => {
+                c.names(names);
+                t.names(names);
+                if let Some(ref o) = *o {
+                    o.names(names);
+                }
+            }
+            StmtKind::While(ref c, ref body) | StmtKind::DoWhile(ref body, ref c) => {
+                c.names(names);
+                body.names(names);
+            }
+            StmtKind::For(ref init, ref c, ref u, ref body) => {
+                if let Some(ref init) = *init {
+                    init.names(names);
+                }
+                for e in c.iter().chain(u.iter()) {
+                    e.names(names);
+                }
+                body.names(names);
+            }
+            StmtKind::Switch(ref c, ref cases) => {
+                c.names(names);
+                for s in cases.iter().flat_map(|case| &case.body) {
+                    s.names(names);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // checks for a `break` which belongs to the enclosing switch,
+    // nested loops and switches are not searched
+    pub fn has_break(&self) -> bool {
+        match self.kind {
+            StmtKind::Break => true,
+            StmtKind::Block(ref stmts) => stmts.iter().any(|s| s.has_break()),
+            StmtKind::If(_, ref t, ref o) => t.has_break() || o.as_ref().is_some_and(|o| o.has_break()),
+            _ => false,
+        }
+    }
+
+    // checks for a `continue` which belongs to the enclosing loop,
+    // nested loops are not searched
+    pub fn has_continue(&self) -> bool {
+        match self.kind {
+            StmtKind::Continue => true,
+            StmtKind::Block(ref stmts) => stmts.iter().any(|s| s.has_continue()),
+            StmtKind::If(_, ref t, ref o) => {
+                t.has_continue() || o.as_ref().is_some_and(|o| o.has_continue())
+            }
+            StmtKind::Switch(_, ref cases) => {
+                cases.iter().any(|case| case.body.iter().any(|s| s.has_continue()))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    // stacked `case` labels sharing one body
+    pub labels: Vec<Expr>,
+    pub is_default: bool,
+    pub body: Vec<Stmt>,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarDecl {
+    pub name: String,
+    pub ty: Type,
+    pub init: Option<Expr>,
+    pub is_const: bool,
+    pub is_static: bool,
+    // written after its initialization
+    pub is_mut: bool,
+    // starts with the default value of its type, as it has no
+    // initializer and may be read before it is assigned
+    pub default_init: bool,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub ty: Type,
+    pub is_mut: bool,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub ret: Type,
+    pub params: Vec<Param>,
+    // takes `...` after the named parameters
+    pub variadic: bool,
+    // `None` for a prototype
+    pub body: Option<Vec<Stmt>>,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Default,
+    Public,
+    Private,
+    Protected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    Struct,
+    Union,
+    Class,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub ty: Type,
+    pub access: Access,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Method {
+    pub access: Access,
+    pub func: Function,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub kind: RecordKind,
+    pub name: String,
+    pub fields: Vec<Field>,
+    pub methods: Vec<Method>,
+    // constructors and destructors are kept as source text
+    pub ctors: Vec<String>,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enumerator {
+    pub name: String,
+    pub value: Option<Expr>,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDef {
+    pub name: String,
+    pub variants: Vec<Enumerator>,
+    pub pos: Pos,
+}
+
+// object-like `#define` whose body is a constant expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstDef {
+    pub name: String,
+    pub ty: Type,
+    pub value: Expr,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MacroBody {
+    Expr(Expr),
+    // do { ... } while (0)
+    Stmt(Box<Stmt>),
+}
+
+// function-like `#define`, an inline function when the types of its
+// parameters and result are known, macro_rules! otherwise
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroDef {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: MacroBody,
+    pub signature: Option<(Vec<Type>, Type)>,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Typedef {
+    pub name: String,
+    pub ty: Type,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decl {
+    Var(Vec<VarDecl>),
+    Function(Function),
+    Record(Record),
+    Enum(EnumDef),
+    Typedef(Typedef),
+    Const(ConstDef),
+    Macro(MacroDef),
+    // `#include` line, stored as written
+    Include(String, Pos),
+    // any other preprocessor line
+    Directive(String, Pos),
+    Comment(String, Pos),
+    Verbatim(String, Pos),
+}
+
+// Rust keeps types, values and struct members apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameSpace {
+    Type,
+    Value,
+    Member,
+}
+
+// name changed to follow the Rust naming conventions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+    pub space: NameSpace,
+    // from as written in C, before keywords were escaped
+    pub c_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Program {
+    pub decls: Vec<Decl>,
+    // variables declared `extern`, which another file defines
+    pub externs: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub renames: Vec<Rename>,
+}
+
+impl Program {
+    // typedef, struct, union, class and enum names declared at file
+    // scope, as they are spelled in the source
+    pub fn type_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for decl in &self.decls {
+            let name = match *decl {
+                Decl::Typedef(ref def) => &def.name,
+                Decl::Record(ref record) => &record.name,
+                Decl::Enum(ref def) => &def.name,
+                _ => continue,
+            };
+            let renamed = self.renames.iter().find(|r| r.space == NameSpace::Type && r.to == *name);
+            names.push(renamed.map_or(name, |r| &r.from).clone());
+        }
+        names
+    }
+
+    // functions and variables declared here and defined by another file
+    pub fn declarations(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.decls
+            .iter()
+            .filter_map(|decl| match *decl {
+                Decl::Function(ref func) if func.body.is_none() => Some(func.name.clone()),
+                _ => None,
+            })
+            .collect();
+        names.extend(self.externs.iter().cloned());
+        names
+    }
+}
@@ -0,0 +1,2305 @@
+#![allow(dead_code)]
+
+use library::ast::*;
+use library::lexeme::Radix;
+use library::doc::DocType;
+use library::doc::DocType::*;
+use library::headers::{self, StdName};
+use std::cell::{Cell, RefCell};
+
+
+/**
+ * Mutability:
+ * which bindings are declared `mut`. Inferred follows the writes the
+ * parser found, Strict and Loose make every binding immutable or mutable
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutability {
+    Inferred,
+    Strict,
+    Loose,
+}
+
+
+struct Generator {
+    mutability: Mutability,
+    public: bool, // items are exported from a header module
+    in_block_stmnt: bool, //default false
+    switch_label: Option<String>, // label of the enclosing switch block, a `break` exits it
+    switch_depth: usize,
+    in_main: bool,
+    once_warned: bool, //default false
+    records: Vec<Record>, // struct, union and class definitions seen so far
+    enumerators: Vec<(String, String)>, // enumerator and the name of its enum
+    consts: Vec<(String, Type)>, // constants from #define and their types
+    macros: Vec<String>, // macro_rules! defined so far
+    macro_params: Vec<String>, // parameters of the macro_rules! being generated
+    std_names: Vec<&'static StdName>, // names of the included standard headers
+    imports: RefCell<Vec<&'static str>>, // paths the standard names used so far need
+    pending: Vec<String>, // postfix updates to emit after the current statement
+    defer_updates: bool, // whether the statement being generated may leave them pending
+    names: Vec<String>, // names the function being generated declares or refers to
+    statics: Vec<String>, // `static mut` items, whose accesses are unsafe
+    exported: Vec<String>, // functions and variables the included headers declare
+    in_unsafe: Cell<bool>, // whether the expression being generated is inside an unsafe block
+    indent: usize,
+    out: String,
+}
+
+
+/**
+ * Unit:
+ * where the generated file sits among the translated headers,
+ * public marks a header module, mods are the modules it declares
+ * (true when they export macros), uses the modules it imports
+ * and imported their syntax trees
+ */
+#[derive(Default)]
+pub struct Unit<'a> {
+    pub public: bool,
+    pub mods: Vec<(String, bool)>,
+    pub uses: Vec<String>,
+    pub imported: Vec<&'a Program>,
+    // functions and variables the other translated files declare
+    pub exported: Vec<String>,
+}
+
+
+/**
+ * generate:
+ * turns the syntax tree into Rust source
+ */
+pub fn generate(program: &Program, mutability: Mutability, unit: &Unit) -> String {
+    let mut gen = Generator {
+        mutability,
+        public: unit.public,
+        in_block_stmnt: false,
+        switch_label: None,
+        switch_depth: 0,
+        in_main: false,
+        once_warned: false,
+        records: Vec::new(),
+        enumerators: Vec::new(),
+        macros: Vec::new(),
+        macro_params: Vec::new(),
+        std_names: Vec::new(),
+        imports: RefCell::new(Vec::new()),
+        pending: Vec::new(),
+        defer_updates: false,
+        names: Vec::new(),
+        statics: Vec::new(),
+        exported: Vec::new(),
+        consts: Vec::new(),
+        in_unsafe: Cell::new(false),
+        indent: 0,
+        out: String::new(),
+    };
+    gen.out.push_str(CRUST.get_doc());
+    // the old names stay searchable
+    if !program.renames.is_empty() {
+        gen.out.push_str("\n/* Renamed to follow the Rust naming conventions:\n");
+        for rename in &program.renames {
+            gen.out.push_str(&format!(" * {} -> {}\n", rename.c_name, rename.to));
+        }
+        gen.out.push_str(" */\n");
+    }
+    for imported in &unit.imported {
+        gen.import(imported);
+    }
+    gen.exported.extend(unit.exported.iter().cloned());
+    for &(ref name, macro_use) in &unit.mods {
+        if macro_use {
+            gen.line("#[macro_use]");
+        }
+        gen.line(&format!("mod {};", name));
+    }
+    for name in &unit.uses {
+        gen.line(&format!("use crate::{}::*;", name));
+    }
+    gen.gen_program(program);
+    gen.out
+}
+
+
+/**
+ * fn parse_type:
+ * takes a C/C++ type
+ * returns either the equivalent Rust type as a string or
+ * None, if it is not a plain (non pointer, non array, non template) type
+ */
+pub fn parse_type(c_type: &Type) -> Option<String> {
+    let rust_type = match *c_type {
+        Type::Void => "()",
+        Type::Bool => "bool",
+        // C char holds bytes
+        Type::Char(Sign::Plain) => "u8",
+        Type::Char(Sign::Signed) => "i8",
+        Type::Char(Sign::Unsigned) => "u8",
+        Type::Short(Sign::Unsigned) => "u16",
+        Type::Short(_) => "i16",
+        Type::Int(Sign::Unsigned) => "u32",
+        Type::Int(_) => "i32",
+        Type::Long(Sign::Unsigned) |
+        Type::LongLong(Sign::Unsigned) => "u64",
+        Type::Long(_) | Type::LongLong(_) => "i64",
+        Type::Size => "usize",
+        Type::Float => "f32",
+        Type::Double => "f64",
+        Type::Auto => "_",
+        Type::Str => "String",
+        Type::Named(ref name) => return Some(name.clone()),
+        Type::Const(ref inner) => return parse_type(inner),
+        Type::Pointer(_) | Type::Array(..) | Type::Template(..) => return None,
+    };
+    Some(rust_type.to_string())
+}
+
+
+pub fn get_default_value_for(c_type: &Type) -> String {
+    match *c_type {
+        Type::Int(Sign::Unsigned) => "0u32".to_string(),
+        Type::Int(_) => "0i32".to_string(),
+        Type::Short(Sign::Unsigned) => "0u16".to_string(),
+        Type::Short(_) => "0i16".to_string(),
+        Type::Long(Sign::Unsigned) |
+        Type::LongLong(Sign::Unsigned) => "0u64".to_string(),
+        Type::Long(_) | Type::LongLong(_) => "0i64".to_string(),
+        Type::Float => "0.0f32".to_string(),
+        Type::Double => "0.0f64".to_string(),
+        Type::Char(Sign::Plain) => "0u8".to_string(),
+        Type::Char(Sign::Signed) => "0i8".to_string(),
+        Type::Char(Sign::Unsigned) => "0u8".to_string(),
+        Type::Bool => "false".to_string(),
+        Type::Str => "String::new()".to_string(),
+        _ => "_".to_string(),
+    }
+}
+
+
+// Rust operator precedence, higher binds tighter
+const PREC_ASSIGN: u8 = 1;
+// if expressions, parenthesized when used as an operand
+const PREC_COND: u8 = 2;
+const PREC_OR: u8 = 4;
+const PREC_AND: u8 = 5;
+const PREC_COMPARE: u8 = 6;
+const PREC_BITOR: u8 = 7;
+const PREC_BITXOR: u8 = 8;
+const PREC_BITAND: u8 = 9;
+const PREC_SHIFT: u8 = 10;
+const PREC_SUM: u8 = 11;
+const PREC_PRODUCT: u8 = 12;
+const PREC_CAST: u8 = 13;
+const PREC_UNARY: u8 = 14;
+const PREC_POSTFIX: u8 = 15;
+
+
+fn binop_prec(op: BinOp) -> u8 {
+    match op {
+        BinOp::Mul | BinOp::Div | BinOp::Rem => PREC_PRODUCT,
+        BinOp::Add | BinOp::Sub => PREC_SUM,
+        BinOp::Shl | BinOp::Shr => PREC_SHIFT,
+        BinOp::BitAnd => PREC_BITAND,
+        BinOp::BitXor => PREC_BITXOR,
+        BinOp::BitOr => PREC_BITOR,
+        BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::Eq | BinOp::Ne => PREC_COMPARE,
+        BinOp::And => PREC_AND,
+        BinOp::Or => PREC_OR,
+    }
+}
+
+
+// precedence of the Rust expression generated for expr
+fn rust_prec(expr: &Expr) -> u8 {
+    match expr.kind {
+        ExprKind::Binary(op, _, _) => binop_prec(op),
+        ExprKind::Assign(..) => PREC_ASSIGN,
+        ExprKind::Cond(..) => PREC_COND,
+        ExprKind::Cast(Type::Void, ref operand) => rust_prec(operand),
+        ExprKind::Cast(..) => PREC_CAST,
+        ExprKind::Unary(UnOp::Plus, ref operand) => rust_prec(operand),
+        ExprKind::Unary(UnOp::PreInc, _) | ExprKind::Unary(UnOp::PreDec, _) |
+        ExprKind::Unary(UnOp::PostInc, _) | ExprKind::Unary(UnOp::PostDec, _) => PREC_POSTFIX,
+        // `&mut x as *mut _`
+        ExprKind::Unary(UnOp::AddrOf, _) => PREC_CAST,
+        ExprKind::Unary(..) => PREC_UNARY,
+        _ => PREC_POSTFIX,
+    }
+}
+
+
+// checks whether the Rust text of expr ends with the type of a cast
+fn ends_in_cast(expr: &Expr) -> bool {
+    match expr.kind {
+        ExprKind::Cast(Type::Void, ref operand) | ExprKind::Unary(UnOp::Plus, ref operand) => ends_in_cast(operand),
+        ExprKind::Cast(..) | ExprKind::Unary(UnOp::AddrOf, _) => true,
+        ExprKind::Binary(_, _, ref r) => ends_in_cast(r),
+        _ => false,
+    }
+}
+
+
+/**
+ * int_literal:
+ * Rust spelling of a C integer literal, octal gets the 0o prefix
+ * and the suffix names the type the suffix gives in C
+ */
+fn int_literal(lit: &NumLit) -> String {
+    let prefix = match lit.radix {
+        Radix::Decimal => "",
+        Radix::Hex => "0x",
+        Radix::Octal => "0o",
+        Radix::Binary => "0b",
+    };
+    let suffix = lit.suffix.to_lowercase();
+    let unsigned = suffix.contains('u');
+    let long = suffix.contains('l');
+    let ty = match (unsigned, long) {
+        (true, true) => "u64",
+        (true, false) => "u32",
+        (false, true) => "i64",
+        (false, false) => "",
+    };
+    format!("{}{}{}", prefix, lit.digits, ty)
+}
+
+
+// Rust type of an unsuffixed C integer literal, the first of int, long and
+// for hexadecimal and octal also their unsigned variants holding its value
+fn c_literal_type(lit: &NumLit) -> &'static str {
+    let value = lit.value().unwrap_or(0);
+    let decimal = lit.radix == Radix::Decimal;
+    if value <= i32::MAX as u64 {
+        "i32"
+    } else if value <= u32::MAX as u64 && !decimal {
+        "u32"
+    } else if value <= i64::MAX as u64 {
+        "i64"
+    } else {
+        "u64"
+    }
+}
+
+
+/**
+ * float_literal:
+ * Rust spelling of a C floating literal. Rust needs digits on both
+ * sides of the point and has no hex floats, those are written out.
+ * Exponent forms are suffixed as the double they are in C
+ */
+fn float_literal(lit: &NumLit) -> String {
+    let exponent = lit.radix == Radix::Decimal && lit.digits.contains(['e', 'E']);
+    let ty = match lit.suffix.as_ref() {
+        "f" | "F" => "f32",
+        "l" | "L" => "f64",
+        // 1e-3 reads like an integer without it
+        _ if exponent => "f64",
+        _ => "",
+    };
+    if lit.radix == Radix::Hex {
+        return format!("{:?}{}", hex_float_value(&lit.digits), ty);
+    }
+    let mut digits = lit.digits.clone();
+    if digits.starts_with('.') {
+        digits.insert(0, '0');
+    }
+    if let Some(dot) = digits.find('.') {
+        let after = digits[dot + 1..].chars().next();
+        if !after.is_some_and(|c| c.is_ascii_digit()) {
+            digits.insert(dot + 1, '0');
+        }
+    }
+    format!("{}{}", digits, ty)
+}
+
+
+// value of a hex float such as 1.8p3, mantissa in hex and a binary exponent
+fn hex_float_value(digits: &str) -> f64 {
+    let digits = digits.replace('_', "");
+    let (mantissa, exp) = match digits.find(['p', 'P']) {
+        Some(p) => (&digits[..p], digits[p + 1..].parse::<i32>().unwrap_or(0)),
+        None => (&digits[..], 0),
+    };
+    let mut value = 0f64;
+    let mut scale = 0;
+    let mut after_point = false;
+    for c in mantissa.chars() {
+        match c.to_digit(16) {
+            Some(d) => {
+                value = value * 16.0 + d as f64;
+                if after_point {
+                    scale += 4;
+                }
+            }
+            None => after_point = true,
+        }
+    }
+    value * 2f64.powi(exp - scale)
+}
+
+
+// escapes text for a Rust literal delimited by quote
+fn escape(text: &str, quote: char) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        match c {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            '\\' => out.push_str("\\\\"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if (c as u32) < 0x20 || c as u32 == 0x7F => out.push_str(&format!("\\x{:02X}", c as u32)),
+            // byte literals only take ASCII, the rest as \x escapes
+            c if quote == '\'' && (c as u32) > 0x7F => {
+                out.push_str(&format!("\\x{:02X}", c as u32 & 0xFF))
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+
+// body of a byte string, bytes outside printable ASCII as \x escapes
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for &b in bytes {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\t' => out.push_str("\\t"),
+            0x20..=0x7E => out.push(b as char),
+            _ => out.push_str(&format!("\\x{:02X}", b)),
+        }
+    }
+    out
+}
+
+
+/**
+ * char_literal:
+ * C char literals are bytes, a multi-character constant is an int
+ * whose value the compiler defines, the usual big-endian packing is
+ * used and flagged in a comment
+ */
+fn char_literal(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() == 1 {
+        return format!("b'{}'", escape(text, '\''));
+    }
+    let value = chars.iter().fold(0u32, |v, &c| (v << 8) | (c as u32 & 0xFF));
+    format!("0x{:X} /* multi-character constant '{}' */", value, escape(text, '\''))
+}
+
+
+// checks for `1` or `true`, the condition of an infinite loop
+fn is_always_true(cond: &Expr) -> bool {
+    match cond.kind {
+        ExprKind::IntLit(ref v) => v.value() == Some(1),
+        ExprKind::BoolLit(b) => b,
+        _ => false,
+    }
+}
+
+
+// checks whether control cannot reach past the end of a case body
+fn ends_in_jump(stmts: &[Stmt]) -> bool {
+    let last = stmts.iter().rev().find(|s| !matches!(s.kind, StmtKind::Comment(_) | StmtKind::Empty));
+    match last.map(|s| &s.kind) {
+        Some(StmtKind::Break) | Some(StmtKind::Continue) | Some(StmtKind::Return(_)) => true,
+        Some(StmtKind::Block(stmts)) => ends_in_jump(stmts),
+        Some(StmtKind::If(_, then, Some(otherwise))) => {
+            ends_in_jump(std::slice::from_ref(then)) && ends_in_jump(std::slice::from_ref(otherwise))
+        }
+        _ => false,
+    }
+}
+
+
+// drops the `break` ending a case body, the arm ends there anyway
+fn strip_tail_break(stmts: &mut [Stmt]) {
+    let last = stmts.iter_mut().rev().find(|s| !matches!(s.kind, StmtKind::Comment(_) | StmtKind::Empty));
+    if let Some(stmt) = last {
+        match stmt.kind {
+            StmtKind::Break => stmt.kind = StmtKind::Empty,
+            StmtKind::Block(ref mut stmts) => strip_tail_break(stmts),
+            StmtKind::If(_, ref mut then, ref mut otherwise) => {
+                strip_tail_break(std::slice::from_mut(then));
+                if let Some(ref mut otherwise) = *otherwise {
+                    strip_tail_break(std::slice::from_mut(otherwise));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+
+// source kept verbatim could touch any variable
+fn body_has_verbatim(stmt: &Stmt) -> bool {
+    match stmt.kind {
+        StmtKind::Verbatim(_) => true,
+        StmtKind::Block(ref stmts) => stmts.iter().any(body_has_verbatim),
+        StmtKind::If(_, ref t, ref o) => {
+            body_has_verbatim(t) || o.as_ref().is_some_and(|o| body_has_verbatim(o))
+        }
+        StmtKind::While(_, ref body) | StmtKind::DoWhile(ref body, _) |
+        StmtKind::For(_, _, _, ref body) => body_has_verbatim(body),
+        StmtKind::Switch(_, ref cases) => {
+            cases.iter().any(|case| case.body.iter().any(body_has_verbatim))
+        }
+        _ => false,
+    }
+}
+
+
+/**
+ * defers_updates:
+ * checks whether the postfix increments of a statement can run after it,
+ * which holds when each one updates a variable used nowhere else in the
+ * statement and nothing is evaluated conditionally or by a call
+ */
+fn defers_updates(expr: &Expr) -> bool {
+    let is_postfix = |e: &Expr| matches!(e.kind, ExprKind::Unary(UnOp::PostInc, _) | ExprKind::Unary(UnOp::PostDec, _));
+    if !expr.any(&is_postfix) {
+        return true;
+    }
+    let branches = expr.any(&|e: &Expr| {
+        matches!(e.kind,
+                 ExprKind::Binary(BinOp::And, ..) | ExprKind::Binary(BinOp::Or, ..) | ExprKind::Cond(..) |
+                 ExprKind::Call(..))
+    });
+    !branches &&
+    !expr.any(&|e: &Expr| match e.kind {
+        ExprKind::Unary(UnOp::PostInc, ref target) | ExprKind::Unary(UnOp::PostDec, ref target) => {
+            match target.kind {
+                ExprKind::Ident(ref name) => uses(expr, name) != 1,
+                _ => true,
+            }
+        }
+        _ => false,
+    })
+}
+
+// checks for assignments, increments and calls inside expr
+fn has_side_effects(expr: &Expr) -> bool {
+    expr.any(&|e: &Expr| {
+        matches!(e.kind,
+                 ExprKind::Assign(..) | ExprKind::Call(..) | ExprKind::Unary(UnOp::PreInc, _) |
+                 ExprKind::Unary(UnOp::PreDec, _) | ExprKind::Unary(UnOp::PostInc, _) |
+                 ExprKind::Unary(UnOp::PostDec, _))
+    })
+}
+
+// number of times expr names the variable name
+fn uses(expr: &Expr, name: &str) -> usize {
+    let count = Cell::new(0);
+    expr.any(&|e: &Expr| {
+        if matches!(e.kind, ExprKind::Ident(ref n) if n == name) {
+            count.set(count.get() + 1);
+        }
+        false
+    });
+    count.get()
+}
+
+
+// checks for iostream style `cout << "..."` expressions
+fn is_stream_expr(expr: &Expr) -> bool {
+    match expr.kind {
+        ExprKind::Binary(BinOp::Shl, ref l, ref r) |
+        ExprKind::Binary(BinOp::Shr, ref l, ref r) => {
+            let literal = |e: &Expr| match e.kind {
+                ExprKind::StrLit(_) | ExprKind::ByteStrLit(_) | ExprKind::CharLit(_) => true,
+                ExprKind::Ident(ref n) => matches!(n.trim_start_matches("std::"), "cout" | "cin" | "cerr" | "endl"),
+                _ => false,
+            };
+            literal(l) || literal(r) || is_stream_expr(l)
+        }
+        _ => false,
+    }
+}
+
+
+impl Generator {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn doc(&mut self, doc: DocType) {
+        let text = doc.get_doc();
+        self.out.push_str(text);
+        if !text.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    // source which could not be translated is passed through as is
+    fn manual(&mut self, text: &str, pos: Pos) {
+        self.line(&format!("//This statement need to be handled manually, line {}:{}",
+                           pos.line,
+                           pos.col));
+        self.line(text);
+    }
+
+    // whether a binding written or not after its initialization is `mut`
+    fn mutable(&self, is_mut: bool) -> bool {
+        match self.mutability {
+            Mutability::Inferred => is_mut,
+            Mutability::Strict => false,
+            Mutability::Loose => true,
+        }
+    }
+
+    // the overrides warn about the bindings they get wrong
+    fn mutability_doc(&mut self) {
+        match self.mutability {
+            Mutability::Inferred => {}
+            Mutability::Strict => self.doc(STRICT),
+            Mutability::Loose => self.doc(NO_STRICT),
+        }
+    }
+
+    fn mut_prefix(&self, is_mut: bool) -> &'static str {
+        if self.mutable(is_mut) { "mut " } else { "" }
+    }
+
+    // name starting with base that the function being generated does not use
+    fn fresh(&self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut n = 0;
+        while self.names.contains(&name) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+        name
+    }
+
+    fn flush_pending(&mut self) {
+        let pending: Vec<String> = self.pending.drain(..).collect();
+        for update in pending {
+            self.line(&update);
+        }
+    }
+
+    fn find_record(&self, ty: &Type) -> Option<Record> {
+        match *ty {
+            Type::Named(ref name) => self.records.iter().find(|r| &r.name == name).cloned(),
+            _ => None,
+        }
+    }
+
+    /**
+     * rust_type:
+     * Rust spelling of a C/C++ type, pointers become references
+     */
+    fn rust_type(&self, ty: &Type) -> String {
+        let borrow = if self.mutability == Mutability::Strict { "&" } else { "&mut " };
+        match *ty {
+            // pointers may be null, so they stay raw
+            Type::Pointer(ref inner) => {
+                let pointer = if let Type::Const(_) = **inner { "*const" } else { self.pointer() };
+                format!("{} {}", pointer, self.rust_type(inner))
+            }
+            Type::Const(ref inner) => self.rust_type(inner),
+            Type::Array(ref inner, Some(ref len)) => format!("[{}; {}]", self.rust_type(inner), self.array_len(len)),
+            // unsized arrays decay to pointers
+            Type::Array(ref inner, None) => format!("{}[{}]", borrow, self.rust_type(inner)),
+            Type::Template(ref name, ref args) => {
+                let args: Vec<String> = args.iter().map(|a| self.rust_type(a)).collect();
+                format!("{}<{}>", self.std_template(name), args.join(", "))
+            }
+            _ => parse_type(ty).unwrap(),
+        }
+    }
+
+
+    // the raw pointer kind of the mutability mode
+    fn pointer(&self) -> &'static str {
+        if self.mutability == Mutability::Strict { "*const" } else { "*mut" }
+    }
+
+    fn null(&self) -> &'static str {
+        if self.mutability == Mutability::Strict { "std::ptr::null()" } else { "std::ptr::null_mut()" }
+    }
+
+    fn array_len(&self, len: &Expr) -> String {
+        match len.kind {
+            ExprKind::IntLit(_) => self.gen_expr_mut(len, &mut Vec::new()),
+            // constants from #define are not usize
+            _ => format!("{} as usize", self.gen_operand(len, PREC_CAST, &mut Vec::new())),
+        }
+    }
+
+    // value a variable without initializer starts with, arrays repeat the one of their elements
+    fn default_value(&self, ty: &Type) -> Option<String> {
+        match *ty {
+            Type::Array(ref inner, Some(ref len)) => {
+                self.default_value(inner).map(|value| format!("[{}; {}]", value, self.array_len(len)))
+            }
+            Type::Template(ref name, _) => Some(format!("{}::new()", self.std_template(name))),
+            Type::Pointer(_) => Some(self.null().to_string()),
+            _ => Some(get_default_value_for(ty)).filter(|value| value != "_"),
+        }
+    }
+
+
+    // definitions of an included header the body refers to
+    fn import(&mut self, program: &Program) {
+        for decl in &program.decls {
+            match *decl {
+                Decl::Record(ref record) => self.records.push(record.clone()),
+                Decl::Enum(ref def) => {
+                    for variant in &def.variants {
+                        self.enumerators.push((variant.name.clone(), def.name.clone()));
+                    }
+                }
+                Decl::Macro(ref def) if def.signature.is_none() => self.macros.push(def.name.clone()),
+                Decl::Const(ref def) => self.consts.push((def.name.clone(), def.ty.clone())),
+                // includers may write the globals of a header
+                Decl::Var(ref vars) if self.mutable(true) => {
+                    self.statics.extend(vars.iter().filter(|v| !v.is_const).map(|v| v.name.clone()));
+                }
+                _ => {}
+            }
+        }
+        // defined by one of the translated files
+        self.exported.extend(program.declarations());
+        if self.mutable(true) {
+            self.statics.extend(program.externs.iter().cloned());
+        }
+    }
+
+    // checks whether expr reads or writes a place behind a raw pointer or
+    // in a `static mut`, `*p`, `(*p).x`, `*p = 1` or `TOTAL += 1`
+    fn unsafe_place(&self, expr: &Expr) -> bool {
+        match expr.kind {
+            ExprKind::Unary(UnOp::Deref, _) => true,
+            // Rust does not let bindings shadow statics
+            ExprKind::Ident(ref name) => self.statics.contains(name),
+            ExprKind::Member(ref base, _) | ExprKind::Index(ref base, _) => self.unsafe_place(base),
+            ExprKind::Assign(_, ref target, _) | ExprKind::Unary(UnOp::AddrOf, ref target) => self.unsafe_place(target),
+            ExprKind::Unary(UnOp::PreInc, ref target) | ExprKind::Unary(UnOp::PreDec, ref target) |
+            ExprKind::Unary(UnOp::PostInc, ref target) | ExprKind::Unary(UnOp::PostDec, ref target) => {
+                self.unsafe_place(target)
+            }
+            _ => false,
+        }
+    }
+
+    // visibility of a file scope item
+    fn vis(&self) -> &'static str {
+        if self.public && !self.in_block_stmnt { "pub " } else { "" }
+    }
+
+    // whether name is defined here for the files including a header
+    fn is_exported(&self, name: &str) -> bool {
+        !self.in_block_stmnt && self.exported.iter().any(|n| n == name)
+    }
+
+
+    fn gen_program(&mut self, program: &Program) {
+        self.find_std_names(program);
+        for decl in &program.decls {
+            if let Decl::Const(ref def) = *decl {
+                self.consts.push((def.name.clone(), def.ty.clone()));
+            }
+        }
+        let start = self.out.len();
+        let mut includes: Vec<String> = Vec::new();
+        for decl in &program.decls {
+            if let Decl::Include(ref text, _) = *decl {
+                includes.push(text.clone());
+                continue;
+            }
+            self.gen_includes(&mut includes);
+            self.gen_decl(decl);
+        }
+        self.gen_includes(&mut includes);
+
+        // standard headers become the imports the translated code uses
+        let mut uses = String::new();
+        for line in headers::use_lines(&self.imports.borrow()) {
+            uses.push_str(&line);
+            uses.push('\n');
+        }
+        self.out.insert_str(start, &uses);
+    }
+
+    // the standard names in scope, unless the program declares its own
+    fn find_std_names(&mut self, program: &Program) {
+        let mut declared: Vec<&str> = Vec::new();
+        for decl in &program.decls {
+            match *decl {
+                Decl::Var(ref vars) => declared.extend(vars.iter().map(|v| &v.name[..])),
+                Decl::Function(ref func) => declared.push(&func.name),
+                Decl::Const(ref def) => declared.push(&def.name),
+                Decl::Macro(ref def) => declared.push(&def.name),
+                _ => {}
+            }
+        }
+        for decl in &program.decls {
+            if let Decl::Include(ref text, _) = *decl {
+                if let Some(header) = headers::find_header(text) {
+                    self.std_names.extend(header.names.iter().filter(|n| !declared.contains(&n.name)));
+                    self.std_names.extend(header.templates);
+                }
+            }
+        }
+    }
+
+    // a standard name, or function called with args arguments,
+    // recording the imports it needs
+    fn std_name(&self, name: &str, args: Option<usize>) -> Option<&'static StdName> {
+        let item = *self.std_names.iter().find(|n| n.name == name)?;
+        let placeholders = item.rust.matches('$').count();
+        let variadic = item.rust.contains("$*") && args.is_some_and(|n| n > 0);
+        if !variadic && (args.unwrap_or(0) != placeholders || args.is_some() && placeholders == 0) {
+            return None;
+        }
+        let mut imports = self.imports.borrow_mut();
+        for import in item.imports {
+            if !imports.contains(import) {
+                imports.push(import);
+            }
+        }
+        Some(item)
+    }
+
+    // checks for a constant `N` or `-N` whose Rust type is ty
+    fn has_const_type(&self, expr: &Expr, ty: &Type) -> bool {
+        match expr.kind {
+            ExprKind::Ident(ref name) => {
+                self.consts.iter().rev().find(|c| c.0 == *name).is_some_and(|c| {
+                    parse_type(&c.1).is_some_and(|rust| Some(rust) == parse_type(ty))
+                })
+            }
+            ExprKind::Unary(UnOp::Neg, ref e) => self.has_const_type(e, ty),
+            _ => false,
+        }
+    }
+
+    // the Rust type of a standard class template, recording the imports it needs
+    fn std_template(&self, name: &str) -> String {
+        let item = match self.std_names.iter().find(|n| n.name == name) {
+            Some(item) => item,
+            None => return name.to_string(),
+        };
+        let mut imports = self.imports.borrow_mut();
+        for import in item.imports {
+            if !imports.contains(import) {
+                imports.push(import);
+            }
+        }
+        item.rust.to_string()
+    }
+
+    /**
+     * gen_print:
+     * generates a print! like macro call writing the text of a printf
+     * format or the operands of a stream, a trailing newline selects
+     * the println! form
+     */
+    fn gen_print(&self, name: &str, mut format: String, args: Vec<String>) -> String {
+        let mut name = name.to_string();
+        if format.ends_with('\n') && name.ends_with("print!") {
+            format.pop();
+            name = name.replace("print!", "println!");
+        }
+        if format.is_empty() && args.is_empty() {
+            return format!("{}()", name);
+        }
+        let mut parts = vec![format!("\"{}\"", escape(&format, '"'))];
+        parts.extend(args);
+        format!("{}({})", name, parts.join(", "))
+    }
+
+    // printf with a literal format string as print!
+    fn gen_printf(&self, template: &str, args: &[Expr], pending: &mut Vec<String>) -> Option<String> {
+        let text = match args.first()?.kind {
+            ExprKind::StrLit(ref text) => text,
+            _ => return None,
+        };
+        let (format, convs) = headers::rust_format(text)?;
+        if convs.len() != args.len() - 1 {
+            return None;
+        }
+        let values = args[1..]
+            .iter()
+            .zip(convs)
+            .map(|(arg, conv)| match conv {
+                // %c takes the character code
+                'c' => format!("{} as u8 as char", self.gen_operand(arg, PREC_CAST, pending)),
+                // %s reads a NUL terminated string through the pointer the parser cast
+                's' => match arg.kind {
+                    ExprKind::Cast(Type::Pointer(_), ref pointer) => {
+                        let in_unsafe = self.in_unsafe.replace(true);
+                        let pointer = self.gen_operand(pointer, PREC_CAST, pending);
+                        self.in_unsafe.set(in_unsafe);
+                        format!("unsafe {{ std::ffi::CStr::from_ptr({} as *const _) }}.to_string_lossy()", pointer)
+                    }
+                    _ => self.gen_operand(arg, 0, pending),
+                },
+                _ => self.gen_operand(arg, 0, pending),
+            })
+            .collect();
+        let name = &template[..template.find('(')?];
+        Some(self.gen_print(name, format, values))
+    }
+
+    /**
+     * gen_stream:
+     * writes `cout << a << "b" << endl` with print!, once the
+     * stream names are in scope. Returns None for other streams
+     */
+    fn gen_stream(&mut self, expr: &Expr) -> Option<String> {
+        let mut operands: Vec<&Expr> = Vec::new();
+        let mut stream = expr;
+        while let ExprKind::Binary(BinOp::Shl, ref l, ref r) = stream.kind {
+            operands.push(r);
+            stream = l;
+        }
+        let std_name = |e: &Expr| match e.kind {
+            ExprKind::Ident(ref name) => self.std_name(name.trim_start_matches("std::"), None),
+            _ => None,
+        };
+        let name = std_name(stream).filter(|item| item.rust.ends_with("print!"))?.rust;
+        let mut format = String::new();
+        let mut args: Vec<String> = Vec::new();
+        let mut pending: Vec<String> = Vec::new();
+        for operand in operands.into_iter().rev() {
+            match operand.kind {
+                ExprKind::StrLit(ref text) => format.push_str(&text.replace('{', "{{").replace('}', "}}")),
+                ExprKind::Ident(_) if std_name(operand).is_some() => format.push_str(std_name(operand)?.rust),
+                _ => {
+                    format.push_str("{}");
+                    args.push(self.gen_operand(operand, 0, &mut pending));
+                }
+            }
+        }
+        if !pending.is_empty() {
+            return None;
+        }
+        Some(self.gen_print(name, format, args))
+    }
+
+    /**
+     * gen_std_call:
+     * fills the template of a standard function with the arguments,
+     * each as tight as the operator following its placeholder
+     */
+    fn gen_std_call(&self, template: &str, args: &[Expr], pending: &mut Vec<String>) -> String {
+        let mut out = String::new();
+        let mut rest = template;
+        while let Some(i) = rest.find('$') {
+            out.push_str(&rest[..i]);
+            let index = rest[i + 1..i + 2].parse::<usize>().unwrap();
+            rest = &rest[i + 2..];
+            let min = if rest.starts_with('.') {
+                PREC_POSTFIX
+            } else if rest.starts_with(" as ") {
+                PREC_CAST
+            } else {
+                0
+            };
+            out.push_str(&self.gen_operand(&args[index], min, pending));
+        }
+        out.push_str(rest);
+        out
+    }
+
+    // consecutive includes share one warning comment,
+    // known standard headers are replaced by imports
+    fn gen_includes(&mut self, includes: &mut Vec<String>) {
+        includes.retain(|include| headers::find_header(include).is_none());
+        if includes.is_empty() {
+            return;
+        }
+        if !self.once_warned {
+            self.doc(INCLUDE_STMT);
+        } else {
+            self.out.push_str("/* >>>>>>>>");
+        }
+        for include in includes.drain(..) {
+            self.out.push_str(&include);
+            self.out.push('\n');
+        }
+        self.out.push_str("**/\n");
+        self.once_warned = true;
+    }
+
+
+    fn gen_decl(&mut self, decl: &Decl) {
+        match *decl {
+            Decl::Var(ref vars) => self.gen_declaration(vars),
+            Decl::Function(ref func) => {
+                if func.body.is_some() {
+                    self.gen_function(func, None);
+                }
+            }
+            Decl::Record(ref record) => {
+                self.records.push(record.clone());
+                match record.kind {
+                    RecordKind::Union => self.gen_union(record),
+                    _ => self.gen_struct(record),
+                }
+            }
+            Decl::Enum(ref def) => self.gen_enum(def),
+            Decl::Typedef(ref def) => {
+                let line = format!("{}type {} = {};", self.vis(), def.name, self.rust_type(&def.ty));
+                self.line(&line);
+            }
+            Decl::Const(ref def) => {
+                let ty = match def.ty {
+                    Type::Str => "&str".to_string(),
+                    ref ty => self.rust_type(ty),
+                };
+                let line = format!("{}const {}: {} = {};", self.vis(), def.name, ty, self.gen_expr(&def.value));
+                self.line(&line);
+            }
+            Decl::Macro(ref def) => self.gen_macro(def),
+            Decl::Include(ref text, _) => {
+                let mut includes = vec![text.clone()];
+                self.gen_includes(&mut includes);
+            }
+            Decl::Directive(ref text, _) => {
+                self.line("//This statement need to be handled manually");
+                self.line(&format!("// {}", text));
+            }
+            Decl::Comment(ref text, _) => self.line(text),
+            Decl::Verbatim(ref text, pos) => self.manual(text, pos),
+        }
+    }
+
+
+    /**
+     * gen_declaration:
+     * generates let (static inside global scope) bindings
+     * struct and class variables are initialized field by field
+     */
+    fn gen_declaration(&mut self, vars: &[VarDecl]) {
+        let mut warned = false;
+        for var in vars {
+            if let Some(record) = self.find_record(&var.ty) {
+                self.gen_record_var(var, &record);
+                continue;
+            }
+            if !warned {
+                self.mutability_doc();
+                warned = true;
+            }
+
+            // includers of a header may write its globals
+            let exported = self.is_exported(&var.name);
+            let mutable = self.mutable(var.is_mut || exported || self.public && !self.in_block_stmnt);
+            let mut stmt = if var.is_const {
+                "const".to_string()
+            } else if self.in_block_stmnt {
+                if mutable { "let mut" } else { "let" }.to_string()
+            } else if mutable || var.ty.innermost().is_pointer() {
+                // raw pointers cannot be shared between threads, only a `static mut` holds them
+                self.statics.push(var.name.clone());
+                "static mut".to_string()
+            } else {
+                "static".to_string()
+            };
+            stmt.insert_str(0, if exported { "pub " } else { self.vis() });
+            stmt.push(' ');
+            stmt.push_str(&var.name);
+
+            let ty = match (&var.ty, &var.init) {
+                // int a[] = {1, 2, 3};
+                (&Type::Array(ref inner, None), &Some(Expr { kind: ExprKind::InitList(ref values), .. })) => {
+                    format!("[{}; {}]", self.rust_type(inner), values.len())
+                }
+                _ => self.rust_type(&var.ty),
+            };
+            if ty != "_" {
+                stmt.push_str(": ");
+                stmt.push_str(&ty);
+            }
+
+            if let Some(ref init) = var.init {
+                stmt.push_str(" = ");
+                self.defer_updates = defers_updates(init);
+                stmt.push_str(&self.gen_expr(init));
+                self.defer_updates = false;
+            } else if let Some(value) = self.default_value(&var.ty).filter(|_| var.default_init) {
+                stmt.push_str(" = ");
+                stmt.push_str(&value);
+            }
+            stmt.push(';');
+            self.line(&stmt);
+            self.flush_pending();
+        }
+    }
+
+
+    // struct variable declaration
+    fn gen_record_var(&mut self, var: &VarDecl, record: &Record) {
+        if record.kind == RecordKind::Union {
+            self.doc(UNION_DECL);
+            let mut stmt = if self.mutable(var.is_mut) { "let mut " } else { "let " }.to_string();
+            stmt.push_str(&var.name);
+            if let Some(ref init) = var.init {
+                stmt.push_str(" = ");
+                stmt.push_str(&self.gen_expr(init));
+            }
+            stmt.push(';');
+            self.line(&stmt);
+            return;
+        }
+
+        let values: Vec<String> = match var.init {
+            Some(Expr { kind: ExprKind::InitList(ref values), .. }) => {
+                values.iter().map(|v| self.gen_expr(v)).collect()
+            }
+            // A a = A(); or no initializer at all
+            Some(Expr { kind: ExprKind::Call(..), .. }) | None => Vec::new(),
+            Some(ref init) => {
+                let stmt = format!("let {}{}: {} = {};",
+                                   self.mut_prefix(var.is_mut),
+                                   var.name,
+                                   record.name,
+                                   self.gen_expr(init));
+                self.line(&stmt);
+                return;
+            }
+        };
+        self.doc(STRUCT_INIT);
+        self.line(&format!("let {}{} = {} {{", self.mut_prefix(var.is_mut), var.name, record.name));
+        self.indent += 1;
+        for (i, field) in record.fields.iter().enumerate() {
+            let value = match values.get(i) {
+                Some(v) => v.clone(),
+                None => self.default_value(&field.ty).unwrap_or_else(|| "_".to_string()),
+            };
+            self.line(&format!("{}: {},", field.name, value));
+        }
+        self.indent -= 1;
+        self.line("};");
+    }
+
+
+    /**
+     * gen_function:
+     * generates a function, or a method when modifier is given
+     */
+    fn gen_function(&mut self, func: &Function, modifier: Option<Access>) {
+        let is_main = func.name == "main" && modifier.is_none();
+        let mut head = String::new();
+        if modifier == Some(Access::Public) {
+            head.push_str("pub ");
+        } else if modifier.is_none() && !is_main {
+            head.push_str(if self.is_exported(&func.name) { "pub " } else { self.vis() });
+        }
+        head.push_str("fn ");
+        head.push_str(&func.name);
+        head.push('(');
+
+        let mut args: Vec<String> = Vec::new();
+        if modifier.is_some() {
+            //first argument of method must be self, for sefety we consider reference/borrow
+            args.push("&self".to_string());
+        }
+        // rust does not have arguments or return type for main
+        if !is_main {
+            for param in &func.params {
+                args.push(format!("{}{}: {}", self.mut_prefix(param.is_mut), param.name, self.rust_type(&param.ty)));
+            }
+        }
+        head.push_str(&args.join(", "));
+        head.push(')');
+        if func.variadic {
+            self.line(&format!("//variadic arguments of {} need to be handled manually", func.name));
+        }
+        if !is_main && func.ret != Type::Void {
+            head.push_str(" -> ");
+            head.push_str(&self.rust_type(&func.ret));
+        }
+        head.push_str(" {");
+        self.line(&head);
+        self.indent += 1;
+
+        // declare argc and argv inside main, if required
+        if is_main && !func.params.is_empty() {
+            self.mutability_doc();
+            let argc = self.mut_prefix(func.params[0].is_mut);
+            let argv = self.mut_prefix(func.params.get(1).is_some_and(|p| p.is_mut));
+            self.line(&format!("let {}argv: Vec<_> = std::env::args().collect();", argv));
+            self.line(&format!("let {}argc = argv.len();", argc));
+        }
+
+        self.in_main = is_main;
+        self.in_block_stmnt = true;
+        self.names = func.params.iter().map(|p| p.name.clone()).collect();
+        for stmt in func.body.iter().flatten() {
+            stmt.names(&mut self.names);
+        }
+        if let Some(ref body) = func.body {
+            self.gen_body(body, true);
+        }
+        self.in_block_stmnt = false;
+        self.in_main = false;
+
+        self.indent -= 1;
+        self.line("}");
+    }
+
+
+    /**
+     * gen_macro:
+     * function-like macros with known types become inline functions,
+     * the others macro_rules! taking each parameter as an expression
+     */
+    fn gen_macro(&mut self, def: &MacroDef) {
+        self.names = def.params.clone();
+        match def.body {
+            MacroBody::Expr(ref body) => body.names(&mut self.names),
+            MacroBody::Stmt(ref body) => body.names(&mut self.names),
+        }
+        if let Some((ref types, ref ret)) = def.signature {
+            let params: Vec<String> = def.params
+                .iter()
+                .zip(types)
+                .map(|(p, ty)| format!("{}: {}", p, self.rust_type(ty)))
+                .collect();
+            self.line("#[inline]");
+            let head = format!("{}fn {}({}) -> {} {{", self.vis(), def.name, params.join(", "), self.rust_type(ret));
+            self.line(&head);
+            self.indent += 1;
+            if let MacroBody::Expr(ref body) = def.body {
+                let body = self.gen_expr(body);
+                self.line(&body);
+            }
+            self.indent -= 1;
+            self.line("}");
+            return;
+        }
+        self.macro_params = def.params.clone();
+        let params: Vec<String> = def.params.iter().map(|p| format!("${}:expr", p)).collect();
+        self.line(&format!("macro_rules! {} {{", def.name));
+        self.indent += 1;
+        match def.body {
+            MacroBody::Expr(ref body) => {
+                let body = self.gen_expr(body);
+                self.line(&format!("({}) => {{ {} }};", params.join(", "), body));
+            }
+            // do { ... } while (0) is a block
+            MacroBody::Stmt(ref stmt) => {
+                self.line(&format!("({}) => {{{{", params.join(", ")));
+                self.in_block_stmnt = true;
+                match stmt.kind {
+                    StmtKind::DoWhile(ref body, _) => self.gen_inner(body),
+                    _ => self.gen_inner(stmt),
+                }
+                self.in_block_stmnt = false;
+                self.line("}};");
+            }
+        }
+        self.indent -= 1;
+        self.line("}");
+        self.macro_params.clear();
+        self.macros.push(def.name.clone());
+    }
+
+
+    /**
+     * gen_body:
+     * generates a statement list, tail marks the function body
+     * whose last return becomes the shorthand notation
+     */
+    fn gen_body(&mut self, stmts: &[Stmt], tail: bool) {
+        let last = stmts.iter()
+            .rposition(|s| !matches!(s.kind, StmtKind::Comment(_) | StmtKind::Empty));
+        for (i, stmt) in stmts.iter().enumerate() {
+            match stmt.kind {
+                StmtKind::Return(Some(ref value)) if tail && Some(i) == last &&
+                                                     !self.in_main => {
+                    self.doc(NO_RETURN_TAIL);
+                    let value = self.gen_expr(value);
+                    self.line(&value);
+                }
+                _ => self.gen_stmt(stmt),
+            }
+        }
+    }
+
+    // body of a compound statement, without the braces
+    fn gen_inner(&mut self, stmt: &Stmt) {
+        self.indent += 1;
+        match stmt.kind {
+            StmtKind::Block(ref stmts) => self.gen_body(stmts, false),
+            _ => self.gen_stmt(stmt),
+        }
+        self.indent -= 1;
+    }
+
+
+    fn gen_stmt(&mut self, stmt: &Stmt) {
+        match stmt.kind {
+            StmtKind::Decl(ref decl) => self.gen_decl(decl),
+            StmtKind::Expr(ref expr) => self.gen_expr_stmt(expr),
+            StmtKind::Block(ref stmts) => {
+                self.line("{");
+                self.indent += 1;
+                self.gen_body(stmts, false);
+                self.indent -= 1;
+                self.line("}");
+            }
+            StmtKind::If(..) => self.gen_if(stmt, ""),
+            StmtKind::While(ref cond, ref body) => {
+                let was_in_switch = self.switch_label.take();
+                if is_always_true(cond) {
+                    self.line("loop {");
+                } else {
+                    let cond = self.gen_expr(cond);
+                    self.line(&format!("while {} {{", cond));
+                }
+                self.gen_inner(body);
+                self.line("}");
+                self.switch_label = was_in_switch;
+            }
+            StmtKind::DoWhile(ref body, ref cond) => self.gen_dowhile(body, cond),
+            StmtKind::For(..) => self.gen_for(stmt),
+            StmtKind::Switch(ref cond, ref cases) => self.gen_switch(cond, cases),
+            StmtKind::Break => {
+                match self.switch_label.clone() {
+                    Some(label) => self.line(&format!("break {};", label)),
+                    None => self.line("break;"),
+                }
+            }
+            StmtKind::Continue => self.line("continue;"),
+            StmtKind::Return(ref value) => {
+                self.doc(NO_RETURN);
+                let value = value.as_ref().map(|v| self.gen_expr(v));
+                match value {
+                    Some(v) => {
+                        if self.in_main {
+                            self.line(&format!("std::process::exit({});", v));
+                        } else {
+                            self.line(&format!("return {};", v));
+                        }
+                    }
+                    None => self.line("return;"),
+                }
+            }
+            StmtKind::Comment(ref text) => self.line(text),
+            StmtKind::Empty => {}
+            StmtKind::Verbatim(ref text) => self.manual(text, stmt.pos),
+        }
+        self.flush_pending();
+    }
+
+
+    // prefix is "else " for else-if chains
+    fn gen_if(&mut self, stmt: &Stmt, prefix: &str) {
+        if let StmtKind::If(ref cond, ref then, ref otherwise) = stmt.kind {
+            let cond = self.gen_expr(cond);
+            self.line(&format!("{}if {} {{", prefix, cond));
+            self.gen_inner(then);
+            match *otherwise {
+                Some(ref other) => {
+                    match other.kind {
+                        StmtKind::If(..) => self.gen_if(other, "} else "),
+                        _ => {
+                            self.line("} else {");
+                            self.gen_inner(other);
+                            self.line("}");
+                        }
+                    }
+                }
+                None => self.line("}"),
+            }
+        }
+    }
+
+
+    /**
+     * gen_dowhile:
+     * the condition is checked at the end of a `loop`. When the body
+     * uses `continue` the check moves to the top, guarded so that
+     * it is skipped on the first iteration
+     */
+    fn gen_dowhile(&mut self, body: &Stmt, cond: &Expr) {
+        let was_in_switch = self.switch_label.take();
+        if is_always_true(cond) {
+            self.line("loop {");
+            self.gen_inner(body);
+        } else if body.has_continue() {
+            let cond = self.gen_expr(cond);
+            let started = self.fresh("started");
+            self.line(&format!("let mut {} = false;", started));
+            self.line("loop {");
+            self.line(&format!("    if {} && !({}) {{", started, cond));
+            self.line("        break;");
+            self.line("    }");
+            self.line(&format!("    {} = true;", started));
+            self.gen_inner(body);
+        } else {
+            self.line("loop {");
+            self.gen_inner(body);
+            let cond = self.gen_expr(cond);
+            self.line(&format!("    if !({}) {{", cond));
+            self.line("        break;");
+            self.line("    }");
+        }
+        self.line("}");
+        self.switch_label = was_in_switch;
+    }
+
+
+    /**
+     * gen_for:
+     * counting loops become `for i in a..b`, other loops become while
+     * loops with the update at the end of the body. When the body uses
+     * `continue` the update is guarded at the top of a `loop` instead,
+     * so that it still runs. Identify infinite loops and replace for with loop{}
+     */
+    fn gen_for(&mut self, stmt: &Stmt) {
+        if let StmtKind::For(ref init, ref cond, ref update, ref body) = stmt.kind {
+            let was_in_switch = self.switch_label.take();
+            if let Some(range) = self.counting_range(stmt) {
+                self.line(&format!("for {} {{", range));
+                self.gen_inner(body);
+                self.line("}");
+                self.switch_label = was_in_switch;
+                return;
+            }
+            // a variable the loop declares goes out of scope with it
+            let scoped = matches!(*init, Some(ref init) if matches!(init.kind, StmtKind::Decl(_)));
+            if scoped {
+                self.line("{");
+                self.indent += 1;
+            }
+            if let Some(ref init) = *init {
+                self.gen_stmt(init);
+            }
+            match *update {
+                Some(ref update) if body.has_continue() => {
+                    let started = self.fresh("started");
+                    self.line(&format!("let mut {} = false;", started));
+                    self.line("loop {");
+                    self.indent += 1;
+                    self.line(&format!("if {} {{", started));
+                    self.indent += 1;
+                    self.gen_expr_stmt(update);
+                    self.indent -= 1;
+                    self.line("}");
+                    self.line(&format!("{} = true;", started));
+                    if let Some(ref cond) = *cond {
+                        let cond = self.gen_expr(cond);
+                        self.line(&format!("if !({}) {{", cond));
+                        self.line("    break;");
+                        self.line("}");
+                    }
+                    self.indent -= 1;
+                    self.gen_inner(body);
+                }
+                _ => {
+                    match *cond {
+                        Some(ref cond) => {
+                            let cond = self.gen_expr(cond);
+                            self.line(&format!("while {} {{", cond));
+                        }
+                        None => self.line("loop {"),
+                    }
+                    self.gen_inner(body);
+                    if let Some(ref update) = *update {
+                        self.indent += 1;
+                        self.gen_expr_stmt(update);
+                        self.indent -= 1;
+                    }
+                }
+            }
+            self.line("}");
+            if scoped {
+                self.indent -= 1;
+                self.line("}");
+            }
+            self.switch_label = was_in_switch;
+        }
+    }
+
+    /**
+     * counting_range:
+     * returns `i in a..b` for a loop of the form
+     * for (int i = a; i < b; i++) whose body leaves i and b untouched
+     */
+    fn counting_range(&self, stmt: &Stmt) -> Option<String> {
+        let (init, cond, update, body) = match stmt.kind {
+            StmtKind::For(Some(ref init), Some(ref cond), Some(ref update), ref body) => {
+                (init, cond, update, body)
+            }
+            _ => return None,
+        };
+        let var = match init.kind {
+            StmtKind::Decl(Decl::Var(ref vars)) if vars.len() == 1 => &vars[0],
+            _ => return None,
+        };
+        let start = match var.init {
+            Some(ref start) if !var.ty.is_pointer() => start,
+            _ => return None,
+        };
+        let is_var = |e: &Expr| match e.kind {
+            ExprKind::Ident(ref n) => *n == var.name,
+            _ => false,
+        };
+        let (inclusive, bound) = match cond.kind {
+            ExprKind::Binary(BinOp::Lt, ref l, ref r) if is_var(l) => (false, r),
+            ExprKind::Binary(BinOp::Le, ref l, ref r) if is_var(l) => (true, r),
+            _ => return None,
+        };
+        let steps_by_one = match update.kind {
+            ExprKind::Unary(UnOp::PostInc, ref e) | ExprKind::Unary(UnOp::PreInc, ref e) => is_var(e),
+            ExprKind::Assign(Some(BinOp::Add), ref e, ref step) => {
+                is_var(e) && matches!(step.kind, ExprKind::IntLit(ref v) if v.value() == Some(1))
+            }
+            _ => false,
+        };
+        if !steps_by_one || body.any_expr(&|e: &Expr| e.modifies(&var.name)) {
+            return None;
+        }
+        // the bound is evaluated once by a range but on every iteration in C
+        let unstable = bound.any(&|e: &Expr| match e.kind {
+            ExprKind::Ident(ref n) => *n == var.name || body.any_expr(&|b: &Expr| b.modifies(n)),
+            ExprKind::Call(..) | ExprKind::Assign(..) | ExprKind::Unary(UnOp::Deref, _) => true,
+            ExprKind::Unary(op, _) => op != UnOp::Neg && op != UnOp::Not && op != UnOp::BitNot,
+            _ => false,
+        });
+        if unstable || body_has_verbatim(body) {
+            return None;
+        }
+        let mut pending: Vec<String> = Vec::new();
+        let start = self.gen_operand(start, PREC_OR, &mut pending);
+        let bound = self.gen_operand(bound, PREC_OR, &mut pending);
+        let range = if inclusive { "..=" } else { ".." };
+        Some(format!("{} in {}{}{}", var.name, start, range, bound))
+    }
+
+
+    /**
+     * gen_switch:
+     * switch becomes match, stacked labels become `1 | 2` patterns and
+     * the default arm goes last. A case falling through repeats the
+     * following cases in its arm. A `break` anywhere but at the end of
+     * an arm exits a labelled block around the match
+     */
+    fn gen_switch(&mut self, cond: &Expr, cases: &[Case]) {
+        let mut arms: Vec<(String, bool, Vec<Stmt>)> = Vec::new();
+        let mut default: Option<(String, bool, Vec<Stmt>)> = None;
+        for (i, case) in cases.iter().enumerate() {
+            let mut body = case.body.clone();
+            let mut next = i + 1;
+            while !ends_in_jump(&body) && next < cases.len() {
+                body.extend(cases[next].body.iter().cloned());
+                next += 1;
+            }
+            let falls = next > i + 1;
+            strip_tail_break(&mut body);
+            if case.is_default {
+                default = Some(("_".to_string(), falls, body));
+            } else {
+                let labels: Vec<String> = case.labels.iter().map(|l| self.gen_label(l)).collect();
+                arms.push((labels.join(" | "), falls, body));
+            }
+        }
+        //look whether default case is handled for exaustive search
+        arms.push(default.unwrap_or(("_".to_string(), false, Vec::new())));
+
+        let labelled = arms.iter().any(|arm| arm.2.iter().any(|s| s.has_break()));
+        self.switch_depth += 1;
+        let label = if self.switch_depth == 1 {
+            "'switch".to_string()
+        } else {
+            format!("'switch_{}", self.switch_depth)
+        };
+        let was_in_switch = self.switch_label.replace(label.clone());
+        if labelled {
+            self.line(&format!("{}: {{", label));
+            self.indent += 1;
+        }
+        let cond = self.gen_expr(cond);
+        self.line(&format!("match {} {{", cond));
+        self.indent += 1;
+        for (pattern, falls, body) in arms {
+            if falls {
+                self.doc(FALLTHROUGH);
+            }
+            if body.is_empty() {
+                self.line(&format!("{} => {{}}", pattern));
+                continue;
+            }
+            self.line(&format!("{} => {{", pattern));
+            self.indent += 1;
+            for stmt in &body {
+                match stmt.kind {
+                    StmtKind::Block(ref stmts) => self.gen_body(stmts, false),
+                    _ => self.gen_stmt(stmt),
+                }
+            }
+            self.indent -= 1;
+            self.line("}");
+        }
+        self.indent -= 1;
+        self.line("}");
+        if labelled {
+            self.indent -= 1;
+            self.line("}");
+        }
+        self.switch_label = was_in_switch;
+        self.switch_depth -= 1;
+    }
+
+    // enum constants are qualified, a bare name would bind anything
+    fn gen_label(&mut self, label: &Expr) -> String {
+        if let ExprKind::Ident(ref name) = label.kind {
+            if let Some((_, owner)) = self.enumerators.iter().find(|e| &e.0 == name) {
+                return format!("{}::{}", owner, name);
+            }
+        }
+        self.gen_expr(label)
+    }
+
+
+    /**
+     * gen_expr_stmt:
+     * expression statements, increments and comma separated
+     * assignments become statements of their own
+     */
+    fn gen_expr_stmt(&mut self, expr: &Expr) {
+        match expr.kind {
+            // chained assignment `a = b = c` runs the inner one first
+            ExprKind::Assign(op, ref target, ref value) => {
+                if let ExprKind::Assign(_, ref inner, _) = value.kind {
+                    self.gen_expr_stmt(value);
+                    let chained = Expr::new(ExprKind::Assign(op, target.clone(), inner.clone()),
+                                            expr.pos);
+                    self.gen_expr_stmt(&chained);
+                    return;
+                }
+            }
+            ExprKind::Comma(ref list) => {
+                for e in list {
+                    self.gen_expr_stmt(e);
+                }
+                return;
+            }
+            ExprKind::Unary(op, ref operand) => {
+                let update = match op {
+                    UnOp::PreInc | UnOp::PostInc => Some("+="),
+                    UnOp::PreDec | UnOp::PostDec => Some("-="),
+                    _ => None,
+                };
+                if let Some(update) = update {
+                    let unsafe_place = self.unsafe_place(operand);
+                    self.in_unsafe.set(unsafe_place);
+                    let operand = self.gen_expr(operand);
+                    self.in_unsafe.set(false);
+                    if unsafe_place {
+                        self.line(&format!("unsafe {{ {} {} 1 }};", operand, update));
+                    } else {
+                        self.line(&format!("{} {} 1;", operand, update));
+                    }
+                    self.flush_pending();
+                    return;
+                }
+            }
+            _ => {}
+        }
+        //check if overloaded operators is in effect like << >>
+        if is_stream_expr(expr) {
+            if let Some(text) = self.gen_stream(expr) {
+                self.line(&format!("{};", text));
+                return;
+            }
+            let text = self.gen_expr(expr);
+            self.pending.clear();
+            self.manual(&format!("{};", text), expr.pos);
+            return;
+        }
+        self.defer_updates = defers_updates(expr);
+        let text = self.gen_expr(expr);
+        self.defer_updates = false;
+        self.line(&format!("{};", text));
+        self.flush_pending();
+    }
+
+
+    /**
+     * gen_expr:
+     * generates an expression, postfix increments are queued and
+     * emitted after the enclosing statement when it defers updates,
+     * otherwise they become blocks yielding the old value
+     */
+    fn gen_expr(&mut self, expr: &Expr) -> String {
+        let mut pending: Vec<String> = Vec::new();
+        let text = self.gen_expr_mut(expr, &mut pending);
+        self.pending.append(&mut pending);
+        text
+    }
+
+    fn gen_expr_mut(&self, expr: &Expr, pending: &mut Vec<String>) -> String {
+        // the whole place goes in the block, `unsafe { *p }.x` would move *p
+        if self.unsafe_place(expr) && !self.in_unsafe.get() {
+            self.in_unsafe.set(true);
+            let place = self.gen_expr_mut(expr, pending);
+            self.in_unsafe.set(false);
+            return format!("unsafe {{ {} }}", place);
+        }
+        match expr.kind {
+            ExprKind::Ident(ref name) if self.macro_params.contains(name) => format!("${}", name),
+            ExprKind::Ident(ref name) => {
+                match self.std_name(name, None) {
+                    Some(item) => item.rust.to_string(),
+                    None => name.clone(),
+                }
+            }
+            ExprKind::IntLit(ref v) => int_literal(v),
+            ExprKind::FloatLit(ref v) => float_literal(v),
+            ExprKind::CharLit(ref v) => char_literal(v),
+            ExprKind::StrLit(ref v) => format!("\"{}\"", escape(v, '"')),
+            ExprKind::ByteStrLit(ref v) => format!("b\"{}\"", escape_bytes(v)),
+            ExprKind::BoolLit(b) => b.to_string(),
+            ExprKind::Null => self.null().to_string(),
+            ExprKind::This => "self".to_string(),
+            ExprKind::Unary(op, ref operand) => {
+                match op {
+                    UnOp::Plus => self.gen_operand(operand, 0, pending),
+                    UnOp::PreInc | UnOp::PreDec | UnOp::PostInc | UnOp::PostDec => {
+                        let update = if op == UnOp::PreInc || op == UnOp::PostInc { "+=" } else { "-=" };
+                        let post = op == UnOp::PostInc || op == UnOp::PostDec;
+                        // an operand with side effects is evaluated once through a reference
+                        let (bind, place) = if has_side_effects(operand) {
+                            let operand = self.gen_operand(operand, PREC_POSTFIX, pending);
+                            let name = self.fresh("place");
+                            (format!("let {} = &mut {}; ", name, operand), format!("*{}", name))
+                        } else {
+                            (String::new(), self.gen_operand(operand, PREC_POSTFIX, pending))
+                        };
+                        if !post {
+                            format!("{{ {}{} {} 1; {} }}", bind, place, update, place)
+                        } else if self.defer_updates && bind.is_empty() {
+                            if self.unsafe_place(operand) {
+                                pending.push(format!("unsafe {{ {} {} 1 }};", place, update));
+                            } else {
+                                pending.push(format!("{} {} 1;", place, update));
+                            }
+                            place
+                        } else {
+                            // the old value, C sequences the update before the next statement
+                            let tmp = self.fresh("tmp");
+                            format!("{{ {}let {} = {}; {} {} 1; {} }}", bind, tmp, place, place, update, tmp)
+                        }
+                    }
+                    _ => {
+                        let prefix = match op {
+                            UnOp::Neg => "-",
+                            UnOp::Not => "!",
+                            // Rust spells bitwise not on integers as `!`
+                            UnOp::BitNot => "!",
+                            UnOp::AddrOf if self.mutability == Mutability::Strict => "&",
+                            UnOp::AddrOf => "&mut ",
+                            _ => "*",
+                        };
+                        let operand = self.gen_operand(operand, PREC_UNARY, pending);
+                        if op == UnOp::AddrOf {
+                            return format!("{}{} as {} _", prefix, operand, self.pointer());
+                        }
+                        format!("{}{}", prefix, operand)
+                    }
+                }
+            }
+            ExprKind::Binary(op, ref l, ref r) => {
+                let prec = binop_prec(op);
+                // comparisons do not chain in Rust, both sides need parentheses
+                let mut left = if prec == PREC_COMPARE { prec + 1 } else { prec };
+                // `x as i32 < y` would start generic arguments
+                if matches!(op, BinOp::Lt | BinOp::Shl) && ends_in_cast(l) {
+                    left = left.max(PREC_CAST + 1);
+                }
+                format!("{} {} {}",
+                        self.gen_operand(l, left, pending),
+                        op.as_str(),
+                        self.gen_operand(r, prec + 1, pending))
+            }
+            ExprKind::Assign(op, ref l, ref r) => {
+                let op = match op {
+                    Some(op) => format!("{}=", op.as_str()),
+                    None => "=".to_string(),
+                };
+                // `*p = 1`
+                let place = if let ExprKind::Unary(UnOp::Deref, _) = l.kind { PREC_UNARY } else { PREC_POSTFIX };
+                format!("{} {} {}",
+                        self.gen_operand(l, place, pending),
+                        op,
+                        self.gen_operand(r, PREC_ASSIGN + 1, pending))
+            }
+            ExprKind::Cond(ref cond, ref then, ref otherwise) => {
+                let cond = self.gen_operand(cond, PREC_COND + 1, pending);
+                let then = self.gen_branch(then);
+                // nested ternaries in the else branch become an else-if chain
+                let otherwise = match otherwise.kind {
+                    ExprKind::Cond(..) => self.gen_expr_mut(otherwise, pending),
+                    _ => format!("{{ {} }}", self.gen_branch(otherwise)),
+                };
+                format!("if {} {{ {} }} else {}", cond, then, otherwise)
+            }
+            ExprKind::Cast(ref ty, ref operand) => {
+                // constants get the type of their uses once all agree
+                if self.has_const_type(operand, ty) {
+                    return self.gen_expr_mut(operand, pending);
+                }
+                // a cast would make Rust infer the target type for a literal,
+                // which may not hold its value
+                let operand = match operand.kind {
+                    ExprKind::IntLit(ref lit) if lit.suffix.is_empty() => {
+                        format!("{}{}", int_literal(lit), c_literal_type(lit))
+                    }
+                    ExprKind::Unary(UnOp::Neg, ref e) => match e.kind {
+                        ExprKind::IntLit(ref lit) if lit.suffix.is_empty() => {
+                            format!("-{}{}", int_literal(lit), c_literal_type(lit))
+                        }
+                        _ => self.gen_operand(operand, PREC_CAST, pending),
+                    },
+                    _ => self.gen_operand(operand, PREC_CAST, pending),
+                };
+                match *ty {
+                    // casting to void only discards the value
+                    Type::Void => operand,
+                    Type::Pointer(_) => format!("{} as {}", operand, self.rust_type(ty)),
+                    _ => format!("{} as {}", operand, self.rust_type(ty)),
+                }
+            }
+            ExprKind::Call(ref func, ref args) => {
+                if let ExprKind::Ident(ref name) = func.kind {
+                    if let Some(item) = self.std_name(name, Some(args.len())) {
+                        if !item.rust.contains("$*") {
+                            return self.gen_std_call(item.rust, args, pending);
+                        }
+                        if let Some(text) = self.gen_printf(item.rust, args, pending) {
+                            return text;
+                        }
+                    }
+                }
+                let args: Vec<String> = args.iter().map(|a| self.gen_operand(a, 0, pending)).collect();
+                let bang = match func.kind {
+                    ExprKind::Ident(ref name) if self.macros.contains(name) => "!",
+                    _ => "",
+                };
+                format!("{}{}({})", self.gen_operand(func, PREC_POSTFIX, pending), bang, args.join(", "))
+            }
+            ExprKind::Index(ref base, ref index) => {
+                format!("{}[{}]",
+                        self.gen_operand(base, PREC_POSTFIX, pending),
+                        self.gen_operand(index, 0, pending))
+            }
+            ExprKind::Member(ref base, ref member) => {
+                format!("{}.{}", self.gen_operand(base, PREC_POSTFIX, pending), member)
+            }
+            ExprKind::SizeofType(ref ty) => format!("std::mem::size_of::<{}>()", self.rust_type(ty)),
+            ExprKind::SizeofExpr(ref operand) => {
+                format!("std::mem::size_of_val(&{})", self.gen_operand(operand, PREC_UNARY, pending))
+            }
+            ExprKind::InitList(ref values) => {
+                let values: Vec<String> = values.iter().map(|v| self.gen_operand(v, 0, pending)).collect();
+                format!("[{}]", values.join(", "))
+            }
+            ExprKind::Comma(ref list) => {
+                let list: Vec<String> = list.iter().map(|e| self.gen_operand(e, 0, pending)).collect();
+                format!("{{ {} }}", list.join("; "))
+            }
+        }
+    }
+
+    // only the taken branch of a conditional may run its increments
+    fn gen_branch(&self, expr: &Expr) -> String {
+        let mut pending: Vec<String> = Vec::new();
+        let text = self.gen_operand(expr, 0, &mut pending);
+        if pending.is_empty() {
+            text
+        } else {
+            let tmp = self.fresh("tmp");
+            format!("let {} = {}; {} {}", tmp, text, pending.join(" "), tmp)
+        }
+    }
+
+    /**
+     * gen_operand:
+     * generates a subexpression, parenthesized when it binds looser
+     * than min in Rust. Assignments are values in C but not in Rust,
+     * a nested one becomes a block yielding the assigned variable
+     */
+    fn gen_operand(&self, expr: &Expr, min: u8, pending: &mut Vec<String>) -> String {
+        let text = self.gen_expr_mut(expr, pending);
+        if let ExprKind::Assign(_, ref target, _) = expr.kind {
+            if min > PREC_ASSIGN {
+                return format!("{{ {}; {} }}", text, self.gen_expr_mut(target, &mut Vec::new()));
+            }
+        }
+        if rust_prec(expr) < min {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+
+    /**
+     * gen_struct:
+     * structs and classes become Rust structs, methods go to an impl block
+     */
+    fn gen_struct(&mut self, record: &Record) {
+        self.line(&format!("{}struct {} {{", self.vis(), record.name));
+        self.indent += 1;
+        for field in &record.fields {
+            // struct members are public by default, class members private
+            let exported = self.public && record.kind != RecordKind::Class && field.access == Access::Default;
+            let modifier = if field.access == Access::Public || exported { "pub " } else { "" };
+            let line = format!("{}{}: {},", modifier, field.name, self.rust_type(&field.ty));
+            self.line(&line);
+        }
+        self.indent -= 1;
+        self.line("}");
+
+        if record.kind != RecordKind::Class && record.methods.is_empty() && record.ctors.is_empty() {
+            return;
+        }
+        self.out.push_str("\n\n/**Method declarations are wrapped inside the impl block \
+                           \n * Which implements the corresponding structure\
+                           \n **/\n");
+        self.line(&format!("impl {} {{", record.name));
+        self.indent += 1;
+        for ctor in &record.ctors {
+            self.doc(CONSTRUCTOR);
+            self.out.push_str(&format!(" {} \n **/\n", ctor));
+        }
+        for method in &record.methods {
+            if method.func.body.is_some() {
+                self.gen_function(&method.func, Some(method.access));
+            }
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+
+
+    //tagged unions become enums with one variant per member
+    fn gen_union(&mut self, record: &Record) {
+        self.doc(UNION);
+        self.line(&format!("{}enum {} {{", self.vis(), record.name));
+        self.indent += 1;
+        for field in &record.fields {
+            let line = format!("{}({}),", field.name, self.rust_type(&field.ty));
+            self.line(&line);
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+
+
+    fn gen_enum(&mut self, def: &EnumDef) {
+        for variant in &def.variants {
+            self.enumerators.push((variant.name.clone(), def.name.clone()));
+        }
+        self.line(&format!("{}enum {} {{", self.vis(), def.name));
+        self.indent += 1;
+        for variant in &def.variants {
+            let line = match variant.value {
+                Some(ref value) => format!("{} = {},", variant.name, self.gen_expr(value)),
+                None => format!("{},", variant.name),
+            };
+            self.line(&line);
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use library::codegen::{generate, Mutability, Unit};
+    use library::lexer;
+    use library::parser::{self, Options};
+    use library::preprocessor::Preprocessor;
+    use std::{env, fs, process};
+
+    // translates C source the way main does, without the file header
+    fn translate(source: &str) -> String {
+        let tokens = lexer::Tokenizer::new(source).tokenize().unwrap();
+        let tokens = Preprocessor::new().run(&tokens).unwrap();
+        let program = parser::init_parser(&tokens, &Options::default());
+        let rust = generate(&program, Mutability::Inferred, &Unit::default());
+        rust[rust.find("*/\n").unwrap() + 3..].to_string()
+    }
+
+    // checks that rustc accepts the translated source
+    fn assert_compiles(rust: &str, name: &str) {
+        let dir = env::temp_dir().join(format!("crust_codegen_{}_{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("lib.rs");
+        fs::write(&file, rust).unwrap();
+        let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let output = process::Command::new(rustc)
+            .args(["--edition", "2021", "--crate-type", "lib", "--emit", "metadata", "-A", "warnings", "--out-dir"])
+            .arg(&dir)
+            .arg(&file)
+            .output()
+            .unwrap();
+        fs::remove_dir_all(&dir).ok();
+        assert!(output.status.success(), "{}\n{}", rust, String::from_utf8_lossy(&output.stderr));
+    }
+
+    #[test]
+    fn test_program() {
+        let rust = translate("#define INC(a) ((a) + 1)\n\
+                              struct point { int x; int y; };\n\
+                              enum color { RED, GREEN = 5, BLUE };\n\
+                              int sum(int n) {\n\
+                              int s = 0; int i;\n\
+                              for (i = 0; i < n; i++) s += i;\n\
+                              if (s > 10) { s = s - 1; } else if (s < 0) { s = 0; } else { s++; }\n\
+                              while (s > 100) s /= 2;\n\
+                              return s + i;\n\
+                              }\n\
+                              int area(struct point p) { int a = p.x * p.y; return INC(a) * 2; }\n");
+        for line in &["fn INC(a: i32) -> i32 {\n    a + 1\n}", "struct point {\n    x: i32,\n    y: i32,\n}",
+                      "enum color {\n    RED,\n    GREEN = 5,\n    BLUE,\n}", "fn sum(n: i32) -> i32 {",
+                      "    let mut i: i32;\n    i = 0;\n    while i < n {\n        s += i;\n        i += 1;\n    }",
+                      "    if s > 10 {\n        s = s - 1;\n    } else if s < 0 {\n        s = 0;\n    } else {\n        s += 1;\n    }",
+                      "// with return statement\n    s + i\n}", "fn area(p: point) -> i32 {", "    INC(a) * 2\n}"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "program");
+    }
+
+    #[test]
+    fn test_precedence() {
+        let rust = translate("void f(int b, int c, int d) {\n\
+                              int a = b + c++ * d;\n\
+                              int e = (a + b) * -c % d;\n\
+                              a = e - (b - d);\n\
+                              }\n");
+        assert!(rust.contains("    let mut a: i32 = b + c * d;\n    c += 1;\n"), "{}", rust);
+        assert!(rust.contains("let e: i32 = (a + b) * -c % d;"), "{}", rust);
+        assert!(rust.contains("a = e - (b - d);"), "{}", rust);
+        assert_compiles(&rust, "precedence");
+    }
+
+    #[test]
+    fn test_for_continue_flag() {
+        let rust = translate("int sum(int n) {\n\
+                              int total = 0;\n\
+                              for (int i = 0; i < n; i += 2) {\n\
+                              int started = i;\n\
+                              if (started % 3 == 0) continue;\n\
+                              total += started;\n\
+                              }\n\
+                              return total;\n\
+                              }\n");
+        assert!(rust.contains("let mut started_1 = false;"), "{}", rust);
+        assert!(rust.contains("if started_1 {"), "{}", rust);
+        assert!(rust.contains("let started: i32 = i;"), "{}", rust);
+        assert_compiles(&rust, "for_continue");
+    }
+
+    #[test]
+    fn test_for_scope() {
+        let rust = translate("int f(int n) {\n\
+                              int i = 5;\n\
+                              for (int i = n; i > 0; i--) { if (i == 2) continue; n++; }\n\
+                              for (int i = 0; i < n; i += 2) n--;\n\
+                              return i;\n\
+                              }\n");
+        assert!(rust.contains("    {\n        let mut i: i32 = n;\n        let mut started = false;\n        loop {"),
+                "{}", rust);
+        assert!(rust.contains("    {\n        let mut i: i32 = 0;\n        while i < n {"), "{}", rust);
+        assert!(rust.contains("        }\n    }\n\n// Crust"), "{}", rust);
+        assert_compiles(&rust, "for_scope");
+    }
+
+    #[test]
+    fn test_counting_for() {
+        let rust = translate("int sum(int n) {\n\
+                              int total = 0;\n\
+                              for (int i = 0; i < n; i++) total += i;\n\
+                              for (int i = 1; i <= n; ++i) { if (i == 2) continue; total += i; }\n\
+                              for (int i = 0; i < n; i++) { n--; }\n\
+                              return total;\n\
+                              }\n");
+        assert!(rust.contains("for i in 0..n {"), "{}", rust);
+        assert!(rust.contains("for i in 1..=n {"), "{}", rust);
+        // the bound changes in the body
+        assert!(rust.contains("while i < n {"), "{}", rust);
+        assert_compiles(&rust, "counting_for");
+    }
+
+    #[test]
+    fn test_dowhile() {
+        let rust = translate("int count(int n) {\n\
+                              int started = 0;\n\
+                              do { n--; if (n % 2) continue; started++; } while (n > 0);\n\
+                              do { n++; } while (n < 3);\n\
+                              do { started--; } while (1);\n\
+                              return started;\n\
+                              }\n");
+        assert!(rust.contains("    let mut started_1 = false;\n    loop {\n        if started_1 && !(n > 0) {"),
+                "{}",
+                rust);
+        assert!(rust.contains("        n += 1;\n        if !(n < 3) {\n            break;\n        }\n"), "{}", rust);
+        assert!(rust.contains("    loop {\n        started -= 1;\n    }"), "{}", rust);
+        assert_compiles(&rust, "dowhile");
+    }
+
+    #[test]
+    fn test_switch() {
+        let rust = translate("enum Color { RED, GREEN, BLUE };\n\
+                              int classify(int n, char c, enum Color color) {\n\
+                              int r = 0;\n\
+                              switch (n) {\n\
+                              case 1: case 2: r = 1; break;\n\
+                              case 3: r = 3;\n\
+                              case 4: r += 4; break;\n\
+                              default: r = -1;\n\
+                              }\n\
+                              switch (c) { case 'a': r += 10; break; }\n\
+                              switch (color) { case RED: r += 100; break; default: break; case BLUE: r += 300; }\n\
+                              while (n > 0) {\n\
+                              switch (n % 4) { case 1: if (r > 10) break; r += 1; break; default: r += 2; }\n\
+                              n--;\n\
+                              }\n\
+                              return r;\n\
+                              }\n");
+        assert!(rust.contains("1 | 2 => {"), "{}", rust);
+        // the fallthrough body is repeated
+        assert!(rust.contains("3 => {\n            r = 3;\n            r += 4;\n        }"), "{}", rust);
+        assert!(rust.contains("b'a' => {"), "{}", rust);
+        assert!(rust.contains("Color::BLUE => {"), "{}", rust);
+        assert!(rust.contains("'switch: {"), "{}", rust);
+        assert!(rust.contains("break 'switch;"), "{}", rust);
+        assert_compiles(&rust, "switch");
+    }
+
+    #[test]
+    fn test_literals() {
+        let rust = translate("void f() {\n\
+                              double a = 1e-3; float b = 3.0f; double h = .5;\n\
+                              int c = 0x1F; int d = 017; long e = 10L; unsigned u = 5u;\n\
+                              int sep = 1'000'000; int k = 100_km;\n\
+                              }\n");
+        for line in &["let a: f64 = 1e-3f64;", "let b: f32 = 3.0f32;", "let h: f64 = 0.5;", "let c: i32 = 0x1F;",
+                      "let d: i32 = 0o17;", "let e: i64 = 10i64;", "let u: u32 = 5u32;",
+                      "let sep: i32 = 1_000_000;", "let k: i32 = 100;"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "literals");
+    }
+
+    #[test]
+    fn test_string_bytes() {
+        let rust = translate("void f() {\n\
+                              g(\"\\xFF\\x01 \\\"end\\\"\");\n\
+                              g(\"caf\\xC3\\xA9 \\x41\\101\" \"\\t\");\n\
+                              char c = '\\xFF';\n\
+                              }\n");
+        // bytes which are not UTF-8 stay bytes instead of becoming chars
+        assert!(rust.contains("g(b\"\\xFF\\x01 \\\"end\\\"\");"), "{}", rust);
+        assert!(rust.contains("g(\"caf\u{e9} AA\\t\");"), "{}", rust);
+        assert!(rust.contains("let c: u8 = b'\\xFF';"), "{}", rust);
+    }
+
+    #[test]
+    fn test_constant_types() {
+        let rust = translate("#define SMALL 300\n#define TINY 30\n#define N 50\n#define IS_BIG (N > 100)\n\
+                              void f() { char c = TINY; char d = SMALL; int x = IS_BIG + 1; }\n");
+        // the first use only narrows a constant whose value fits its type
+        for line in &["const SMALL: i32 = 300;", "const TINY: u8 = 30;", "const IS_BIG: i32 = (N > 100) as i32;",
+                      "let x: i32 = IS_BIG + 1;"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "constant_types");
+    }
+
+    #[test]
+    fn test_macro_conversions() {
+        let rust = translate("#define TWICE(x) 2*x\n#define AREA(w,h) w*h\n#define HALF(n) n/2.0\n\
+                              #define TWICE1(x) ((x) + (x) + 1)\n\
+                              void f(long l, int i) { double d = TWICE(2.5); long a = AREA(l, i); double h = HALF(i);\n\
+                              double t = TWICE1(d) + TWICE1(h); }\n");
+        for line in &["fn TWICE(x: f64) -> f64 {\n    2.0 * x\n}", "fn AREA(w: i64, h: i32) -> i64 {\n    w * h as i64\n}",
+                      "fn HALF(n: i32) -> f64 {\n    n as f64 / 2.0\n}", "($x:expr) => { $x + $x + 1.0 };"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "macro_conversions");
+    }
+
+    #[test]
+    fn test_std_headers() {
+        let rust = translate("#include <iostream>\n#include <map>\n#include <unordered_map>\n#include <vector>\n\
+                              #include <stdio.h>\n#include <stdlib.h>\n\
+                              void f(int n, double d, char c) {\n\
+                              std::vector<int> v; std::map<int, long> m; std::unordered_map<int, int> u;\n\
+                              int a = abs(-5); long b = labs(n);\n\
+                              std::cout << \"n = \" << n << \", {d} \" << d << std::endl;\n\
+                              std::cerr << \"oops\\n\"; std::cout << std::endl;\n\
+                              printf(\"%d %s %5.2f %c %x%%\\n\", n, \"x\", d, c, n); printf(\"%f\", d);\n\
+                              }\n");
+        for line in &["use std::collections::{BTreeMap, HashMap};", "let v: Vec<i32> = Vec::new();",
+                      "let m: BTreeMap<i32, i64> = BTreeMap::new();", "let u: HashMap<i32, i32> = HashMap::new();",
+                      "let a: i32 = i32::abs(-5);", "let b: i64 = i64::abs(n as i64);", "println!(\"n = {}, {{d}} {}\", n, d);",
+                      "eprintln!(\"oops\");", "println!();",
+                      "println!(\"{} {} {:5.2} {} {:x}%\", n, \"x\", d, c as u8 as char, n);",
+                      "print!(\"{:.6}\", d);"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "std_headers");
+    }
+
+    #[test]
+    fn test_scopes() {
+        let rust = translate("double f(long n) {\n\
+                              double x = 1.5;\n\
+                              { int x = 2; long y = x; n = y; }\n\
+                              double w = x + n;\n\
+                              return w;\n\
+                              }\n\
+                              int g() { int x = 3; double z = x; int r = x + (int)z; return r; }\n");
+        // the inner x shadows the outer one until the end of its block,
+        // the x of g is not the one of f
+        for line in &["let y: i64 = x as i64;", "let w: f64 = x + n as f64;", "let z: f64 = x as f64;",
+                      "let r: i32 = x + z as i32;"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "scopes");
+    }
+
+    #[test]
+    fn test_conversions() {
+        let rust = translate("#include <math.h>\n#include <limits.h>\n\
+                              #define N 5\n#define SMALL 300\n#define IS_BIG (N > 100)\n#define WIDE 7\n\
+                              void f(int i, double d) {\n\
+                              long b = 10UL; float x = 1.5L; unsigned u = -1; char c = 300; char s = SMALL;\n\
+                              long a = N; int k = N; long w = WIDE; long v = -WIDE;\n\
+                              _Bool z = 0; _Bool y = i; bool t = 2; z = i + 1;\n\
+                              double p = pow(2, 3); double r = sqrt(i); int q = sqrt(d); long m = INT_MAX;\n\
+                              if (IS_BIG) i = 2;\n\
+                              int big = IS_BIG + 1;\n\
+                              int g[3][4]; g[i][b] = g[0][b + 1]; g[N - 3][1] = 2;\n\
+                              }\n");
+        for line in &["let b: i64 = 10;", "let x: f32 = 1.5;", "let u: u32 = -1i32 as u32;", "let c: u8 = 300i32 as u8;",
+                      "let s: u8 = SMALL as u8;", "let a: i64 = N as i64;", "let k: i32 = N;",
+                      // a constant only used as a long becomes one
+                      "const WIDE: i64 = 7;", "let w: i64 = WIDE;", "let v: i64 = -WIDE;",
+                      "let mut z: bool = false;", "let y: bool = i != 0;", "let t: bool = true;", "z = i + 1 != 0;",
+                      "let p: f64 = f64::powf(2.0, 3.0);", "let r: f64 = f64::sqrt(i as f64);",
+                      "let q: i32 = f64::sqrt(d) as i32;", "let m: i64 = i32::MAX as i64;", "if IS_BIG != 0 {",
+                      "let big: i32 = IS_BIG + 1;", "g[i as usize][b as usize] = g[0][(b + 1) as usize];",
+                      "g[(N - 3) as usize][1] = 2;"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "conversions");
+    }
+
+    #[test]
+    fn test_statics() {
+        let rust = translate("int total; int counts[4]; int limit = 10;\n\
+                              void add(int c, int *p) {\n\
+                              total += c; counts[c]++;\n\
+                              if (total > limit) total = 0;\n\
+                              int x = total++ + counts[c];\n\
+                              p = &total;\n\
+                              }\n\
+                              int peek() { int t = total; return t; }\n");
+        for line in &["static mut total: i32 = 0i32;", "static limit: i32 = 10;", "unsafe { total += c };",
+                      "unsafe { counts[c as usize] += 1 };", "if unsafe { total } > limit {", "unsafe { total = 0 };",
+                      "let x: i32 = unsafe { total } + unsafe { counts[c as usize] };\n    unsafe { total += 1 };",
+                      "p = unsafe { &mut total as *mut _ };", "let t: i32 = unsafe { total };"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "statics");
+    }
+
+    #[test]
+    fn test_pointer_conditions() {
+        let rust = translate("struct node { int v; struct node *next; };\n\
+                              void swap(int *a, int *b) { int t = *a; *a = *b; *b = t; }\n\
+                              void count(struct node *n, int *c) { while (n) { (*c)++; n = n->next; } }\n\
+                              void f(int x, double d, int a[]) {\n\
+                              int *p = NULL; int *q = 0;\n\
+                              if (!p) p = &x;\n\
+                              if (p && *p > 0) *p = 3;\n\
+                              while (x) x--;\n\
+                              if (!x || d) x = 1;\n\
+                              if (a) x = a[x];\n\
+                              struct node first; first.v = 1; first.next = NULL;\n\
+                              count(&first, &x);\n\
+                              }\n");
+        for line in &["next: *mut node,", "fn swap(a: *mut i32, b: *mut i32) {", "let t: i32 = unsafe { *a };",
+                      "while !n.is_null() {", "unsafe { *c += 1 };", "n = unsafe { (*n).next };",
+                      "let mut p: *mut i32 = std::ptr::null_mut();", "let q: *mut i32 = std::ptr::null_mut();",
+                      "if p.is_null() {", "p = &mut x as *mut _;", "if !p.is_null() && unsafe { *p } > 0 {",
+                      "while x != 0 {", "if x == 0 || d != 0.0 {", "if !a.is_null() {",
+                      "x = unsafe { *a.add(x as usize) };", "count(&mut first as *mut _, &mut x as *mut _);"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "pointer_conditions");
+    }
+
+    #[test]
+    fn test_c_strings() {
+        let rust = translate("#include <stdio.h>\n\
+                              const char *greeting = \"hello\";\n\
+                              int sum(const int a[], int n) { int s = 0; for (int i = 0; i < n; i++) s += a[i]; return s; }\n\
+                              void show(char *name) {\n\
+                              const char *s = \"hi\";\n\
+                              printf(\"%s %s %s\\n\", s, name, greeting);\n\
+                              }\n\
+                              int total(void) { int v[3] = {1, 2, 3}; char *name = \"n\"; show(name); return sum(v, 3); }\n\
+                              int main(int argc, char *argv[]) { if (argc > 1) printf(\"%s\\n\", argv[1]); return total(); }\n");
+        for line in &["static mut greeting: *const u8 = b\"hello\\x00\".as_ptr();",
+                      "fn sum(a: *const i32, n: i32) -> i32 {", "s += unsafe { *a.add(i as usize) };",
+                      "let s: *const u8 = b\"hi\\x00\".as_ptr();",
+                      "unsafe { std::ffi::CStr::from_ptr(name as *const _) }.to_string_lossy(),",
+                      "unsafe { std::ffi::CStr::from_ptr(greeting as *const _) }.to_string_lossy()",
+                      "let name: *mut u8 = b\"n\\x00\".as_ptr() as *mut u8;", "sum(v.as_ptr(), 3)",
+                      "println!(\"{}\", argv[1]);"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "c_strings");
+    }
+
+    #[test]
+    fn test_fresh_temporaries() {
+        let rust = translate("int g(int tmp, int i) {\n\
+                              int place[4] = {0, 0, 0, 0};\n\
+                              int b = place[i++]++;\n\
+                              int tmp_1 = tmp > 0 ? tmp++ : place[i]--;\n\
+                              return tmp_1;\n\
+                              }\n");
+        for line in &["let place_1 = &mut place[{ let tmp_2 = i; i += 1; tmp_2 } as usize];", "let tmp_2 = *place_1;",
+                      "*place_1 += 1;", "{ let tmp_2 = tmp; tmp += 1; tmp_2 }",
+                      "{ let tmp_2 = place[i as usize]; place[i as usize] -= 1; tmp_2 }"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+        assert_compiles(&rust, "fresh_temporaries");
+    }
+
+    #[test]
+    fn test_cast_before_less_than() {
+        let rust = translate("bool less(double d, int n) { bool b = (int)d < n; return b; }\n\
+                              int shift(int n, int s) { int r = n + (int)s << 2; return r; }\n");
+        assert!(rust.contains("let b: bool = (d as i32) < n;"), "{}", rust);
+        assert!(rust.contains("let r: i32 = (n + s as i32) << 2;"), "{}", rust);
+        assert_compiles(&rust, "cast");
+    }
+}
@@ -6,6 +6,7 @@ pub enum DocType {
     STRICT,
     STRUCT_INIT,
     NO_RETURN,
+    NO_RETURN_TAIL,
     INCLUDE_STMT,
     NO_POINTER,
     CONSTRUCTOR,
@@ -13,6 +14,7 @@ pub enum DocType {
     DEFAULT,
 	UNION,
 	UNION_DECL,
+	FALLTHROUGH,
 }
 
 impl DocType {
@@ -34,6 +36,12 @@ impl DocType {
                  * shorthand notation. If error found in this line, Please replace shorthand \
                  notation \n * with return statement \n **/\n"
             }
+            // a tail expression cannot carry a doc comment
+            DocType::NO_RETURN_TAIL => {
+                "\n// Crust tries to identify return statement and replace with rust equivalent\n// \
+                 shorthand notation. If error found in this line, Please replace shorthand \
+                 notation\n// with return statement\n"
+            }
             DocType::INCLUDE_STMT => {
                 "\n/** Crust doesn't resolve C/C++ dependencies or included header.\n* You may have \
                  to define your own module and implement those functionality in Rust \n* Or you \
@@ -65,6 +73,10 @@ impl DocType {
 				"\n/* Union declaration must be translated to Rust equivalent Some type varients\
 				\n * Make changes before compilation \n */"
 			}
+			DocType::FALLTHROUGH => {
+				"\n/* C/C++ case falls through into the cases below it.\
+				\n * Their statements are repeated in this arm, keep the copies in sync \n */"
+			}
             _ => "//Doc Not Found. Please Report bug",
         }
     }
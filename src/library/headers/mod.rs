@@ -0,0 +1,355 @@
+use library::ast::{Sign, Type};
+
+/**
+ * StdName:
+ * a name declared by a standard header and its Rust equivalent.
+ * rust is a path replacing the name, or when it holds `$0`, `$1`..
+ * a template for the whole call with the arguments in place.
+ * imports are the paths it needs, `self` imports the module itself.
+ * ty is the C type of the value or of the result of a function,
+ * whose arguments convert to params
+ */
+#[derive(Debug, PartialEq)]
+pub struct StdName {
+    pub name: &'static str,
+    pub rust: &'static str,
+    pub imports: &'static [&'static str],
+    pub ty: Type,
+    pub params: &'static [Type],
+}
+
+// a standard header under its C and C++ spellings,
+// templates are the class templates it declares
+#[derive(Debug, PartialEq)]
+pub struct StdHeader {
+    pub files: &'static [&'static str],
+    pub names: &'static [StdName],
+    pub templates: &'static [StdName],
+}
+
+const IO: &[&str] = &["std::io::self"];
+const IO_WRITE: &[&str] = &["std::io::self", "std::io::Write"];
+const WRITE: &[&str] = &["std::io::Write"];
+const PROCESS: &[&str] = &["std::process::self"];
+const CONSTS: &[&str] = &["std::f64::consts::self"];
+const HASH_MAP: &[&str] = &["std::collections::HashMap"];
+const BTREE_MAP: &[&str] = &["std::collections::BTreeMap"];
+
+const INT: Type = Type::Int(Sign::Plain);
+const LONG: Type = Type::Long(Sign::Plain);
+const DOUBLE: Type = Type::Double;
+
+// `"sqrt"(DOUBLE) -> DOUBLE => "f64::sqrt", &[];`, the types are optional
+macro_rules! names {
+    ($($name:literal $(($($param:expr),*))? $(-> $ty:expr)? => $rust:expr, $imports:expr;)*) => {
+        &[$(StdName {
+            name: $name,
+            rust: $rust,
+            imports: $imports,
+            ty: or_void!($($ty)?),
+            params: &[$($($param),*)?],
+        }),*]
+    };
+}
+
+macro_rules! or_void {
+    () => { Type::Void };
+    ($ty:expr) => { $ty };
+}
+
+pub static STD_HEADERS: &[StdHeader] = &[
+    StdHeader {
+        files: &["stdio.h", "cstdio"],
+        names: names! {
+            "stdin" => "io::stdin()", IO;
+            "stdout" => "io::stdout()", IO;
+            "stderr" => "io::stderr()", IO;
+            "fflush" => "$0.flush().unwrap()", WRITE;
+            "putchar"(INT) => "io::stdout().write_all(&[$0 as u8]).unwrap()", IO_WRITE;
+            "puts" => "println!(\"{}\", $0)", &[];
+            // the format string is rewritten, $* stands for all arguments
+            "printf" => "print!($*)", &[];
+        },
+        templates: &[],
+    },
+    StdHeader {
+        files: &["stdlib.h", "cstdlib"],
+        names: names! {
+            "exit"(INT) => "process::exit", PROCESS;
+            "abort" => "process::abort", PROCESS;
+            "abs"(INT) -> INT => "i32::abs", &[];
+            "labs"(LONG) -> LONG => "i64::abs", &[];
+            "EXIT_SUCCESS" -> INT => "0", &[];
+            "EXIT_FAILURE" -> INT => "1", &[];
+        },
+        templates: &[],
+    },
+    StdHeader {
+        files: &["math.h", "cmath"],
+        names: names! {
+            "sqrt"(DOUBLE) -> DOUBLE => "f64::sqrt", &[];
+            "cbrt"(DOUBLE) -> DOUBLE => "f64::cbrt", &[];
+            "pow"(DOUBLE, DOUBLE) -> DOUBLE => "f64::powf", &[];
+            "fabs"(DOUBLE) -> DOUBLE => "f64::abs", &[];
+            "floor"(DOUBLE) -> DOUBLE => "f64::floor", &[];
+            "ceil"(DOUBLE) -> DOUBLE => "f64::ceil", &[];
+            "round"(DOUBLE) -> DOUBLE => "f64::round", &[];
+            "exp"(DOUBLE) -> DOUBLE => "f64::exp", &[];
+            "log"(DOUBLE) -> DOUBLE => "f64::ln", &[];
+            "log2"(DOUBLE) -> DOUBLE => "f64::log2", &[];
+            "log10"(DOUBLE) -> DOUBLE => "f64::log10", &[];
+            "sin"(DOUBLE) -> DOUBLE => "f64::sin", &[];
+            "cos"(DOUBLE) -> DOUBLE => "f64::cos", &[];
+            "tan"(DOUBLE) -> DOUBLE => "f64::tan", &[];
+            "atan2"(DOUBLE, DOUBLE) -> DOUBLE => "f64::atan2", &[];
+            "hypot"(DOUBLE, DOUBLE) -> DOUBLE => "f64::hypot", &[];
+            "M_PI" -> DOUBLE => "consts::PI", CONSTS;
+            "M_E" -> DOUBLE => "consts::E", CONSTS;
+            "M_SQRT2" -> DOUBLE => "consts::SQRT_2", CONSTS;
+        },
+        templates: &[],
+    },
+    StdHeader {
+        files: &["limits.h", "climits"],
+        names: names! {
+            "SCHAR_MIN" -> Type::Char(Sign::Signed) => "i8::MIN", &[];
+            "SCHAR_MAX" -> Type::Char(Sign::Signed) => "i8::MAX", &[];
+            "UCHAR_MAX" -> Type::Char(Sign::Unsigned) => "u8::MAX", &[];
+            "SHRT_MIN" -> Type::Short(Sign::Plain) => "i16::MIN", &[];
+            "SHRT_MAX" -> Type::Short(Sign::Plain) => "i16::MAX", &[];
+            "USHRT_MAX" -> Type::Short(Sign::Unsigned) => "u16::MAX", &[];
+            "INT_MIN" -> INT => "i32::MIN", &[];
+            "INT_MAX" -> INT => "i32::MAX", &[];
+            "UINT_MAX" -> Type::Int(Sign::Unsigned) => "u32::MAX", &[];
+            "LONG_MIN" -> LONG => "i64::MIN", &[];
+            "LONG_MAX" -> LONG => "i64::MAX", &[];
+            "ULONG_MAX" -> Type::Long(Sign::Unsigned) => "u64::MAX", &[];
+            "LLONG_MIN" -> Type::LongLong(Sign::Plain) => "i64::MIN", &[];
+            "LLONG_MAX" -> Type::LongLong(Sign::Plain) => "i64::MAX", &[];
+            "ULLONG_MAX" -> Type::LongLong(Sign::Unsigned) => "u64::MAX", &[];
+        },
+        templates: &[],
+    },
+    // the streams are written with print! and eprint!
+    StdHeader {
+        files: &["iostream"],
+        names: names! {
+            "cout" => "print!", &[];
+            "cerr" => "eprint!", &[];
+            "endl" => "\n", &[];
+        },
+        templates: &[],
+    },
+    StdHeader {
+        files: &["vector"],
+        names: &[],
+        templates: names! {
+            "vector" => "Vec", &[];
+        },
+    },
+    // map keeps its keys in order, like a BTreeMap
+    StdHeader {
+        files: &["map"],
+        names: &[],
+        templates: names! {
+            "map" => "BTreeMap", BTREE_MAP;
+        },
+    },
+    StdHeader {
+        files: &["unordered_map"],
+        names: &[],
+        templates: names! {
+            "unordered_map" => "HashMap", HASH_MAP;
+        },
+    },
+    // bool and string are Rust types already
+    StdHeader {
+        files: &["stdbool.h", "cstdbool"],
+        names: &[],
+        templates: &[],
+    },
+    StdHeader {
+        files: &["string"],
+        names: &[],
+        templates: &[],
+    },
+];
+
+
+// whether name is a class template of a standard header
+pub fn is_template(name: &str) -> bool {
+    STD_HEADERS.iter().any(|h| h.templates.iter().any(|t| t.name == name))
+}
+
+
+/**
+ * find_header:
+ * takes the text of an #include directive and returns the
+ * standard header it includes, if it is a known one
+ */
+pub fn find_header(include: &str) -> Option<&'static StdHeader> {
+    let start = include.find('<')?;
+    let end = include.rfind('>')?;
+    let file = include.get(start + 1..end)?.replace(' ', "");
+    STD_HEADERS.iter().find(|h| h.files.contains(&&file[..]))
+}
+
+
+/**
+ * rust_format:
+ * rewrites a printf format string as a Rust format string, `%5.2f`
+ * gives `{:5.2}`. Returns it with the conversion character of each
+ * argument, or None for conversions Rust has no equivalent of
+ */
+pub fn rust_format(format: &str) -> Option<(String, Vec<char>)> {
+    let mut out = String::new();
+    let mut convs: Vec<char> = Vec::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => out.push_str("{{"),
+            '}' => out.push_str("}}"),
+            '%' => {
+                if chars.next_if_eq(&'%').is_some() {
+                    out.push('%');
+                    continue;
+                }
+                let (mut left, mut plus, mut alternate, mut zero) = (false, false, false, false);
+                while let Some(flag) = chars.next_if(|c| "-+#0".contains(*c)) {
+                    match flag {
+                        '-' => left = true,
+                        '+' => plus = true,
+                        '#' => alternate = true,
+                        _ => zero = true,
+                    }
+                }
+                let mut width = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    width.push(digit);
+                }
+                let mut precision = None;
+                if chars.next_if_eq(&'.').is_some() {
+                    let mut digits = String::new();
+                    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(digit);
+                    }
+                    precision = Some(digits);
+                }
+                // the argument has its own type already
+                while chars.next_if(|c| "hlLqjzt".contains(*c)).is_some() {}
+                let conv = chars.next()?;
+                let kind = match conv {
+                    'd' | 'i' | 'u' | 's' | 'c' => "",
+                    // %f prints six digits unless told otherwise
+                    'f' | 'F' => {
+                        precision = precision.or_else(|| Some("6".to_string()));
+                        ""
+                    }
+                    'x' => "x",
+                    'X' => "X",
+                    'o' => "o",
+                    _ => return None,
+                };
+                let mut spec = String::new();
+                if left {
+                    spec.push('<');
+                }
+                if plus {
+                    spec.push('+');
+                }
+                if alternate {
+                    spec.push('#');
+                }
+                if zero && !left {
+                    spec.push('0');
+                }
+                spec.push_str(&width);
+                if let Some(precision) = precision {
+                    spec.push('.');
+                    spec.push_str(if precision.is_empty() { "0" } else { &precision });
+                }
+                spec.push_str(kind);
+                if spec.is_empty() {
+                    out.push_str("{}");
+                } else {
+                    out.push_str(&format!("{{:{}}}", spec));
+                }
+                convs.push(conv);
+            }
+            c => out.push(c),
+        }
+    }
+    Some((out, convs))
+}
+
+
+/**
+ * use_lines:
+ * groups the import paths by module into `use` declarations,
+ * `std::io::self` and `std::io::Write` give `use std::io::{self, Write};`
+ */
+pub fn use_lines(imports: &[&str]) -> Vec<String> {
+    let mut modules: Vec<(&str, Vec<&str>)> = Vec::new();
+    for import in imports {
+        let (module, item) = match import.rfind("::") {
+            Some(i) => (&import[..i], &import[i + 2..]),
+            None => continue,
+        };
+        match modules.iter().position(|m| m.0 == module) {
+            Some(i) => {
+                if !modules[i].1.contains(&item) {
+                    modules[i].1.push(item);
+                }
+            }
+            None => modules.push((module, vec![item])),
+        }
+    }
+    modules.sort();
+    modules.into_iter()
+        .map(|(module, mut items)| {
+            // self goes first
+            items.sort_by_key(|item| (*item != "self", *item));
+            match items[..] {
+                ["self"] => format!("use {};", module),
+                [item] => format!("use {}::{};", module, item),
+                _ => format!("use {}::{{{}}};", module, items.join(", ")),
+            }
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod test {
+    use library::headers::{find_header, rust_format, use_lines};
+
+    #[test]
+    fn test_find_header() {
+        assert_eq!(find_header("#include <stdio.h>").unwrap().files[0], "stdio.h");
+        assert_eq!(find_header("#include < cmath >").unwrap().files[0], "math.h");
+        assert_eq!(find_header("#include <iostream>").unwrap().names[0].rust, "print!");
+        assert_eq!(find_header("#include <unordered_map>").unwrap().templates[0].rust, "HashMap");
+        assert_eq!(find_header("#include <map>").unwrap().templates[0].rust, "BTreeMap");
+        assert_eq!(find_header("#include <fstream>"), None);
+        assert_eq!(find_header("#include \"stdio.h\""), None);
+    }
+
+    #[test]
+    fn test_rust_format() {
+        assert_eq!(rust_format("%d items, %s\n"), Some(("{} items, {}\n".to_string(), vec!['d', 's'])));
+        assert_eq!(rust_format("%5.2f|%-4ld|%08X|%#o|%f"),
+                   Some(("{:5.2}|{:<4}|{:08X}|{:#o}|{:.6}".to_string(), vec!['f', 'd', 'X', 'o', 'f'])));
+        assert_eq!(rust_format("100%% {%c}"), Some(("100% {{{}}}".to_string(), vec!['c'])));
+        assert_eq!(rust_format("%p"), None);
+        assert_eq!(rust_format("%*d"), None);
+    }
+
+    #[test]
+    fn test_use_lines() {
+        assert_eq!(use_lines(&["std::process::self", "std::io::Write", "std::io::self", "std::io::self"]),
+                   vec!["use std::io::{self, Write};", "use std::process;"]);
+        assert_eq!(use_lines(&["std::collections::HashMap"]),
+                   vec!["use std::collections::HashMap;"]);
+        assert!(use_lines(&[]).is_empty());
+    }
+}
@@ -0,0 +1,594 @@
+use library::ast::*;
+use library::mutability::reads_from_cin;
+
+// which of the tracked variables are assigned at a point of the
+// program, on all paths leading there or on some of them
+#[derive(Debug, Clone)]
+struct State {
+    assigned: Vec<bool>,
+    maybe: Vec<bool>,
+    // after return, break or continue nothing is read
+    dead: bool,
+}
+
+impl State {
+    fn is_assigned(&self, id: usize) -> bool {
+        self.dead || self.assigned.get(id) == Some(&true)
+    }
+
+    fn maybe_assigned(&self, id: usize) -> bool {
+        self.maybe.get(id) == Some(&true)
+    }
+
+    fn assign(&mut self, id: usize) {
+        if self.assigned.len() <= id {
+            self.assigned.resize(id + 1, false);
+            self.maybe.resize(id + 1, false);
+        }
+        self.assigned[id] = true;
+        self.maybe[id] = true;
+    }
+
+    // state where two paths meet
+    fn join(&self, other: &State) -> State {
+        if self.dead {
+            return other.clone();
+        }
+        if other.dead {
+            return self.clone();
+        }
+        let len = self.assigned.len().max(other.assigned.len());
+        State {
+            assigned: (0..len).map(|id| self.is_assigned(id) && other.is_assigned(id)).collect(),
+            maybe: (0..len).map(|id| self.maybe_assigned(id) || other.maybe_assigned(id)).collect(),
+            dead: false,
+        }
+    }
+}
+
+// a local declared without an initializer
+#[derive(Debug)]
+struct Tracked {
+    name: String,
+    pos: Pos,
+    // first read which may come before an assignment
+    read: Option<Pos>,
+    // loops around the declaration
+    loops: usize,
+    // assigned where it may be assigned already, so it needs `mut`
+    reassigned: bool,
+}
+
+// what a declaration starts with
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Start {
+    // an initializer, or nothing and a deferred `let`
+    Given,
+    // the default value of its type
+    Default,
+    // decided by the reads of a tracked variable
+    Tracked(usize),
+}
+
+// finds the reads of locals on paths where they were not assigned
+struct Analysis {
+    scopes: Vec<Vec<(String, Option<usize>)>>,
+    vars: Vec<Tracked>,
+    starts: Vec<Start>,
+    state: State,
+    // states at the `break`s of the enclosing loops and switches
+    breaks: Vec<Vec<State>>,
+    // states at the `continue`s of the enclosing loops
+    continues: Vec<Vec<State>>,
+    loops: usize,
+}
+
+
+/**
+ * check:
+ * keeps `int x;` a deferred `let x;` when every read of x follows an
+ * assignment on all paths, otherwise x starts with the default value of
+ * its type and a diagnostic names the line of the first such read.
+ * Globals, statics and arrays always start with the default value,
+ * as C initializes the former and Rust cannot assign arrays in parts.
+ * A deferred variable assigned again, or inside a loop, is marked mut
+ */
+pub fn check(program: &mut Program) {
+    let mut analysis = Analysis {
+        scopes: vec![Vec::new()],
+        vars: Vec::new(),
+        starts: Vec::new(),
+        state: State {
+            assigned: Vec::new(),
+            maybe: Vec::new(),
+            dead: false,
+        },
+        breaks: Vec::new(),
+        continues: Vec::new(),
+        loops: 0,
+    };
+    for decl in &program.decls {
+        analysis.global(decl);
+    }
+    // default_init and is_mut of every declaration
+    let mut starts = Vec::new();
+    for start in &analysis.starts {
+        let tracked = match *start {
+            Start::Tracked(id) => &analysis.vars[id],
+            _ => {
+                starts.push((*start == Start::Default, false));
+                continue;
+            }
+        };
+        let read = match tracked.read {
+            Some(read) => read,
+            None => {
+                starts.push((false, tracked.reassigned));
+                continue;
+            }
+        };
+        program.diagnostics.push(Diagnostic {
+            msg: format!("`{}` may be read at line {} before it is assigned, it is initialized with \
+                          the default value of its type",
+                         tracked.name,
+                         read.line),
+            pos: tracked.pos,
+        });
+        starts.push((true, false));
+    }
+    let mut next = 0;
+    for decl in &mut program.decls {
+        mark_decl(decl, &starts, &mut next);
+    }
+}
+
+
+// conditions of loops which only end with a break
+fn always_true(cond: &Expr) -> bool {
+    match cond.kind {
+        ExprKind::IntLit(ref lit) => lit.value().is_some_and(|v| v != 0),
+        ExprKind::BoolLit(b) => b,
+        _ => false,
+    }
+}
+
+
+impl Analysis {
+    fn scoped<F: FnOnce(&mut Analysis)>(&mut self, walk: F) {
+        self.scopes.push(Vec::new());
+        walk(self);
+        self.scopes.pop();
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|&(n, _)| n == name)
+            .and_then(|&(_, id)| id)
+    }
+
+    fn declare(&mut self, var: &VarDecl, start: Start) {
+        let id = match start {
+            Start::Tracked(id) => Some(id),
+            _ => None,
+        };
+        self.scopes.last_mut().unwrap().push((var.name.clone(), id));
+        self.starts.push(start);
+    }
+
+    fn global(&mut self, decl: &Decl) {
+        match *decl {
+            Decl::Var(ref vars) => {
+                for var in vars {
+                    let start = if var.init.is_none() { Start::Default } else { Start::Given };
+                    self.declare(var, start);
+                }
+            }
+            Decl::Function(ref func) => self.function(func),
+            Decl::Record(ref record) => {
+                for method in &record.methods {
+                    self.function(&method.func);
+                }
+            }
+            // macro bodies are checked where they are expanded
+            Decl::Macro(ref def) => {
+                if let MacroBody::Stmt(ref body) = def.body {
+                    let outer = std::mem::replace(&mut self.state.dead, true);
+                    self.scoped(|a| a.stmt(body));
+                    self.state.dead = outer;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn function(&mut self, func: &Function) {
+        let outer = std::mem::replace(&mut self.state.dead, false);
+        self.scoped(|a| {
+            for param in &func.params {
+                a.scopes.last_mut().unwrap().push((param.name.clone(), None));
+            }
+            if let Some(ref body) = func.body {
+                for stmt in body {
+                    a.stmt(stmt);
+                }
+            }
+        });
+        self.state.dead = outer;
+    }
+
+    fn local(&mut self, var: &VarDecl) {
+        if let Some(ref init) = var.init {
+            self.read(init);
+            self.declare(var, Start::Given);
+            return;
+        }
+        let start = match var.ty {
+            _ if var.is_static => Start::Default,
+            // containers start empty
+            Type::Array(..) | Type::Template(..) => Start::Default,
+            // struct variables get the defaults of their fields
+            Type::Named(_) => Start::Given,
+            _ => {
+                self.vars.push(Tracked {
+                    name: var.name.clone(),
+                    pos: var.pos,
+                    read: None,
+                    loops: self.loops,
+                    reassigned: false,
+                });
+                Start::Tracked(self.vars.len() - 1)
+            }
+        };
+        self.declare(var, start);
+    }
+
+    fn assign(&mut self, name: &str) {
+        if let Some(id) = self.lookup(name) {
+            if self.state.maybe_assigned(id) || self.loops > self.vars[id].loops {
+                self.vars[id].reassigned = true;
+            }
+            self.state.assign(id);
+        }
+    }
+
+    // an assignment to target, only a whole variable gets assigned
+    fn write(&mut self, target: &Expr) {
+        match target.kind {
+            ExprKind::Ident(ref name) => self.assign(name),
+            _ => self.read(target),
+        }
+    }
+
+    fn read(&mut self, expr: &Expr) {
+        match expr.kind {
+            ExprKind::Ident(ref name) => {
+                if let Some(id) = self.lookup(name) {
+                    if !self.state.is_assigned(id) && self.vars[id].read.is_none() {
+                        self.vars[id].read = Some(expr.pos);
+                    }
+                }
+            }
+            ExprKind::Assign(None, ref l, ref r) => {
+                self.read(r);
+                self.write(l);
+            }
+            ExprKind::Assign(Some(_), ref l, ref r) => {
+                self.read(l);
+                self.read(r);
+                self.write(l);
+            }
+            // scanf("%d", &x) may assign x
+            ExprKind::Unary(UnOp::AddrOf, ref e) => self.write(e),
+            ExprKind::Unary(_, ref e) | ExprKind::Member(ref e, _) | ExprKind::Cast(_, ref e) => self.read(e),
+            ExprKind::Binary(BinOp::Shr, ref l, ref r) if reads_from_cin(l) => {
+                self.read(l);
+                self.write(r);
+            }
+            // the right operand may not run
+            ExprKind::Binary(BinOp::And, ref l, ref r) | ExprKind::Binary(BinOp::Or, ref l, ref r) => {
+                self.read(l);
+                let before = self.state.clone();
+                self.read(r);
+                self.state = self.state.join(&before);
+            }
+            ExprKind::Binary(_, ref l, ref r) | ExprKind::Index(ref l, ref r) => {
+                self.read(l);
+                self.read(r);
+            }
+            ExprKind::Cond(ref c, ref t, ref o) => {
+                self.read(c);
+                let before = self.state.clone();
+                self.read(t);
+                let then = std::mem::replace(&mut self.state, before);
+                self.read(o);
+                self.state = self.state.join(&then);
+            }
+            ExprKind::Call(ref f, ref args) => {
+                self.read(f);
+                for a in args {
+                    self.read(a);
+                }
+            }
+            ExprKind::InitList(ref list) | ExprKind::Comma(ref list) => {
+                for e in list {
+                    self.read(e);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn looped<F: FnOnce(&mut Analysis)>(&mut self, body: F) {
+        self.loops += 1;
+        body(self);
+        self.loops -= 1;
+    }
+
+    // runs body as a loop or switch, returning the states at its breaks
+    fn breakable<F: FnOnce(&mut Analysis)>(&mut self, body: F) -> Vec<State> {
+        self.breaks.push(Vec::new());
+        body(self);
+        self.breaks.pop().unwrap()
+    }
+
+    // runs the body of a loop, ending at its end or at one of its continues
+    fn continuable<F: FnOnce(&mut Analysis)>(&mut self, body: F) {
+        self.continues.push(Vec::new());
+        body(self);
+        let continues = self.continues.pop().unwrap();
+        self.state = continues.iter().fold(self.state.clone(), |state, c| state.join(c));
+    }
+
+    /**
+     * flagged:
+     * a loop whose body continues is generated with its condition or
+     * update at the top behind a flag, Rust checks them as if they could
+     * also run on entry
+     */
+    fn flagged(&mut self, body: &Stmt, entry: &State) {
+        if body.has_continue() {
+            self.state = self.state.join(entry);
+        }
+    }
+
+    // the state after a loop, which ends at exit or at one of its breaks
+    fn leave(&mut self, exit: State, breaks: Vec<State>) {
+        self.state = breaks.iter().fold(exit, |state, b| state.join(b));
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt.kind {
+            StmtKind::Decl(Decl::Var(ref vars)) => {
+                for var in vars {
+                    self.local(var);
+                }
+            }
+            StmtKind::Expr(ref e) => self.read(e),
+            StmtKind::Return(ref e) => {
+                if let Some(ref e) = *e {
+                    self.read(e);
+                }
+                self.state.dead = true;
+            }
+            StmtKind::Break => {
+                let state = self.state.clone();
+                if let Some(breaks) = self.breaks.last_mut() {
+                    breaks.push(state);
+                }
+                self.state.dead = true;
+            }
+            StmtKind::Continue => {
+                let state = self.state.clone();
+                if let Some(continues) = self.continues.last_mut() {
+                    continues.push(state);
+                }
+                self.state.dead = true;
+            }
+            StmtKind::Block(ref stmts) => {
+                self.scoped(|a| {
+                    for s in stmts {
+                        a.stmt(s);
+                    }
+                })
+            }
+            StmtKind::If(ref c, ref t, ref o) => {
+                self.read(c);
+                let before = self.state.clone();
+                self.scoped(|a| a.stmt(t));
+                let then = std::mem::replace(&mut self.state, before);
+                if let Some(ref o) = *o {
+                    self.scoped(|a| a.stmt(o));
+                }
+                self.state = self.state.join(&then);
+            }
+            StmtKind::While(ref c, ref body) => {
+                self.looped(|a| {
+                    a.read(c);
+                    let exit = a.exit_state(Some(c));
+                    let breaks = a.breakable(|a| a.continuable(|a| a.scoped(|a| a.stmt(body))));
+                    a.leave(exit, breaks);
+                })
+            }
+            StmtKind::DoWhile(ref body, ref c) => {
+                self.looped(|a| {
+                    let entry = a.state.clone();
+                    // the condition is reached from the end of the body or a continue
+                    let breaks = a.breakable(|a| a.continuable(|a| a.scoped(|a| a.stmt(body))));
+                    a.flagged(body, &entry);
+                    a.read(c);
+                    let exit = a.state.clone();
+                    a.leave(exit, breaks);
+                })
+            }
+            StmtKind::For(ref init, ref c, ref u, ref body) => {
+                self.scoped(|a| {
+                    if let Some(ref init) = *init {
+                        a.stmt(init);
+                    }
+                    a.looped(|a| {
+                        if let Some(ref c) = *c {
+                            a.read(c);
+                        }
+                        let exit = a.exit_state(c.as_ref());
+                        let entry = a.state.clone();
+                        let breaks = a.breakable(|a| {
+                            a.continuable(|a| a.scoped(|a| a.stmt(body)));
+                            if let Some(ref u) = *u {
+                                a.flagged(body, &entry);
+                                a.read(u);
+                            }
+                        });
+                        a.leave(exit, breaks);
+                    })
+                })
+            }
+            StmtKind::Switch(ref c, ref cases) => {
+                self.read(c);
+                let entry = self.state.clone();
+                self.state.dead = true;
+                let breaks = self.breakable(|a| {
+                    a.scoped(|a| {
+                        for case in cases {
+                            // a case is entered from the switch or falls through
+                            a.state = a.state.join(&entry);
+                            for s in &case.body {
+                                a.stmt(s);
+                            }
+                        }
+                    })
+                });
+                let mut exit = self.state.clone();
+                if !cases.iter().any(|case| case.is_default) {
+                    exit = exit.join(&entry);
+                }
+                self.leave(exit, breaks);
+            }
+            _ => {}
+        }
+    }
+
+    // the state when the condition of a loop is false
+    fn exit_state(&self, cond: Option<&Expr>) -> State {
+        let mut exit = self.state.clone();
+        if cond.is_none_or(always_true) {
+            exit.dead = true;
+        }
+        exit
+    }
+}
+
+
+// sets default_init and is_mut, numbering the declarations in the same order as Analysis
+fn mark_decl(decl: &mut Decl, starts: &[(bool, bool)], next: &mut usize) {
+    match *decl {
+        Decl::Var(ref mut vars) => {
+            for var in vars {
+                let (default_init, is_mut) = starts[*next];
+                var.default_init = default_init;
+                var.is_mut = is_mut;
+                *next += 1;
+            }
+        }
+        Decl::Function(ref mut func) => mark_body(&mut func.body, starts, next),
+        Decl::Record(ref mut record) => {
+            for method in &mut record.methods {
+                mark_body(&mut method.func.body, starts, next);
+            }
+        }
+        Decl::Macro(ref mut def) => {
+            if let MacroBody::Stmt(ref mut body) = def.body {
+                mark_stmt(body, starts, next);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn mark_body(body: &mut Option<Vec<Stmt>>, starts: &[(bool, bool)], next: &mut usize) {
+    if let Some(ref mut body) = *body {
+        for stmt in body {
+            mark_stmt(stmt, starts, next);
+        }
+    }
+}
+
+fn mark_stmt(stmt: &mut Stmt, starts: &[(bool, bool)], next: &mut usize) {
+    match stmt.kind {
+        StmtKind::Decl(ref mut decl @ Decl::Var(_)) => mark_decl(decl, starts, next),
+        StmtKind::Block(ref mut stmts) => {
+            for s in stmts {
+                mark_stmt(s, starts, next);
+            }
+        }
+        StmtKind::If(_, ref mut t, ref mut o) => {
+            mark_stmt(t, starts, next);
+            if let Some(ref mut o) = *o {
+                mark_stmt(o, starts, next);
+            }
+        }
+        StmtKind::While(_, ref mut body) | StmtKind::DoWhile(ref mut body, _) => mark_stmt(body, starts, next),
+        StmtKind::For(ref mut init, _, _, ref mut body) => {
+            if let Some(ref mut init) = *init {
+                mark_stmt(init, starts, next);
+            }
+            mark_stmt(body, starts, next);
+        }
+        StmtKind::Switch(_, ref mut cases) => {
+            for case in cases {
+                for s in &mut case.body {
+                    mark_stmt(s, starts, next);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use library::ast::*;
+    use library::lexer;
+    use library::parser::{self, Options};
+
+    #[test]
+    fn test_check() {
+        let source = "int g;\n\
+                      int f(int c) {\n\
+                      int a; int b; int d; int e; int arr[4];\n\
+                      if (c) { a = 1; } else { a = 2; }\n\
+                      if (c) b = 1;\n\
+                      while (1) { d = 3; break; }\n\
+                      scanf(\"%d\", &e);\n\
+                      int y; do { if (c) continue; y = 1; } while (y);\n\
+                      int v; do { v = 1; if (c) break; } while (v);\n\
+                      return a + b + d + e;\n\
+                      }";
+        let tokens = lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = parser::init_parser(&tokens, &Options::default());
+        let mut defaults = Vec::new();
+        for decl in &program.decls {
+            match *decl {
+                Decl::Var(ref vars) => defaults.extend(vars.iter().filter(|v| v.default_init).map(|v| v.name.clone())),
+                Decl::Function(ref func) => {
+                    for stmt in func.body.as_ref().unwrap() {
+                        if let StmtKind::Decl(Decl::Var(ref vars)) = stmt.kind {
+                            defaults.extend(vars.iter().filter(|v| v.default_init).map(|v| v.name.clone()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(defaults, vec!["g", "b", "arr", "y"]);
+        let msgs: Vec<&str> = program.diagnostics.iter().map(|d| &d.msg[..]).collect();
+        assert_eq!(msgs,
+                   vec!["`b` may be read at line 10 before it is assigned, it is initialized with the default \
+                         value of its type",
+                        "`y` may be read at line 8 before it is assigned, it is initialized with the default \
+                         value of its type"]);
+    }
+}
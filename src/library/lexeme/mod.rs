@@ -22,6 +22,9 @@ pub enum Type {
     KEYWORD_SIGNED, //
     KEYWORD_AUTO, //13
     TYPE_STRING, //14
+    PRIMITIVE_WCHAR, // wchar_t
+    PRIMITIVE_CHAR16, // char16_t
+    PRIMITIVE_CHAR32, // char32_t
 
     //---> Put Base Types for tokens here for recognizing different token categories
     BASE_DATATYPE,
@@ -53,6 +56,12 @@ pub enum Type {
     OP_BITNEG, //
     OP_BITLSHIFT, //
     OP_BITRSHIFT, //
+    OP_BITXOR, //
+    OP_BITANDEQU, //
+    OP_BITOREQU, //
+    OP_BITXOREQU, //
+    OP_BITLSHIFTEQU, //
+    OP_BITRSHIFTEQU, //
     OP_LOGAND, //
     OP_LOGOR, //
     OP_LOGNOT, //
@@ -66,6 +75,11 @@ pub enum Type {
     OP_ASSIGN, //
     OP_INDIRECT, // differentiate b/w this and OP_BITAND during parsing?
 	OP_SIZEOF,
+    OP_TERNARY, // ? of the conditional operator
+    OP_DOT, // member access
+    OP_ARROW, // member access through a pointer
+    OP_SCOPE, // ::
+    OP_ELLIPSIS, // variadic parameters
     RETTYPE,
 
     //---> Values here
@@ -116,6 +130,46 @@ pub enum Type {
     KEYWORD_STRUCT,
     KEYWORD_ENUM,
     KEYWORD_UNION,
+    KEYWORD_EXTERN,
+    KEYWORD_INLINE,
+    KEYWORD_REGISTER,
+    KEYWORD_VOLATILE,
+    KEYWORD_RESTRICT,
+    KEYWORD_CONSTEXPR,
+    KEYWORD_MUTABLE,
+    KEYWORD_EXPLICIT,
+    KEYWORD_VIRTUAL,
+    KEYWORD_FRIEND,
+    KEYWORD_NORETURN, // _Noreturn
+    KEYWORD_THREAD_LOCAL, // thread_local, _Thread_local
+    KEYWORD_ATOMIC, // _Atomic
+    KEYWORD_COMPLEX, // _Complex
+    KEYWORD_IMAGINARY, // _Imaginary
+    KEYWORD_ALIGNAS, // alignas, _Alignas
+    KEYWORD_ALIGNOF, // alignof, _Alignof
+    KEYWORD_GENERIC, // _Generic
+    KEYWORD_STATIC_ASSERT, // static_assert, _Static_assert
+    KEYWORD_GOTO,
+    KEYWORD_NAMESPACE,
+    KEYWORD_USING,
+    KEYWORD_TEMPLATE,
+    KEYWORD_TYPENAME,
+    KEYWORD_OPERATOR,
+    KEYWORD_THIS,
+    KEYWORD_DELETE,
+    KEYWORD_TRY,
+    KEYWORD_CATCH,
+    KEYWORD_THROW,
+    KEYWORD_NOEXCEPT,
+    KEYWORD_DECLTYPE,
+    KEYWORD_TYPEID,
+    KEYWORD_ASM,
+    KEYWORD_EXPORT,
+    KEYWORD_STATIC_CAST,
+    KEYWORD_CONST_CAST,
+    KEYWORD_REINTERPRET_CAST,
+    KEYWORD_DYNAMIC_CAST,
+    KEYWORD_SIZEOF_PACK, // sizeof... of a parameter pack
 	HEADER_DEFINE,
 	HEADER_INCLUDE,
     NULL,
@@ -125,8 +179,33 @@ pub enum Type {
 }
 
 
+// radix of a numeric literal
 #[derive(Debug)]
+#[derive(Clone,Copy)]
+#[derive(PartialEq, Eq)]
+pub enum Radix {
+    Decimal,
+    Hex,
+    Octal,
+    Binary,
+}
+
+
+// location of a token in the source, start and end are byte
+// offsets, line and col are 1-based and col counts tabs to the next
+// multiple of eight
+#[derive(Debug)]
+#[derive(Clone,Copy,Default)]
 #[derive(PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub col: u32,
+}
+
+
+#[derive(Debug)]
 pub struct Token {
     value: String,
     // base type of token
@@ -134,12 +213,28 @@ pub struct Token {
     typ: Type,
     ln: u32,
     id: u32,
+    // radix and suffix of numeric literals, as in 0x1Fu
+    radix: Radix,
+    suffix: String,
+    span: Span,
+}
+
+// the span is where a token was found, not part of what it is
+impl PartialEq for Token {
+    fn eq(&self, other: &Token) -> bool {
+        self.value == other.value && self.base_type == other.base_type &&
+        self.typ == other.typ && self.ln == other.ln && self.id == other.id &&
+        self.radix == other.radix && self.suffix == other.suffix
+    }
 }
 
+impl Eq for Token {}
+
 impl Clone for Token {
     fn clone(&self) -> Token {
         let v = self.value.clone();
-        Token { value: v, ..*self }
+        let s = self.suffix.clone();
+        Token { value: v, suffix: s, ..*self }
     }
 }
 
@@ -151,6 +246,9 @@ impl Token {
             typ: tok_type,
             ln: line_no,
             id: id_,
+            radix: Radix::Decimal,
+            suffix: String::new(),
+            span: Span::default(),
         }
     }
 
@@ -178,7 +276,28 @@ impl Token {
         self.id
     }
 
-    fn set_token_value(&mut self, val: &str) {
+    pub fn get_radix(&self) -> Radix {
+        self.radix
+    }
+
+    pub fn get_suffix(&self) -> String {
+        self.suffix.clone()
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn set_number_form(&mut self, radix: Radix, suffix: &str) {
+        self.radix = radix;
+        self.suffix = suffix.to_string();
+    }
+
+    pub fn set_token_value(&mut self, val: &str) {
         self.value = val.to_string();
     }
 
@@ -190,7 +309,7 @@ impl Token {
         self.base_type = typ;
     }
 
-    fn set_token_ln(&mut self, ln: u32) {
+    pub fn set_token_ln(&mut self, ln: u32) {
         self.ln = ln;
     }
     fn set_token_id(&mut self, id_: u32) {
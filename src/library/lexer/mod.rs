@@ -1,12 +1,33 @@
 #![allow(dead_code)]
 
+use library::lexeme::Radix;
+use library::lexeme::Span;
 use library::lexeme::Token;
 use library::lexeme::Type;
 use library::lexeme::Type::*;
+use std::fmt;
 use std::str::Chars;
 
+// error found while tokenizing, line and column are 1-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    pub msg: String,
+    pub line: u32,
+    pub col: u32,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: error: {}", self.line, self.col, self.msg)
+    }
+}
+
 pub struct Tokenizer<'a> {
     line_no: u32,
+    joined: u32, // lines joined by backslash-newline, tokens carry logical lines
+    col: u32, // column of current_char
+    offset: usize, // byte offset of current_char
+    start: Span, // where the token in self.token began
     id: u32,
     pos: usize,
     current_char: char,
@@ -31,8 +52,12 @@ impl<'a> Tokenizer<'a> {
             pos: 0,
             id: 0,
             line_no: 0,
+            joined: 0,
+            col: 0,
+            offset: 0,
+            start: Span::default(),
             current_char: ' ',
-            length: text.len(),
+            length: text.chars().count(),
             token: token,
             token_buffer: token_stream,
             input: text.chars(),
@@ -44,7 +69,7 @@ impl<'a> Tokenizer<'a> {
     // function walks over given code text and
     // returns the stream of tokens
     // trait bound Clone
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, LexError> {
 
         self.current_char = self.get_next_char();
         loop {
@@ -54,39 +79,32 @@ impl<'a> Tokenizer<'a> {
                     self.current_char = self.get_next_char()
                 }
 
-                ' ' | '\t' => {
+                ' ' | '\t' | '\r' => {
                     self.current_char = self.get_next_char();
                 }
 
-                '"' => {
-                    self.push_advance();
-
-                    while self.current_char != '"' {
-
-                        self.push_advance();
-                        if self.current_char == '\\' {
-                            // 	self.token.push('$');
-                            self.push_advance();
-                            self.push_advance();
-                        }
+                // line continuation, the line may end in \r\n
+                '\\' if self.continues_line() => {
+                    self.current_char = self.get_next_char();
+                    if self.current_char == '\r' {
+                        self.current_char = self.get_next_char();
                     }
+                    self.line_no += 1;
+                    self.joined += 1;
+                    self.current_char = self.get_next_char();
+                }
 
-                    self.push_advance();
-                    // 	println!(" stream : {:?}",self.token);
+                '\0' if self.at_end() => {
+                    break;
+                }
+
+                '"' => {
+                    self.push_quoted('"')?;
                     self.push_to_tok_buffer(STRING, BASE_VALUE);
                 }
 
                 '\'' => {
-                    self.push_advance();
-
-                    while self.current_char != '\'' {
-                        if self.current_char == '\\' {
-                            self.push_advance();
-                        }
-                        self.push_advance();
-                    }
-
-                    self.push_advance();
+                    self.push_quoted('\'')?;
                     self.push_to_tok_buffer(CHAR_VAL, BASE_VALUE);
                 }
 
@@ -125,7 +143,12 @@ impl<'a> Tokenizer<'a> {
                     match self.current_char {
                         '<' => {
                             self.push_advance();
-                            self.push_to_tok_buffer(OP_BITLSHIFT, BASE_BINOP);
+                            if self.current_char == '=' {
+                                self.push_advance();
+                                self.push_to_tok_buffer(OP_BITLSHIFTEQU, BASE_ASSIGNOP);
+                            } else {
+                                self.push_to_tok_buffer(OP_BITLSHIFT, BASE_BINOP);
+                            }
                         }
 
                         '=' => {
@@ -144,7 +167,12 @@ impl<'a> Tokenizer<'a> {
                     match self.current_char {
                         '>' => {
                             self.push_advance();
-                            self.push_to_tok_buffer(OP_BITRSHIFT, BASE_BINOP);
+                            if self.current_char == '=' {
+                                self.push_advance();
+                                self.push_to_tok_buffer(OP_BITRSHIFTEQU, BASE_ASSIGNOP);
+                            } else {
+                                self.push_to_tok_buffer(OP_BITRSHIFT, BASE_BINOP);
+                            }
                         }
 
                         '=' => {
@@ -184,33 +212,20 @@ impl<'a> Tokenizer<'a> {
                             }
                         }
                     }
-                    let (token_type, base_type) = self.identify_token_type();
+                    let (mut token_type, base_type) = self.identify_token_type();
+                    // sizeof...(Args) counts a parameter pack
+                    if token_type == OP_SIZEOF && self.current_char == '.' &&
+                       self.input.clone().take(2).eq("..".chars()) {
+                        for _ in 0..3 {
+                            self.push_advance();
+                        }
+                        token_type = KEYWORD_SIZEOF_PACK;
+                    }
                     self.push_to_tok_buffer(token_type, base_type);
                 }
 
                 '0'...'9' => {
-                    self.push_advance();
-                    let mut is_int: bool = true;
-
-                    loop {
-                        match self.current_char {
-                            '0'...'9' => {
-                                self.push_advance();
-                            }
-                            '.' => {
-                                self.push_advance();
-                                is_int = false;
-                            }
-                            _ => {
-                                break;
-                            }
-                        };
-                    }
-                    if is_int {
-                        self.push_to_tok_buffer(NUM_INT, BASE_VALUE);
-                    } else {
-                        self.push_to_tok_buffer(NUM_FLOAT, BASE_VALUE);
-                    }
+                    self.push_number();
                 }
 
                 '+' => {
@@ -243,6 +258,11 @@ impl<'a> Tokenizer<'a> {
                             self.push_advance();
                             self.push_to_tok_buffer(OP_MINEQU, BASE_ASSIGNOP);
                         }
+
+                        '>' => {
+                            self.push_advance();
+                            self.push_to_tok_buffer(OP_ARROW, BASE_NONE);
+                        }
                         _ => {
                             self.push_to_tok_buffer(OP_MINUS, BASE_BINOP);
                         }
@@ -275,6 +295,35 @@ impl<'a> Tokenizer<'a> {
                     };
                 }
 
+                '^' => {
+                    self.push_advance();
+                    match self.current_char {
+                        '=' => {
+                            self.push_advance();
+                            self.push_to_tok_buffer(OP_BITXOREQU, BASE_ASSIGNOP);
+                        }
+                        _ => {
+                            self.push_to_tok_buffer(OP_BITXOR, BASE_BINOP);
+                        }
+                    };
+                }
+
+                '.' if self.input.clone().next().is_some_and(|c| c.is_ascii_digit()) => {
+                    self.push_number();
+                }
+
+                '.' => {
+                    self.push_advance();
+                    // `..` alone is not an operator, only `...` is
+                    if self.current_char == '.' && self.input.clone().next() == Some('.') {
+                        self.push_advance();
+                        self.push_advance();
+                        self.push_to_tok_buffer(OP_ELLIPSIS, BASE_NONE);
+                    } else {
+                        self.push_to_tok_buffer(OP_DOT, BASE_NONE);
+                    }
+                }
+
                 '~' => {
                     self.push_advance();
                     self.push_to_tok_buffer(OP_BITNEG, BASE_UNOP);
@@ -288,6 +337,11 @@ impl<'a> Tokenizer<'a> {
                             self.push_advance();
                             self.push_to_tok_buffer(OP_LOGAND, BASE_BINOP);
                         }
+
+                        '=' => {
+                            self.push_advance();
+                            self.push_to_tok_buffer(OP_BITANDEQU, BASE_ASSIGNOP);
+                        }
                         _ => {
                             self.push_to_tok_buffer(OP_BITAND, BASE_BINOP);
                         }
@@ -301,6 +355,11 @@ impl<'a> Tokenizer<'a> {
                             self.push_advance();
                             self.push_to_tok_buffer(OP_LOGOR, BASE_BINOP);
                         }
+
+                        '=' => {
+                            self.push_advance();
+                            self.push_to_tok_buffer(OP_BITOREQU, BASE_ASSIGNOP);
+                        }
                         _ => {
                             self.push_to_tok_buffer(OP_BITOR, BASE_BINOP);
                         }
@@ -325,15 +384,24 @@ impl<'a> Tokenizer<'a> {
                     match self.current_char {
                         '*' => {
                             // start of multi line comment
+                            let (line, col) = (self.line_no, self.col - 1);
+                            self.push_advance();
                             loop {
-                                self.push_advance();
-                                if self.current_char == '*' {
-                                    self.push_advance();
-                                    if self.current_char == '/' {
+                                match self.current_char {
+                                    '*' if self.input.clone().next() == Some('/') => {
+                                        self.push_advance();
                                         self.push_advance();
                                         self.push_to_tok_buffer(COMMENT_MULTI, BASE_COMMENT);
                                         break;
                                     }
+                                    '\0' if self.at_end() => {
+                                        return Err(self.error("unterminated comment", line, col));
+                                    }
+                                    '\n' => {
+                                        self.line_no += 1;
+                                        self.push_advance();
+                                    }
+                                    _ => self.push_advance(),
                                 }
                             }
                         }
@@ -342,10 +410,9 @@ impl<'a> Tokenizer<'a> {
                             // single line comment
                             loop {
                                 match self.current_char {
+                                    // the line break is left to be counted once, \r\n included
                                     '\n' | '\r' | '\0' => {
                                         self.push_to_tok_buffer(COMMENT_SINGLE, BASE_COMMENT);
-                                        self.current_char = self.get_next_char();
-                                        self.line_no += 1;
                                         break;
                                     }
 
@@ -372,7 +439,17 @@ impl<'a> Tokenizer<'a> {
 
                 ':' => {
                     self.push_advance();
-                    self.push_to_tok_buffer(COLON, BASE_NONE);
+                    if self.current_char == ':' {
+                        self.push_advance();
+                        self.push_to_tok_buffer(OP_SCOPE, BASE_NONE);
+                    } else {
+                        self.push_to_tok_buffer(COLON, BASE_NONE);
+                    }
+                }
+
+                '?' => {
+                    self.push_advance();
+                    self.push_to_tok_buffer(OP_TERNARY, BASE_NONE);
                 }
 
                 ',' => {
@@ -384,19 +461,99 @@ impl<'a> Tokenizer<'a> {
                     self.push_to_tok_buffer(HEADER_INCLUDE, BASE_PREP);
                 }
 
-                _ => {
-                    self.push_advance();
-                    self.push_to_tok_buffer(OTHER, BASE_NONE);
+                c => {
+                    let msg = format!("stray `{}` in program", c.escape_default());
+                    return Err(self.error(&msg, self.line_no, self.col));
                 }
             };
 
-            if self.pos > self.length {
+            if self.at_end() {
                 break;
             }
         } //loop
 
         // return the stream clone to struct internal object
-        self.token_buffer.clone()
+        Ok(self.token_buffer.clone())
+    }
+
+    // push_quoted:
+    // pushes a char or string literal delimited by quote,
+    // checking its escape sequences
+    //
+    fn push_quoted(&mut self, quote: char) -> Result<(), LexError> {
+        let (line, col) = (self.line_no, self.col);
+        let what = if quote == '"' { "string" } else { "character" };
+        self.push_advance();
+        loop {
+            match self.current_char {
+                c if c == quote => {
+                    self.push_advance();
+                    return Ok(());
+                }
+                '\n' => return Err(self.error(&format!("unterminated {} literal", what), line, col)),
+                '\0' if self.at_end() => {
+                    return Err(self.error(&format!("unterminated {} literal", what), line, col))
+                }
+                '\\' => {
+                    let (esc_line, esc_col) = (self.line_no, self.col);
+                    self.push_advance();
+                    let esc = self.current_char;
+                    match esc {
+                        'n' | 't' | 'r' | 'a' | 'b' | 'f' | 'v' | 'e' | '\\' | '\'' | '"' | '?' |
+                        '0'...'7' => self.push_advance(),
+                        'x' | 'u' | 'U' => {
+                            self.push_advance();
+                            if !self.current_char.is_ascii_hexdigit() {
+                                let msg = format!("`\\{}` used with no following hex digits", esc);
+                                return Err(self.error(&msg, esc_line, esc_col));
+                            }
+                        }
+                        // escaped newline continues the literal
+                        '\r' if self.input.clone().next() == Some('\n') => {
+                            self.push_advance();
+                            self.line_no += 1;
+                            self.joined += 1;
+                            self.push_advance();
+                        }
+                        '\n' => {
+                            self.line_no += 1;
+                            self.joined += 1;
+                            self.push_advance();
+                        }
+                        _ => {
+                            let msg = format!("unknown escape sequence `\\{}`", esc.escape_default());
+                            return Err(self.error(&msg, esc_line, esc_col));
+                        }
+                    }
+                }
+                _ => self.push_advance(),
+            }
+        }
+    }
+
+    // checks whether the whole input has been read
+    fn at_end(&self) -> bool {
+        self.pos > self.length
+    }
+
+    fn error(&self, msg: &str, line: u32, col: u32) -> LexError {
+        LexError {
+            msg: msg.to_string(),
+            line: line + 1,
+            col,
+        }
+    }
+
+    // continues_line:
+    // checks for a backslash at current_char ending the line
+    //
+    fn continues_line(&self) -> bool {
+        let mut rest = self.input.clone();
+        match rest.next() {
+            Some('\n') => true,
+            Some('\r') => rest.next() == Some('\n'),
+            _ => false,
+        }
     }
 
     // get_next_token:
@@ -404,7 +561,15 @@ impl<'a> Tokenizer<'a> {
     // pointed by `pos` position
     //
     fn get_next_char(&mut self) -> char {
+        if self.pos > 0 {
+            self.offset += self.current_char.len_utf8();
+        }
         self.pos += 1;
+        match self.current_char {
+            '\n' => self.col = 1,
+            '\t' => self.col = self.col.div_ceil(8) * 8 + 1,
+            _ => self.col += 1,
+        }
         if let Some(ch) = self.input.next() {
             ch
         } else {
@@ -457,6 +622,63 @@ impl<'a> Tokenizer<'a> {
 			"include" => (HEADER_INCLUDE,BASE_PREP),
 			"define" => (HEADER_DEFINE,BASE_PREP),
             "sizeof" => (OP_SIZEOF,BASE_UNOP),
+            "_Bool" => (PRIMITIVE_BOOL, BASE_DATATYPE),
+            "wchar_t" => (PRIMITIVE_WCHAR, BASE_DATATYPE),
+            "char16_t" => (PRIMITIVE_CHAR16, BASE_DATATYPE),
+            "char32_t" => (PRIMITIVE_CHAR32, BASE_DATATYPE),
+            "nullptr" => (NULL, BASE_NONE),
+            // specifiers and qualifiers go along with the type
+            "extern" => (KEYWORD_EXTERN, BASE_DATATYPE),
+            "inline" => (KEYWORD_INLINE, BASE_DATATYPE),
+            "register" => (KEYWORD_REGISTER, BASE_DATATYPE),
+            "volatile" => (KEYWORD_VOLATILE, BASE_DATATYPE),
+            "restrict" => (KEYWORD_RESTRICT, BASE_DATATYPE),
+            "constexpr" => (KEYWORD_CONSTEXPR, BASE_DATATYPE),
+            "mutable" => (KEYWORD_MUTABLE, BASE_DATATYPE),
+            "explicit" => (KEYWORD_EXPLICIT, BASE_DATATYPE),
+            "virtual" => (KEYWORD_VIRTUAL, BASE_DATATYPE),
+            "friend" => (KEYWORD_FRIEND, BASE_DATATYPE),
+            "_Noreturn" => (KEYWORD_NORETURN, BASE_DATATYPE),
+            "thread_local" | "_Thread_local" => (KEYWORD_THREAD_LOCAL, BASE_DATATYPE),
+            "_Atomic" => (KEYWORD_ATOMIC, BASE_DATATYPE),
+            "_Complex" => (KEYWORD_COMPLEX, BASE_DATATYPE),
+            "_Imaginary" => (KEYWORD_IMAGINARY, BASE_DATATYPE),
+            "alignas" | "_Alignas" => (KEYWORD_ALIGNAS, BASE_DATATYPE),
+            "alignof" | "_Alignof" => (KEYWORD_ALIGNOF, BASE_NONE),
+            "_Generic" => (KEYWORD_GENERIC, BASE_NONE),
+            "static_assert" | "_Static_assert" => (KEYWORD_STATIC_ASSERT, BASE_NONE),
+            "goto" => (KEYWORD_GOTO, BASE_NONE),
+            "namespace" => (KEYWORD_NAMESPACE, BASE_NONE),
+            "using" => (KEYWORD_USING, BASE_NONE),
+            "template" => (KEYWORD_TEMPLATE, BASE_NONE),
+            "typename" => (KEYWORD_TYPENAME, BASE_NONE),
+            "operator" => (KEYWORD_OPERATOR, BASE_NONE),
+            "this" => (KEYWORD_THIS, BASE_NONE),
+            "delete" => (KEYWORD_DELETE, BASE_NONE),
+            "try" => (KEYWORD_TRY, BASE_NONE),
+            "catch" => (KEYWORD_CATCH, BASE_NONE),
+            "throw" => (KEYWORD_THROW, BASE_NONE),
+            "noexcept" => (KEYWORD_NOEXCEPT, BASE_NONE),
+            "decltype" => (KEYWORD_DECLTYPE, BASE_NONE),
+            "typeid" => (KEYWORD_TYPEID, BASE_NONE),
+            "asm" => (KEYWORD_ASM, BASE_NONE),
+            "export" => (KEYWORD_EXPORT, BASE_NONE),
+            "static_cast" => (KEYWORD_STATIC_CAST, BASE_NONE),
+            "const_cast" => (KEYWORD_CONST_CAST, BASE_NONE),
+            "reinterpret_cast" => (KEYWORD_REINTERPRET_CAST, BASE_NONE),
+            "dynamic_cast" => (KEYWORD_DYNAMIC_CAST, BASE_NONE),
+            // C++ alternative spellings of operators
+            "and" => (OP_LOGAND, BASE_BINOP),
+            "or" => (OP_LOGOR, BASE_BINOP),
+            "not" => (OP_LOGNOT, BASE_UNOP),
+            "not_eq" => (OP_NEQ, BASE_BINOP),
+            "bitand" => (OP_BITAND, BASE_BINOP),
+            "bitor" => (OP_BITOR, BASE_BINOP),
+            "xor" => (OP_BITXOR, BASE_BINOP),
+            "compl" => (OP_BITNEG, BASE_UNOP),
+            "and_eq" => (OP_BITANDEQU, BASE_ASSIGNOP),
+            "or_eq" => (OP_BITOREQU, BASE_ASSIGNOP),
+            "xor_eq" => (OP_BITXOREQU, BASE_ASSIGNOP),
 			_ => (IDENTIFIER, BASE_NONE),
         }
     }
@@ -467,18 +689,101 @@ impl<'a> Tokenizer<'a> {
     fn push_to_tok_buffer(&mut self, tok_type: Type, base_type: Type) {
         let token: String = self.token.iter().cloned().collect();
         if !token.is_empty() {
-            let t = Token::new(token, base_type, tok_type, self.line_no, self.id);
+            let mut t = Token::new(token, base_type, tok_type, self.line_no - self.joined, self.id);
+            t.set_span(Span { end: self.offset, ..self.start });
             self.token_buffer.push(t);
             self.id += 1;
         }
         self.token.clear();
     }
 
+    // push_number:
+    // lexes an integer or floating literal starting at current_char
+    // and records its radix and suffix on the token
+    //
+    fn push_number(&mut self) {
+        let mut radix = Radix::Decimal;
+        let mut is_int = true;
+        if self.current_char == '0' {
+            self.push_advance();
+            match self.current_char {
+                'x' | 'X' => {
+                    self.push_advance();
+                    radix = Radix::Hex;
+                }
+                'b' | 'B' => {
+                    self.push_advance();
+                    radix = Radix::Binary;
+                }
+                '0'..='9' | '\'' => radix = Radix::Octal,
+                _ => {}
+            }
+        }
+        loop {
+            let is_digit = |c: char| if radix == Radix::Hex {
+                c.is_ascii_hexdigit()
+            } else {
+                c.is_ascii_digit()
+            };
+            match self.current_char {
+                c if is_digit(c) => self.push_advance(),
+                // C++14 digit separator
+                '\'' if self.input.clone().next().is_some_and(is_digit) => self.push_advance(),
+                '.' if radix != Radix::Binary => {
+                    self.push_advance();
+                    is_int = false;
+                }
+                'e' | 'E' if radix != Radix::Hex => {
+                    self.push_exponent();
+                    is_int = false;
+                }
+                'p' | 'P' if radix == Radix::Hex => {
+                    self.push_exponent();
+                    is_int = false;
+                }
+                _ => break,
+            }
+        }
+        // a leading zero makes floats like 01.5 decimal, not octal
+        if radix == Radix::Octal && !is_int {
+            radix = Radix::Decimal;
+        }
+        let digits = self.token.len();
+        while self.current_char.is_ascii_alphanumeric() || self.current_char == '_' {
+            self.push_advance();
+        }
+        let suffix: String = self.token[digits..].iter().cloned().collect();
+        if is_int {
+            self.push_to_tok_buffer(NUM_INT, BASE_VALUE);
+        } else {
+            self.push_to_tok_buffer(NUM_FLOAT, BASE_VALUE);
+        }
+        if let Some(tok) = self.token_buffer.last_mut() {
+            tok.set_number_form(radix, &suffix);
+        }
+    }
+
+    // exponent marker with an optional sign
+    fn push_exponent(&mut self) {
+        self.push_advance();
+        if self.current_char == '+' || self.current_char == '-' {
+            self.push_advance();
+        }
+    }
+
     // push_advance:
     // push the char token passed to it onto self.token
     // gets next char and stores it in self.current_char
     //
     fn push_advance(&mut self) {
+        if self.token.is_empty() {
+            self.start = Span {
+                start: self.offset,
+                end: self.offset,
+                line: self.line_no + 1,
+                col: self.col,
+            };
+        }
         self.token.push(self.current_char);
         self.current_char = self.get_next_char();
     }
@@ -504,6 +809,105 @@ impl<'a> Tokenizer<'a> {
 }
 
 
+// unescape:
+// decodes the escape sequences in the body of a C char or
+// string literal, the quotes are not part of body
+//
+pub fn unescape(body: &str) -> String {
+    decode(body).into_iter().map(|(value, _)| ::std::char::from_u32(value).unwrap_or('\u{FFFD}')).collect()
+}
+
+// unescape_bytes:
+// the bytes the body of a C string literal stands for. Characters
+// and universal character names are UTF-8 encoded, while \x and
+// octal escapes give a single byte
+//
+pub fn unescape_bytes(body: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (value, byte) in decode(body) {
+        if byte {
+            out.push(value as u8);
+        } else {
+            let c = ::std::char::from_u32(value).unwrap_or('\u{FFFD}');
+            out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+    }
+    out
+}
+
+// values of the characters in body, marking the ones
+// given by a byte escape
+fn decode(body: &str) -> Vec<(u32, bool)> {
+    let mut out = Vec::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push((c as u32, false));
+            continue;
+        }
+        let esc = match chars.next() {
+            Some(e) => e,
+            None => break,
+        };
+        // an escaped newline only continues the literal
+        if esc == '\n' || esc == '\r' && chars.peek() == Some(&'\n') {
+            if esc == '\r' {
+                chars.next();
+            }
+            continue;
+        }
+        let value = match esc {
+            'n' => 0x0A,
+            't' => 0x09,
+            'r' => 0x0D,
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0C,
+            'v' => 0x0B,
+            'e' => 0x1B,
+            '0'..='7' => {
+                let mut value = esc.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|d| d.to_digit(8)) {
+                        Some(d) => {
+                            value = value * 8 + d;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                value
+            }
+            'x' | 'u' | 'U' => {
+                let max = match esc {
+                    'u' => 4,
+                    'U' => 8,
+                    _ => usize::MAX,
+                };
+                let mut value: u32 = 0;
+                let mut count = 0;
+                while count < max {
+                    match chars.peek().and_then(|d| d.to_digit(16)) {
+                        Some(d) => {
+                            value = value.wrapping_mul(16).wrapping_add(d);
+                            chars.next();
+                            count += 1;
+                        }
+                        None => break,
+                    }
+                }
+                value
+            }
+            // \\ \' \" \? and unknown escapes stand for the character itself
+            other => other as u32,
+        };
+        let byte = matches!(esc, '0'..='7' | 'x');
+        out.push((if byte { value & 0xFF } else { value }, byte));
+    }
+    out
+}
+
+
 #[cfg(test)]
 mod test {
     use std::fs::File;
@@ -512,7 +916,7 @@ mod test {
     use std::io::BufReader;
     use library::lexer;
     use library::lexeme::Type::*;
-    use library::lexeme::Token;
+    use library::lexeme::{Radix, Span, Token, Type};
 
     fn read_file(path: &str) -> String {
         let file = match File::open(path) {
@@ -655,7 +1059,44 @@ mod test {
                  Token::new(String::from("const"), BASE_NONE, KEYWORD_CONST, 17, 17),
                  Token::new(String::from("default"), BASE_NONE, KEYWORD_DEFAULT, 18, 18),
                  Token::new(String::from("return"), BASE_NONE, KEYWORD_RETURN, 19, 19)];
-        assert_eq!(tok_vector, tok.tokenize());
+        assert_eq!(Ok(tok_vector), tok.tokenize());
+    }
+
+    #[test]
+    fn test_tokenize_extended_keywords() {
+        let types = |text: &str| -> Vec<(Type, Type)> {
+            let tokens = lexer::Tokenizer::new(text).tokenize().unwrap();
+            tokens.iter().map(|t| (t.get_base_type(), t.get_token_type())).collect()
+        };
+        assert_eq!(types("extern inline volatile constexpr _Thread_local thread_local"),
+                   vec![(BASE_DATATYPE, KEYWORD_EXTERN),
+                        (BASE_DATATYPE, KEYWORD_INLINE),
+                        (BASE_DATATYPE, KEYWORD_VOLATILE),
+                        (BASE_DATATYPE, KEYWORD_CONSTEXPR),
+                        (BASE_DATATYPE, KEYWORD_THREAD_LOCAL),
+                        (BASE_DATATYPE, KEYWORD_THREAD_LOCAL)]);
+        assert_eq!(types("_Bool wchar_t char32_t"),
+                   vec![(BASE_DATATYPE, PRIMITIVE_BOOL),
+                        (BASE_DATATYPE, PRIMITIVE_WCHAR),
+                        (BASE_DATATYPE, PRIMITIVE_CHAR32)]);
+        assert_eq!(types("namespace template typename this goto try catch throw"),
+                   vec![(BASE_NONE, KEYWORD_NAMESPACE),
+                        (BASE_NONE, KEYWORD_TEMPLATE),
+                        (BASE_NONE, KEYWORD_TYPENAME),
+                        (BASE_NONE, KEYWORD_THIS),
+                        (BASE_NONE, KEYWORD_GOTO),
+                        (BASE_NONE, KEYWORD_TRY),
+                        (BASE_NONE, KEYWORD_CATCH),
+                        (BASE_NONE, KEYWORD_THROW)]);
+        assert_eq!(types("nullptr and not_eq compl"),
+                   vec![(BASE_NONE, NULL),
+                        (BASE_BINOP, OP_LOGAND),
+                        (BASE_BINOP, OP_NEQ),
+                        (BASE_UNOP, OP_BITNEG)]);
+        let tokens = lexer::Tokenizer::new("sizeof...(Args) sizeof(x)").tokenize().unwrap();
+        assert_eq!(tokens[0].get_token_value(), "sizeof...");
+        assert_eq!(tokens[0].get_token_type(), KEYWORD_SIZEOF_PACK);
+        assert_eq!(tokens[4].get_token_type(), OP_SIZEOF);
     }
 
     #[test]
@@ -676,7 +1117,7 @@ mod test {
                  Token::new(String::from("bool"), BASE_DATATYPE, PRIMITIVE_BOOL, 6, 6),
                  Token::new(String::from("void"), BASE_DATATYPE, PRIMITIVE_VOID, 7, 7),
                  Token::new(String::from("typedef"), BASE_NONE, PRIMITIVE_TYPEDEF, 8, 8)];
-        assert_eq!(tok_vector, tok.tokenize());
+        assert_eq!(Ok(tok_vector), tok.tokenize());
     }
 
     #[test]
@@ -698,7 +1139,7 @@ mod test {
                                          COMMENT_SINGLE,
                                          2,
                                          2)];
-        assert_eq!(tok_vector, tok.tokenize());
+        assert_eq!(Ok(tok_vector), tok.tokenize());
     }
 
     #[test]
@@ -733,7 +1174,7 @@ mod test {
                               Token::new(String::from("-="), BASE_ASSIGNOP, OP_MINEQU, 23, 23),
                               Token::new(String::from("/="), BASE_ASSIGNOP, OP_DIVEQU, 24, 24),
                               Token::new(String::from("%="), BASE_ASSIGNOP, OP_MODEQU, 25, 25)];
-        assert_eq!(tok_vector, tok.tokenize());
+        assert_eq!(Ok(tok_vector), tok.tokenize());
     }
 
     #[test]
@@ -749,7 +1190,154 @@ mod test {
                               Token::new(String::from(":"), BASE_NONE, COLON, 6, 6),
                               Token::new(String::from(";"), BASE_NONE, SEMICOLON, 7, 7),
                               Token::new(String::from(","), BASE_NONE, COMMA, 8, 8)];
-        assert_eq!(tok_vector, tok.tokenize());
+        assert_eq!(Ok(tok_vector), tok.tokenize());
+    }
+
+    #[test]
+    fn test_tokenize_ternary() {
+        let mut tok = lexer::Tokenizer::new("a ? b : c");
+        let tok_vector = vec![Token::new(String::from("a"), BASE_NONE, IDENTIFIER, 0, 0),
+                              Token::new(String::from("?"), BASE_NONE, OP_TERNARY, 0, 1),
+                              Token::new(String::from("b"), BASE_NONE, IDENTIFIER, 0, 2),
+                              Token::new(String::from(":"), BASE_NONE, COLON, 0, 3),
+                              Token::new(String::from("c"), BASE_NONE, IDENTIFIER, 0, 4)];
+        assert_eq!(Ok(tok_vector), tok.tokenize());
+    }
+
+    #[test]
+    fn test_tokenize_compound_operators() {
+        let mut tok = lexer::Tokenizer::new("-> :: . ^ ^= &= |= <<= >>= ...");
+        let tok_vector = vec![Token::new(String::from("->"), BASE_NONE, OP_ARROW, 0, 0),
+                              Token::new(String::from("::"), BASE_NONE, OP_SCOPE, 0, 1),
+                              Token::new(String::from("."), BASE_NONE, OP_DOT, 0, 2),
+                              Token::new(String::from("^"), BASE_BINOP, OP_BITXOR, 0, 3),
+                              Token::new(String::from("^="), BASE_ASSIGNOP, OP_BITXOREQU, 0, 4),
+                              Token::new(String::from("&="), BASE_ASSIGNOP, OP_BITANDEQU, 0, 5),
+                              Token::new(String::from("|="), BASE_ASSIGNOP, OP_BITOREQU, 0, 6),
+                              Token::new(String::from("<<="),
+                                         BASE_ASSIGNOP,
+                                         OP_BITLSHIFTEQU,
+                                         0,
+                                         7),
+                              Token::new(String::from(">>="),
+                                         BASE_ASSIGNOP,
+                                         OP_BITRSHIFTEQU,
+                                         0,
+                                         8),
+                              Token::new(String::from("..."), BASE_NONE, OP_ELLIPSIS, 0, 9)];
+        assert_eq!(Ok(tok_vector), tok.tokenize());
+    }
+
+    #[test]
+    fn test_tokenize_number_forms() {
+        let mut tok = lexer::Tokenizer::new("0x1Fu 017 0b101 1e-3 .5 10UL 3.0f 1'000");
+        let forms: Vec<(String, Type, Radix, String)> = tok.tokenize()
+            .unwrap()
+            .iter()
+            .map(|t| (t.get_token_value(), t.get_token_type(), t.get_radix(), t.get_suffix()))
+            .collect();
+        let expected = vec![("0x1Fu", NUM_INT, Radix::Hex, "u"),
+                            ("017", NUM_INT, Radix::Octal, ""),
+                            ("0b101", NUM_INT, Radix::Binary, ""),
+                            ("1e-3", NUM_FLOAT, Radix::Decimal, ""),
+                            (".5", NUM_FLOAT, Radix::Decimal, ""),
+                            ("10UL", NUM_INT, Radix::Decimal, "UL"),
+                            ("3.0f", NUM_FLOAT, Radix::Decimal, "f"),
+                            ("1'000", NUM_INT, Radix::Decimal, "")];
+        let expected: Vec<(String, Type, Radix, String)> = expected.into_iter()
+            .map(|(v, t, r, s)| (v.to_string(), t, r, s.to_string()))
+            .collect();
+        assert_eq!(expected, forms);
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        let error = |text: &str| lexer::Tokenizer::new(text).tokenize().unwrap_err();
+        assert_eq!(error("a = \"abc;\nb = 1;"),
+                   lexer::LexError {
+                       msg: String::from("unterminated string literal"),
+                       line: 1,
+                       col: 5,
+                   });
+        assert_eq!(error("c = 'a"),
+                   lexer::LexError {
+                       msg: String::from("unterminated character literal"),
+                       line: 1,
+                       col: 5,
+                   });
+        assert_eq!(error("int a;\n/* open"),
+                   lexer::LexError {
+                       msg: String::from("unterminated comment"),
+                       line: 2,
+                       col: 1,
+                   });
+        assert_eq!(error("int a; // c\r\nint b = @;"),
+                   lexer::LexError {
+                       msg: String::from("stray `@` in program"),
+                       line: 2,
+                       col: 9,
+                   });
+        assert_eq!(error("x = @y;"),
+                   lexer::LexError {
+                       msg: String::from("stray `@` in program"),
+                       line: 1,
+                       col: 5,
+                   });
+        assert_eq!(error("s = \"\\q\";"),
+                   lexer::LexError {
+                       msg: String::from("unknown escape sequence `\\q`"),
+                       line: 1,
+                       col: 6,
+                   });
+    }
+
+    #[test]
+    fn test_line_continuation() {
+        let text = "#define TWICE(x) \\\r\n  ((x) * 2)\r\nchar *s = \"a\\\r\nb\";\r\nint y;\n";
+        let tokens = lexer::Tokenizer::new(text).tokenize().unwrap();
+        let values: Vec<String> = tokens.iter().map(|t| t.get_token_value()).collect();
+        assert_eq!(values, vec!["#", "define", "TWICE", "(", "x", ")", "(", "(", "x", ")", "*", "2", ")", "char", "*", "s",
+                                "=", "\"a\\\r\nb\"", ";", "int", "y", ";"]);
+        assert_eq!(tokens[12].get_span().line, 2);
+        assert_eq!(tokens[19].get_span().line, 5);
+        assert_eq!(lexer::unescape("a\\\r\nb\\\nc"), "abc");
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let spans: Vec<Span> = lexer::Tokenizer::new("s = \"é\";\n\tx = 1;")
+            .tokenize()
+            .unwrap()
+            .iter()
+            .map(|t| t.get_span())
+            .collect();
+        let span = |start, end, line, col| {
+            Span {
+                start,
+                end,
+                line,
+                col,
+            }
+        };
+        assert_eq!(spans,
+                   vec![span(0, 1, 1, 1),
+                        span(2, 3, 1, 3),
+                        span(4, 8, 1, 5),
+                        span(8, 9, 1, 8),
+                        span(11, 12, 2, 9),
+                        span(13, 14, 2, 11),
+                        span(15, 16, 2, 13),
+                        span(16, 17, 2, 14)]);
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(lexer::unescape("\\x41\\101\\0"), "AA\0");
+        assert_eq!(lexer::unescape("\\a\\v\\e\\?"), "\x07\x0B\x1B?");
+        assert_eq!(lexer::unescape("it\\'s \\\"q\\\" \\\\"), "it's \"q\" \\");
+        assert_eq!(lexer::unescape("\\u00e9"), "\u{e9}");
+        assert_eq!(lexer::unescape_bytes("\\xFF\\u00e9\\101é"), vec![0xFF, 0xC3, 0xA9, 0x41, 0xC3, 0xA9]);
+        assert_eq!(lexer::unescape_bytes("\\xC3\\251"), "é".as_bytes());
     }
 
     #[test]
@@ -773,7 +1361,7 @@ mod test {
                                          6),
                               Token::new(String::from("true"), BASE_VALUE, TRUE_VAL, 7, 7),
                               Token::new(String::from("false"), BASE_VALUE, FALSE_VAL, 8, 8)];
-        assert_eq!(tok_vector, tok.tokenize());
+        assert_eq!(Ok(tok_vector), tok.tokenize());
     }
 
     #[test]
@@ -790,6 +1378,6 @@ mod test {
                  Token::new(String::from("abcd_deff04_"), BASE_NONE, IDENTIFIER, 2, 2),
                  Token::new(String::from("inte"), BASE_NONE, IDENTIFIER, 3, 3),
                  Token::new(String::from("main"), BASE_NONE, MAIN, 4, 4)];
-        assert_eq!(tok_vector, tok.tokenize());
+        assert_eq!(Ok(tok_vector), tok.tokenize());
     }
 }
@@ -1,4 +1,12 @@
 pub mod lexer;
 pub mod lexeme;
+pub mod preprocessor;
+pub mod ast;
 pub mod parser;
+pub mod codegen;
+pub mod headers;
+pub mod naming;
+pub mod mutability;
+pub mod initialization;
+pub mod sequencing;
 pub mod doc;
@@ -0,0 +1,445 @@
+use library::ast::*;
+
+// what a name in scope refers to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    // a variable or parameter, numbered in declaration order
+    Var(usize),
+    // a parameter of the macro being analysed
+    MacroParam(usize),
+    // a field used inside a method
+    Field,
+}
+
+#[derive(Debug)]
+struct Binding {
+    name: String,
+    target: Target,
+    is_pointer: bool,
+    // a deferred `let x;`, its plain assignments are checked by the
+    // initialization pass, which marks it mut when it is assigned twice
+    deferred: bool,
+}
+
+// what expanding a function-like macro writes
+#[derive(Debug)]
+struct MacroWrites {
+    name: String,
+    params: Vec<usize>,
+    names: Vec<String>,
+}
+
+// counts the writes to every variable
+struct Writes {
+    scopes: Vec<Vec<Binding>>,
+    next: usize,
+    written: Vec<bool>,
+    macros: Vec<MacroWrites>,
+    // writes found in the body of the macro being analysed
+    macro_params: Vec<usize>,
+    macro_names: Vec<String>,
+}
+
+
+/**
+ * infer:
+ * marks the variables and parameters which are written after their
+ * initialization, by assignment, `++`/`--`, taking their address or
+ * a macro doing so
+ */
+pub fn infer(program: &mut Program) {
+    let mut writes = Writes {
+        scopes: vec![Vec::new()],
+        next: 0,
+        written: Vec::new(),
+        macros: Vec::new(),
+        macro_params: Vec::new(),
+        macro_names: Vec::new(),
+    };
+    for decl in &program.decls {
+        writes.decl(decl);
+    }
+    let mut next = 0;
+    for decl in &mut program.decls {
+        mark_decl(decl, &writes.written, &mut next);
+    }
+}
+
+
+impl Writes {
+    fn declare(&mut self, name: &str, ty: &Type, deferred: bool) {
+        self.scopes.last_mut().unwrap().push(Binding {
+            name: name.to_string(),
+            target: Target::Var(self.next),
+            // unsized array parameters are passed by reference
+            is_pointer: matches!(*ty, Type::Pointer(_) | Type::Array(_, None)),
+            deferred,
+        });
+        self.written.push(false);
+        self.next += 1;
+    }
+
+    fn bind(&mut self, name: &str, target: Target) {
+        self.scopes.last_mut().unwrap().push(Binding {
+            name: name.to_string(),
+            target,
+            is_pointer: false,
+            deferred: false,
+        });
+    }
+
+    fn scoped<F: FnOnce(&mut Writes)>(&mut self, walk: F) {
+        self.scopes.push(Vec::new());
+        walk(self);
+        self.scopes.pop();
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes.iter_mut().rev().flat_map(|scope| scope.iter_mut().rev()).find(|b| b.name == name)
+    }
+
+    fn is_deferred(&mut self, lvalue: &Expr) -> bool {
+        match lvalue.kind {
+            ExprKind::Ident(ref name) => self.lookup(name).is_some_and(|b| b.deferred),
+            _ => false,
+        }
+    }
+
+    // records a write to the variable an lvalue is stored in
+    fn write(&mut self, lvalue: &Expr) {
+        match lvalue.kind {
+            ExprKind::Ident(ref name) => self.write_name(name, false),
+            // writing through a pointer leaves the pointer itself as it is
+            ExprKind::Member(ref e, _) | ExprKind::Index(ref e, _) => {
+                if let ExprKind::Ident(ref name) = e.kind {
+                    self.write_name(name, true);
+                } else {
+                    self.write(e);
+                }
+            }
+            ExprKind::Cast(_, ref e) => self.write(e),
+            ExprKind::Comma(ref list) => {
+                if let Some(last) = list.last() {
+                    self.write(last);
+                }
+            }
+            ExprKind::Cond(_, ref t, ref o) => {
+                self.write(t);
+                self.write(o);
+            }
+            _ => {}
+        }
+    }
+
+    fn write_name(&mut self, name: &str, through: bool) {
+        let target = match self.lookup(name) {
+            Some(ref b) if through && b.is_pointer => return,
+            Some(b) => b.target,
+            None => {
+                self.macro_names.push(name.to_string());
+                return;
+            }
+        };
+        match target {
+            Target::Var(id) => self.written[id] = true,
+            Target::MacroParam(i) => self.macro_params.push(i),
+            Target::Field => {}
+        }
+    }
+
+    fn function(&mut self, func: &Function, fields: &[Field]) {
+        self.scoped(|w| {
+            for field in fields {
+                w.bind(&field.name, Target::Field);
+            }
+            w.scoped(|w| {
+                for param in &func.params {
+                    w.declare(&param.name, &param.ty, false);
+                }
+                if let Some(ref body) = func.body {
+                    for stmt in body {
+                        w.stmt(stmt);
+                    }
+                }
+            });
+        });
+    }
+
+    fn decl(&mut self, decl: &Decl) {
+        match *decl {
+            Decl::Var(ref vars) => {
+                for var in vars {
+                    if let Some(ref init) = var.init {
+                        self.expr(init);
+                    }
+                    let deferred = var.init.is_none() && !var.default_init && !matches!(var.ty, Type::Named(_));
+                    self.declare(&var.name, &var.ty, deferred);
+                }
+            }
+            Decl::Function(ref func) => self.function(func, &[]),
+            Decl::Record(ref record) => {
+                for method in &record.methods {
+                    self.function(&method.func, &record.fields);
+                }
+            }
+            Decl::Macro(ref def) => self.macro_def(def),
+            _ => {}
+        }
+    }
+
+    // finds the parameters and outer names a macro writes
+    fn macro_def(&mut self, def: &MacroDef) {
+        let (outer_params, outer_names) = (self.macro_params.split_off(0), self.macro_names.split_off(0));
+        self.scoped(|w| {
+            for (i, param) in def.params.iter().enumerate() {
+                w.bind(param, Target::MacroParam(i));
+            }
+            match def.body {
+                MacroBody::Expr(ref body) => w.expr(body),
+                MacroBody::Stmt(ref body) => w.stmt(body),
+            }
+        });
+        let params = std::mem::replace(&mut self.macro_params, outer_params);
+        let names = std::mem::replace(&mut self.macro_names, outer_names);
+        self.macros.push(MacroWrites {
+            name: def.name.clone(),
+            params,
+            names,
+        });
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt.kind {
+            StmtKind::Decl(ref decl) => self.decl(decl),
+            StmtKind::Expr(ref e) | StmtKind::Return(Some(ref e)) => self.expr(e),
+            StmtKind::Block(ref stmts) => {
+                self.scoped(|w| {
+                    for s in stmts {
+                        w.stmt(s);
+                    }
+                })
+            }
+            StmtKind::If(ref c, ref t, ref o) => {
+                self.scoped(|w| {
+                    w.expr(c);
+                    w.scoped(|w| w.stmt(t));
+                    if let Some(ref o) = *o {
+                        w.scoped(|w| w.stmt(o));
+                    }
+                })
+            }
+            StmtKind::While(ref c, ref body) | StmtKind::DoWhile(ref body, ref c) => {
+                self.scoped(|w| {
+                    w.expr(c);
+                    w.scoped(|w| w.stmt(body));
+                })
+            }
+            StmtKind::For(ref init, ref c, ref u, ref body) => {
+                self.scoped(|w| {
+                    if let Some(ref init) = *init {
+                        w.stmt(init);
+                    }
+                    for e in c.iter().chain(u.iter()) {
+                        w.expr(e);
+                    }
+                    w.scoped(|w| w.stmt(body));
+                })
+            }
+            StmtKind::Switch(ref c, ref cases) => {
+                self.scoped(|w| {
+                    w.expr(c);
+                    for case in cases {
+                        for label in &case.labels {
+                            w.expr(label);
+                        }
+                        for s in &case.body {
+                            w.stmt(s);
+                        }
+                    }
+                })
+            }
+            _ => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr.kind {
+            ExprKind::Assign(op, ref l, ref r) => {
+                if op.is_some() || !self.is_deferred(l) {
+                    self.write(l);
+                }
+                self.expr(l);
+                self.expr(r);
+            }
+            ExprKind::Unary(op, ref e) => {
+                match op {
+                    UnOp::PreInc | UnOp::PreDec | UnOp::PostInc | UnOp::PostDec | UnOp::AddrOf => self.write(e),
+                    _ => {}
+                }
+                self.expr(e);
+            }
+            // cin >> a >> b reads into a and b
+            ExprKind::Binary(BinOp::Shr, ref l, ref r) if reads_from_cin(l) => {
+                self.write(r);
+                self.expr(l);
+                self.expr(r);
+            }
+            ExprKind::Call(ref f, ref args) => {
+                if let ExprKind::Ident(ref name) = f.kind {
+                    let (params, names) = match self.macros.iter().find(|m| m.name == *name) {
+                        Some(m) => (m.params.clone(), m.names.clone()),
+                        None => (Vec::new(), Vec::new()),
+                    };
+                    for i in params {
+                        if let Some(arg) = args.get(i) {
+                            self.write(arg);
+                        }
+                    }
+                    for name in names {
+                        self.write_name(&name, false);
+                    }
+                }
+                self.expr(f);
+                for a in args {
+                    self.expr(a);
+                }
+            }
+            ExprKind::Member(ref e, _) | ExprKind::Cast(_, ref e) | ExprKind::SizeofExpr(ref e) => self.expr(e),
+            ExprKind::Binary(_, ref l, ref r) | ExprKind::Index(ref l, ref r) => {
+                self.expr(l);
+                self.expr(r);
+            }
+            ExprKind::Cond(ref c, ref t, ref o) => {
+                self.expr(c);
+                self.expr(t);
+                self.expr(o);
+            }
+            ExprKind::InitList(ref list) | ExprKind::Comma(ref list) => {
+                for e in list {
+                    self.expr(e);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+
+// checks for `cin >> a` chains
+pub fn reads_from_cin(expr: &Expr) -> bool {
+    match expr.kind {
+        ExprKind::Ident(ref name) => name == "cin",
+        ExprKind::Binary(BinOp::Shr, ref l, _) => reads_from_cin(l),
+        _ => false,
+    }
+}
+
+
+// sets is_mut, numbering the variables in the same order as Writes
+fn mark_decl(decl: &mut Decl, written: &[bool], next: &mut usize) {
+    match *decl {
+        Decl::Var(ref mut vars) => {
+            for var in vars {
+                var.is_mut = var.is_mut || written[*next];
+                *next += 1;
+            }
+        }
+        Decl::Function(ref mut func) => mark_function(func, written, next),
+        Decl::Record(ref mut record) => {
+            for method in &mut record.methods {
+                mark_function(&mut method.func, written, next);
+            }
+        }
+        Decl::Macro(ref mut def) => {
+            if let MacroBody::Stmt(ref mut body) = def.body {
+                mark_stmt(body, written, next);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn mark_function(func: &mut Function, written: &[bool], next: &mut usize) {
+    for param in &mut func.params {
+        param.is_mut = written[*next];
+        *next += 1;
+    }
+    if let Some(ref mut body) = func.body {
+        for stmt in body {
+            mark_stmt(stmt, written, next);
+        }
+    }
+}
+
+fn mark_stmt(stmt: &mut Stmt, written: &[bool], next: &mut usize) {
+    match stmt.kind {
+        StmtKind::Decl(ref mut decl) => mark_decl(decl, written, next),
+        StmtKind::Block(ref mut stmts) => {
+            for s in stmts {
+                mark_stmt(s, written, next);
+            }
+        }
+        StmtKind::If(_, ref mut t, ref mut o) => {
+            mark_stmt(t, written, next);
+            if let Some(ref mut o) = *o {
+                mark_stmt(o, written, next);
+            }
+        }
+        StmtKind::While(_, ref mut body) | StmtKind::DoWhile(ref mut body, _) => mark_stmt(body, written, next),
+        StmtKind::For(ref mut init, _, _, ref mut body) => {
+            if let Some(ref mut init) = *init {
+                mark_stmt(init, written, next);
+            }
+            mark_stmt(body, written, next);
+        }
+        StmtKind::Switch(_, ref mut cases) => {
+            for case in cases {
+                for s in &mut case.body {
+                    mark_stmt(s, written, next);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use library::ast::*;
+    use library::lexer;
+    use library::parser::{self, Options};
+
+    // names marked mutable at file scope and in the outer block of functions
+    fn mutable_names(source: &str) -> Vec<String> {
+        let tokens = lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = parser::init_parser(&tokens, &Options::default());
+        let mut names = Vec::new();
+        for decl in &program.decls {
+            match *decl {
+                Decl::Var(ref vars) => names.extend(vars.iter().filter(|v| v.is_mut).map(|v| v.name.clone())),
+                Decl::Function(ref func) => {
+                    names.extend(func.params.iter().filter(|p| p.is_mut).map(|p| p.name.clone()));
+                    for stmt in func.body.as_ref().unwrap() {
+                        if let StmtKind::Decl(Decl::Var(ref vars)) = stmt.kind {
+                            names.extend(vars.iter().filter(|v| v.is_mut).map(|v| v.name.clone()));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn test_infer() {
+        let source = "int total = 0;\nint limit = 10;\n\
+                      int f(int n, int *p, int k) {\n\
+                      int a = 1; int b = 2; int c; int d; int e = 0;\n\
+                      c = a; d = 1; d = 2; b += n; total++; *p = a; k--;\n\
+                      int *q = &e; { int a = 3; a = 4; }\n\
+                      return limit; }";
+        assert_eq!(mutable_names(source), vec!["total", "k", "b", "d", "e"]);
+    }
+}
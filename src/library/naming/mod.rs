@@ -0,0 +1,405 @@
+use library::ast::*;
+use library::parser::{RENAMED, RUST_KEYWORDS};
+
+// naming convention a declared name should follow in Rust
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Convention {
+    // functions, methods, locals and fields
+    Snake,
+    // types and enum variants
+    Camel,
+    // constants and statics
+    Screaming,
+}
+
+
+/**
+ * words:
+ * splits a name at underscores and case changes,
+ * `getHTTPResponse2` gives get, HTTP and Response2
+ */
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(word.clone());
+                word.clear();
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // aB or the B of ABc
+            if prev.is_lowercase() || prev.is_ascii_digit() || prev.is_uppercase() && next_lower {
+                words.push(word.clone());
+                word.clear();
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+
+/**
+ * convert:
+ * returns name written in the given case, None if it already is
+ * or the converted name cannot be used. Leading and trailing
+ * underscores are kept, keywords become raw identifiers
+ */
+pub fn convert(name: &str, case: Convention) -> Option<String> {
+    let bare = name.trim_start_matches("r#");
+    let core = bare.trim_matches('_');
+    if core.is_empty() {
+        return None;
+    }
+    let words = words(core);
+    let converted: Vec<String> = match case {
+        Convention::Snake => words.iter().map(|w| w.to_lowercase()).collect(),
+        Convention::Screaming => words.iter().map(|w| w.to_uppercase()).collect(),
+        Convention::Camel => {
+            words.iter()
+                .map(|w| {
+                    let mut chars = w.chars();
+                    let first = chars.next().unwrap();
+                    first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect()
+                })
+                .collect()
+        }
+    };
+    let separator = if case == Convention::Camel { "" } else { "_" };
+    let start = bare.len() - bare.trim_start_matches('_').len();
+    let end = bare.trim_end_matches('_').len();
+    let renamed = format!("{}{}{}", &bare[..start], converted.join(separator), &bare[end..]);
+    if renamed.starts_with(|c: char| c.is_ascii_digit()) || RENAMED.contains(&&renamed[..]) {
+        return None;
+    }
+    let renamed = if RUST_KEYWORDS.contains(&&renamed[..]) {
+        format!("r#{}", renamed)
+    } else {
+        renamed
+    };
+    if renamed == name { None } else { Some(renamed) }
+}
+
+
+/**
+ * rename_map:
+ * takes every declared name with its namespace and case and returns
+ * the renames to apply. A name declared with two different cases, or
+ * whose new name is taken by another one, is left as it is
+ */
+pub fn rename_map(declared: &[(String, NameSpace, Convention)]) -> Vec<Rename> {
+    let mut renames: Vec<Rename> = Vec::new();
+    for &(ref name, space, case) in declared {
+        let to = convert(name, case).unwrap_or_else(|| name.clone());
+        if !renames.iter().any(|r| r.from == *name && r.space == space) {
+            renames.push(Rename {
+                from: name.clone(),
+                to,
+                space,
+                c_name: name.clone(),
+            });
+        } else if renames.iter().any(|r| r.from == *name && r.space == space && r.to != to) {
+            for r in renames.iter_mut().filter(|r| r.from == *name && r.space == space) {
+                r.to = name.clone();
+            }
+        }
+    }
+    let clashes: Vec<usize> = (0..renames.len())
+        .filter(|&i| {
+            let r = &renames[i];
+            r.to != r.from &&
+            renames.iter().enumerate().any(|(j, o)| j != i && o.space == r.space && (o.to == r.to || o.from == r.to))
+        })
+        .collect();
+    for i in clashes {
+        renames[i].to = renames[i].from.clone();
+    }
+    renames.retain(|r| r.to != r.from);
+    renames
+}
+
+
+// applies renames to a syntax tree, values are the declared value names
+struct Renamer<'a> {
+    renames: &'a [Rename],
+    values: Vec<&'a str>,
+}
+
+/**
+ * apply:
+ * renames the declarations and every use of the names in renames.
+ * An identifier which is not a variable, function or constant may
+ * be a member used inside a method, it keeps its name when the
+ * member's new name is taken by a value
+ */
+pub fn apply(program: &mut Program, renames: &[Rename], declared: &[(String, NameSpace, Convention)]) {
+    let values = declared.iter().filter(|d| d.1 == NameSpace::Value).map(|d| &d.0[..]).collect();
+    let renamer = Renamer { renames, values };
+    for decl in &mut program.decls {
+        renamer.decl(decl);
+    }
+}
+
+impl<'a> Renamer<'a> {
+    fn find(&self, name: &str, space: NameSpace) -> Option<String> {
+        self.renames.iter().find(|r| r.space == space && r.from == name).map(|r| r.to.clone())
+    }
+
+    fn name(&self, name: &mut String, space: NameSpace) {
+        let found = match space {
+            NameSpace::Value => {
+                self.find(name, NameSpace::Value).or_else(|| {
+                    if self.values.contains(&&name[..]) {
+                        return None;
+                    }
+                    self.find(name, NameSpace::Member).filter(|to| {
+                        !self.values.contains(&&to[..]) &&
+                        !self.renames.iter().any(|r| r.space == NameSpace::Value && r.to == *to)
+                    })
+                })
+            }
+            _ => self.find(name, space),
+        };
+        if let Some(to) = found {
+            *name = to;
+        }
+    }
+
+    fn ty(&self, ty: &mut Type) {
+        match *ty {
+            Type::Named(ref mut name) => self.name(name, NameSpace::Type),
+            Type::Pointer(ref mut inner) | Type::Const(ref mut inner) => self.ty(inner),
+            Type::Array(ref mut inner, ref mut len) => {
+                self.ty(inner);
+                if let Some(ref mut len) = *len {
+                    self.expr(len);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn function(&self, func: &mut Function, space: NameSpace) {
+        self.name(&mut func.name, space);
+        self.ty(&mut func.ret);
+        for param in &mut func.params {
+            self.name(&mut param.name, NameSpace::Value);
+            self.ty(&mut param.ty);
+        }
+        if let Some(ref mut body) = func.body {
+            for stmt in body {
+                self.stmt(stmt);
+            }
+        }
+    }
+
+    fn decl(&self, decl: &mut Decl) {
+        match *decl {
+            Decl::Var(ref mut vars) => {
+                for var in vars {
+                    self.name(&mut var.name, NameSpace::Value);
+                    self.ty(&mut var.ty);
+                    if let Some(ref mut init) = var.init {
+                        self.expr(init);
+                    }
+                }
+            }
+            Decl::Function(ref mut func) => self.function(func, NameSpace::Value),
+            Decl::Record(ref mut record) => {
+                self.name(&mut record.name, NameSpace::Type);
+                for field in &mut record.fields {
+                    self.name(&mut field.name, NameSpace::Member);
+                    self.ty(&mut field.ty);
+                }
+                for method in &mut record.methods {
+                    self.function(&mut method.func, NameSpace::Member);
+                }
+            }
+            Decl::Enum(ref mut def) => {
+                self.name(&mut def.name, NameSpace::Type);
+                for variant in &mut def.variants {
+                    self.name(&mut variant.name, NameSpace::Value);
+                    if let Some(ref mut value) = variant.value {
+                        self.expr(value);
+                    }
+                }
+            }
+            Decl::Typedef(ref mut def) => {
+                self.name(&mut def.name, NameSpace::Type);
+                self.ty(&mut def.ty);
+            }
+            Decl::Const(ref mut def) => {
+                self.name(&mut def.name, NameSpace::Value);
+                self.ty(&mut def.ty);
+                self.expr(&mut def.value);
+            }
+            Decl::Macro(ref mut def) => {
+                self.name(&mut def.name, NameSpace::Value);
+                for param in &mut def.params {
+                    self.name(param, NameSpace::Value);
+                }
+                match def.body {
+                    MacroBody::Expr(ref mut body) => self.expr(body),
+                    MacroBody::Stmt(ref mut body) => self.stmt(body),
+                }
+                if let Some((ref mut types, ref mut ret)) = def.signature {
+                    for ty in types {
+                        self.ty(ty);
+                    }
+                    self.ty(ret);
+                }
+            }
+            Decl::Include(..) | Decl::Directive(..) | Decl::Comment(..) | Decl::Verbatim(..) => {}
+        }
+    }
+
+    fn stmt(&self, stmt: &mut Stmt) {
+        match stmt.kind {
+            StmtKind::Decl(ref mut decl) => self.decl(decl),
+            StmtKind::Expr(ref mut e) | StmtKind::Return(Some(ref mut e)) => self.expr(e),
+            StmtKind::Block(ref mut stmts) => {
+                for s in stmts {
+                    self.stmt(s);
+                }
+            }
+            StmtKind::If(ref mut c, ref mut t, ref mut o) => {
+                self.expr(c);
+                self.stmt(t);
+                if let Some(ref mut o) = *o {
+                    self.stmt(o);
+                }
+            }
+            StmtKind::While(ref mut c, ref mut body) | StmtKind::DoWhile(ref mut body, ref mut c) => {
+                self.expr(c);
+                self.stmt(body);
+            }
+            StmtKind::For(ref mut init, ref mut c, ref mut u, ref mut body) => {
+                if let Some(ref mut init) = *init {
+                    self.stmt(init);
+                }
+                for e in c.iter_mut().chain(u.iter_mut()) {
+                    self.expr(e);
+                }
+                self.stmt(body);
+            }
+            StmtKind::Switch(ref mut c, ref mut cases) => {
+                self.expr(c);
+                for case in cases {
+                    for label in &mut case.labels {
+                        self.expr(label);
+                    }
+                    for s in &mut case.body {
+                        self.stmt(s);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expr(&self, expr: &mut Expr) {
+        match expr.kind {
+            ExprKind::Ident(ref mut name) => self.name(name, NameSpace::Value),
+            ExprKind::Member(ref mut e, ref mut member) => {
+                self.expr(e);
+                self.name(member, NameSpace::Member);
+            }
+            ExprKind::Cast(ref mut ty, ref mut e) => {
+                self.ty(ty);
+                self.expr(e);
+            }
+            ExprKind::SizeofType(ref mut ty) => self.ty(ty),
+            ExprKind::Unary(_, ref mut e) | ExprKind::SizeofExpr(ref mut e) => self.expr(e),
+            ExprKind::Binary(_, ref mut l, ref mut r) | ExprKind::Assign(_, ref mut l, ref mut r) |
+            ExprKind::Index(ref mut l, ref mut r) => {
+                self.expr(l);
+                self.expr(r);
+            }
+            ExprKind::Cond(ref mut c, ref mut t, ref mut o) => {
+                self.expr(c);
+                self.expr(t);
+                self.expr(o);
+            }
+            ExprKind::Call(ref mut f, ref mut args) => {
+                self.expr(f);
+                for a in args {
+                    self.expr(a);
+                }
+            }
+            ExprKind::InitList(ref mut list) | ExprKind::Comma(ref mut list) => {
+                for e in list {
+                    self.expr(e);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use library::ast::{NameSpace, Rename};
+    use library::codegen::{generate, Mutability, Unit};
+    use library::lexer;
+    use library::naming::{convert, rename_map, Convention};
+    use library::parser::{init_parser, Options};
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(convert("getInt", Convention::Snake), Some("get_int".to_string()));
+        assert_eq!(convert("getHTTPResponse2", Convention::Snake), Some("get_http_response2".to_string()));
+        assert_eq!(convert("node_t", Convention::Camel), Some("NodeT".to_string()));
+        assert_eq!(convert("maxSize", Convention::Screaming), Some("MAX_SIZE".to_string()));
+        assert_eq!(convert("_private_", Convention::Screaming), Some("_PRIVATE_".to_string()));
+        assert_eq!(convert("count", Convention::Snake), None);
+        // keywords and names which cannot be used
+        assert_eq!(convert("Type", Convention::Snake), Some("r#type".to_string()));
+        assert_eq!(convert("r#match", Convention::Camel), Some("Match".to_string()));
+        assert_eq!(convert("string", Convention::Camel), None);
+        assert_eq!(convert("self_", Convention::Snake), None);
+    }
+
+    #[test]
+    fn test_rename_map() {
+        let declared = vec![("getInt".to_string(), NameSpace::Member, Convention::Snake),
+                            ("getInt".to_string(), NameSpace::Member, Convention::Snake),
+                            ("myVal".to_string(), NameSpace::Value, Convention::Snake),
+                            ("my_val".to_string(), NameSpace::Value, Convention::Snake),
+                            ("Limit".to_string(), NameSpace::Value, Convention::Screaming),
+                            ("Limit".to_string(), NameSpace::Value, Convention::Snake)];
+        assert_eq!(rename_map(&declared),
+                   vec![Rename {
+                            from: "getInt".to_string(),
+                            to: "get_int".to_string(),
+                            space: NameSpace::Member,
+                            c_name: "getInt".to_string(),
+                        }]);
+    }
+
+    #[test]
+    fn test_apply() {
+        let source = "struct s { int fooBar; };\n\
+                      class c { int countX; int get() { return countX; } };\n\
+                      int f(int fooBar) { int foo_bar = 1; int r = fooBar + foo_bar; return r; }\n";
+        let tokens = lexer::Tokenizer::new(source).tokenize().unwrap();
+        let options = Options {
+            naming: true,
+            ..Default::default()
+        };
+        let rust = generate(&init_parser(&tokens, &options), Mutability::Inferred, &Unit::default());
+        // a local keeps its name rather than take the one of a member
+        for line in &["foo_bar: i32,", "fn f(fooBar: i32) -> i32 {", "let r: i32 = fooBar + foo_bar;", "    count_x\n"] {
+            assert!(rust.contains(line), "{}\n{}", line, rust);
+        }
+    }
+}
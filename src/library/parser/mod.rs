@@ -1,2115 +1,2931 @@
 #![allow(dead_code)]
 
-use library::doc::DocType::*;
+use library::ast::*;
+use library::lexeme;
+use library::lexer;
 use library::lexeme::Type::*;
+use library::lexeme::Radix;
 use library::lexeme::Token;
+use library::naming::{self, Convention};
+use library::mutability;
+use library::initialization;
+use library::sequencing;
+use library::headers;
+use std::cell::Cell;
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct SymbolTable {
-    typ: i32,
+    typ: Type,
     id_name: String,
     is_assigned: bool,
     is_ptr: bool,
-    assigned_val: String,
     its_constant: bool,
+    is_fn: bool, // functions and macros
+    params: Vec<Type>, // parameter types of a function, typ is its return type
+    case: Convention, // naming convention of the Rust name
+}
+
+// lexical scopes, records hold the fields visible in their methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScopeKind {
+    Global,
+    Function,
+    Block,
+    Record,
 }
+
 #[derive(Debug)]
+struct Scope {
+    kind: ScopeKind,
+    symbols: Vec<SymbolTable>,
+}
+
+#[derive(Debug, Clone)]
 struct StructMem {
     name: String,
-    typ: i32,
+    typ: Type,
     identifier: String,
 }
 
-
-impl Clone for SymbolTable {
-    fn clone(&self) -> SymbolTable {
-        let id = self.id_name.clone();
-        let val = self.assigned_val.clone();
-        SymbolTable {
-            assigned_val: val,
-            id_name: id,
-            ..*self
-        }
-    }
+#[derive(Debug)]
+struct ParseError {
+    msg: String,
+    pos: Pos,
 }
 
-impl Clone for StructMem {
-    fn clone(&self) -> StructMem {
-        StructMem {
-            name: self.name.clone(),
-            typ: self.typ,
-            identifier: self.identifier.clone(),
-        }
-    }
+type PResult<T> = Result<T, ParseError>;
+
+// declaration specifiers collected in front of a declarator
+struct Specs {
+    ty: Type,
+    is_const: bool,
+    is_static: bool,
+    is_typedef: bool,
+    is_extern: bool,
+    // struct, union, class or enum defined inside the specifiers
+    defined: Option<Decl>,
 }
 
 
-
 struct Parser {
-    from: usize, //for symbol table
-    once_warned: bool, //default false
-    in_block_stmnt: bool, //default false
-    in_expr: bool, //default false
-    in_switch: bool, //defalt false
-    strict: bool, //default true
-    in_main: bool,
-    sym_tab: Vec<SymbolTable>, //symbol table
+    lexeme: Vec<Token>,
+    head: usize,
+    sym_tab: Vec<Scope>, //symbol table, innermost scope last
+    left_scopes: Vec<SymbolTable>, // symbols of the scopes already left
     struct_mem: Vec<StructMem>, // structure book keeping
     typde_def_table: Vec<String>,
+    record_table: Vec<String>, // struct, union, class and enum names
+    macro_table: Vec<String>, // function-like macros
+    macro_calls: Vec<(String, Vec<Option<Type>>)>, // argument types at each macro use
+    externs: Vec<String>, // variables declared extern at file scope
+    diagnostics: Vec<Diagnostic>, // errors recovered from
+    ret_type: Option<Type>, // return type of the function being parsed
+    escaped: Vec<(String, String)>, // identifiers escaped from Rust keywords, and their escapes
+    std_headers: Vec<&'static headers::StdHeader>, // standard headers included so far
+    options: Options,
 }
 
 
+/**
+ * Options:
+ * type_names are the types declared by included headers and renames
+ * the names they changed, naming turns on the Rust naming conventions
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub type_names: Vec<String>,
+    pub renames: Vec<Rename>,
+    pub naming: bool,
+}
 
-pub fn init_parser(lexeme: &Vec<Token>, strict_parser: bool) -> Vec<String> {
-
-    let mut stream: Vec<String> = Vec::new();
-    stream.push(CRUST.get_doc().to_string());
 
+/**
+ * init_parser:
+ * builds the syntax tree for the given token stream
+ */
+pub fn init_parser(lexeme: &[Token], options: &Options) -> Program {
+    let mut lexeme = lexeme.to_vec();
+    let escaped = escape_identifiers(&mut lexeme);
     let mut parser = Parser {
-        from: 0,
-        once_warned: false,
-        in_block_stmnt: false,
-        in_expr: false,
-        in_switch: false,
-        strict: strict_parser,
-        in_main: false,
-        sym_tab: Vec::new(),
+        lexeme,
+        head: 0,
+        sym_tab: vec![Scope {
+            kind: ScopeKind::Global,
+            symbols: Vec::new(),
+        }],
+        left_scopes: Vec::new(),
         struct_mem: Vec::new(),
-        typde_def_table: Vec::new(),
+        typde_def_table: options.type_names.clone(),
+        record_table: Vec::new(),
+        macro_table: Vec::new(),
+        macro_calls: Vec::new(),
+        externs: Vec::new(),
+        diagnostics: Vec::new(),
+        ret_type: None,
+        escaped,
+        std_headers: Vec::new(),
+        options: options.clone(),
     };
-	
-
-    stream.append(&mut parser.parse_program(&lexeme));
-    stream
+    parser.parse_program()
 }
 
 
+// Rust keywords, C and C++ code may use them as names
+pub const RUST_KEYWORDS: &[&str] = &["abstract", "as", "async", "await", "become", "box", "crate", "dyn",
+                                 "final", "fn", "gen", "impl", "in", "let", "loop", "macro", "match",
+                                 "mod", "move", "mut", "override", "priv", "pub", "ref", "self", "Self",
+                                 "super", "trait", "type", "typeof", "unsafe", "unsized", "use",
+                                 "where", "yield"];
+
+// keywords which are not allowed as raw identifiers, and prelude
+// names the translated code relies on
+pub const RENAMED: &[&str] = &["crate", "self", "Self", "super", "Box", "Err", "None", "Ok", "Some",
+                           "String", "Vec"];
+
 
 /**
- * skip_stmt:
- * forwards the lookahead by one statement
- * returns the lookahead at the lexeme after the semi-colon
+ * escape_identifiers:
+ * turns identifiers which collide with Rust keywords into raw
+ * identifiers, `type` becomes `r#type`. Those that cannot be raw
+ * get a trailing underscore instead, as many as needed to not clash
+ * with another name. Declarations and uses are renamed alike.
+ * Returns each escaped name with its escape
  */
-fn skip_stmt(lexeme: &Vec<Token>, mut lookahead: usize) -> usize {
-    while lexeme[lookahead].get_token_type() != SEMICOLON {
-        lookahead += 1;
+fn escape_identifiers(tokens: &mut [Token]) -> Vec<(String, String)> {
+    let mut renames: Vec<(String, String)> = Vec::new();
+    for i in 0..tokens.len() {
+        if tokens[i].get_token_type() != IDENTIFIER {
+            continue;
+        }
+        let name = tokens[i].get_token_value();
+        let escaped = if RENAMED.contains(&&name[..]) {
+            match renames.iter().find(|r| r.0 == name) {
+                Some(rename) => rename.1.clone(),
+                None => {
+                    let mut rename = format!("{}_", name);
+                    while tokens.iter().any(|t| t.get_token_value() == rename) {
+                        rename.push('_');
+                    }
+                    renames.push((name, rename.clone()));
+                    rename
+                }
+            }
+        } else if RUST_KEYWORDS.contains(&&name[..]) {
+            let raw = format!("r#{}", name);
+            if !renames.iter().any(|r| r.0 == name) {
+                renames.push((name, raw.clone()));
+            }
+            raw
+        } else {
+            continue;
+        };
+        tokens[i].set_token_value(&escaped);
     }
-    lookahead + 1
+    renames
 }
 
 
-/**
- * skip_block:
- * forwards the lookahead by one block
- * returns the lookahead at the lexeme after the closing brace
- */
-fn skip_block(lexeme: &Vec<Token>, mut lookahead: usize) -> usize {
-    let mut paren = 1;
-
-    // while all braces are not closed
-    // skip nested blocks if any
-    while paren != 0 && lookahead < lexeme.len() {
-        if lexeme[lookahead].get_token_type() == LEFT_CBRACE {
-            paren += 1;
-        }
-        if lexeme[lookahead].get_token_type() == RIGHT_CBRACE {
-            paren -= 1;
-        }
-        lookahead += 1;
-    }
-    lookahead
+fn pos_of(token: &Token) -> Pos {
+    token.get_span()
 }
 
 
 /**
- * fn parse_type:
- * takes the integer value of type Type
- * returns either the equivalent Rust type as a string or
- * None, if does not correspond to any c/c++ type
+ * binop_of:
+ * returns the binary operator for the token along with its
+ * C precedence, higher binds tighter
  */
-fn parse_type(c_type: i32) -> Option<String> {
-    match c_type {
-        0 => Some("i32".to_string()),
-        1 => Some("i16".to_string()),
-        2 => Some("i64".to_string()),
-        3 => Some("f32".to_string()),
-        4 => Some("f64".to_string()),
-        5 => Some("char".to_string()),
-        6 => Some("bool".to_string()),
-        7 => Some("void".to_string()),
-        8 => Some("u32".to_string()),
-        9 => Some("u16".to_string()),
-        10 => Some("u64".to_string()),
-        11 => Some("_".to_string()),
-        13 => Some("u8".to_string()),
-        12 => Some("String".to_string()),
+fn binop_of(token: &Token) -> Option<(BinOp, u8)> {
+    match token.get_token_type() {
+        OP_LOGOR => Some((BinOp::Or, 1)),
+        OP_LOGAND => Some((BinOp::And, 2)),
+        OP_BITOR => Some((BinOp::BitOr, 3)),
+        OP_BITXOR => Some((BinOp::BitXor, 4)),
+        OP_BITAND => Some((BinOp::BitAnd, 5)),
+        OP_EQU => Some((BinOp::Eq, 6)),
+        OP_NEQ => Some((BinOp::Ne, 6)),
+        OP_LT => Some((BinOp::Lt, 7)),
+        OP_GT => Some((BinOp::Gt, 7)),
+        OP_LE => Some((BinOp::Le, 7)),
+        OP_GE => Some((BinOp::Ge, 7)),
+        OP_BITLSHIFT => Some((BinOp::Shl, 8)),
+        OP_BITRSHIFT => Some((BinOp::Shr, 8)),
+        OP_PLUS => Some((BinOp::Add, 9)),
+        OP_MINUS => Some((BinOp::Sub, 9)),
+        OP_MUL => Some((BinOp::Mul, 10)),
+        OP_DIV => Some((BinOp::Div, 10)),
+        OP_MOD => Some((BinOp::Rem, 10)),
         _ => None,
     }
 }
 
 
-
-impl Parser {
-    fn parse_program(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-
-        let mut stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
-        let mut lookahead: usize;
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        while head < lexeme.len() {
-            // gets both base type and token type
-            lookahead = head;
-            match lexeme[head].get_type() {
-
-                // matches any datatype
-                (BASE_DATATYPE, _) => {
-                    if lexeme[head].get_token_type() == KEYWORD_SIGNED ||
-                       lexeme[head].get_token_type() == KEYWORD_UNSIGNED {
-                        lookahead += 1;
-                    }
-                    lookahead += 2;
-                    match lexeme[lookahead].get_token_type() {
-
-                        // function
-                        LEFT_BRACKET => {
-                            //inside the function
-                            self.in_block_stmnt = true;
-
-                            while lexeme[lookahead].get_token_type() != RIGHT_BRACKET {
-                                lookahead += 1;
-                            }
-                            lookahead += 1;
-
-                            // skip function declaration
-                            if lexeme[lookahead].get_token_type() != LEFT_CBRACE {
-                                lookahead += 1;
-                                head = lookahead;
-                                continue;
-                            }
-                            // advance lookahead to end of block
-                            lookahead = skip_block(&lexeme, lookahead + 1);
-                            // collect entire function block
-                            while head < lookahead {
-                                let l: Token = lexeme[head].clone();
-                                temp_lexeme.push(l);
-                                head += 1;
-                            }
-
-                            //parse function block
-                            stream.append(&mut self.parse_function(&temp_lexeme));
-                            temp_lexeme.clear();
-
-                            self.in_block_stmnt = false;
-
-                        }
-
-                        //array declaration found
-                        LEFT_SBRACKET => {
-                            lookahead = skip_stmt(&lexeme, lookahead);
-
-                            // collect variable declaration
-                            while head != lookahead {
-                                let l: Token = lexeme[head].clone();
-                                temp_lexeme.push(l);
-                                head += 1;
-                            }
-                            // parse declaration
-                            stream.append(&mut self.parse_array_declaration(&temp_lexeme));
-                            temp_lexeme.clear();
-                        }
-
-                        // variable declaration or declaration + assignment
-                        SEMICOLON | COMMA | OP_ASSIGN => {
-                            lookahead = skip_stmt(&lexeme, lookahead);
-
-                            // collect variable declaration
-                            while head != lookahead {
-                                let l: Token = lexeme[head].clone();
-                                temp_lexeme.push(l);
-                                head += 1;
-                            }
-                            //  println!(" {:?}", temp_lexeme);
-                            // parse declaration
-                            stream.append(&mut self.parse_declaration(&temp_lexeme));
-                            temp_lexeme.clear();
-                        }
-                        IDENTIFIER => {
-                            //in case of pointer declaration
-                            while lexeme[head].get_token_type() != SEMICOLON {
-                                temp_lexeme.push(lexeme[head].clone());
-                                head += 1;
-                            }
-                            temp_lexeme.push(lexeme[head].clone());
-                            stream.append(&mut self.parse_declaration(&temp_lexeme));
-                            head += 1;
-                            // println!("{}", lexeme[head].get_token_value());
-                            temp_lexeme.clear();
+// splits a numeric token into digits, radix and suffix
+// checks for the suffixes C gives integer and floating literals,
+// anything else is a C++ user-defined literal
+fn is_c_suffix(suffix: &str, float: bool) -> bool {
+    let suffix = suffix.to_lowercase();
+    if float {
+        return matches!(&suffix[..], "" | "f" | "l");
+    }
+    matches!(&suffix[..], "" | "u" | "l" | "ll" | "ul" | "lu" | "ull" | "llu")
+}
 
 
-                        }
+fn num_lit(token: &Token) -> NumLit {
+    let value = token.get_token_value();
+    let suffix = token.get_suffix();
+    let prefix = match token.get_radix() {
+        Radix::Hex | Radix::Binary => 2,
+        Radix::Octal => 1,
+        Radix::Decimal => 0,
+    };
+    let digits = value[prefix..value.len() - suffix.len()].replace('\'', "_");
+    NumLit {
+        digits,
+        radix: token.get_radix(),
+        suffix,
+    }
+}
 
-                        _ => {}
-                    };
-                }
-                (BASE_TYPEDEF, PRIMITIVE_TYPEDEF) => {
-                    //in case of pointer declaration
-                    while lexeme[head].get_token_type() != SEMICOLON {
-                        temp_lexeme.push(lexeme[head].clone());
-                        head += 1;
-                    }
-                    temp_lexeme.push(lexeme[head].clone());
-                    stream.append(&mut self.parse_typdef(&temp_lexeme));
-                    head += 1;
-                    // println!("{}", lexeme[head].get_token_value());
-                    temp_lexeme.clear();
 
-                }
+// decoded text of a char or string literal token
+fn literal_text(token: &Token) -> String {
+    lexer::unescape(&literal_body(token))
+}
 
-                // matches if statement
-                (_, KEYWORD_IF) => {
-                    // let mut temp_lexeme: Vec<Token> = Vec::new();
+// bytes of a string literal with escapes decoded
+fn literal_bytes(token: &Token) -> Vec<u8> {
+    lexer::unescape_bytes(&literal_body(token))
+}
 
-                    // move lookahead past conditon
-                    while lexeme[lookahead].get_token_type() != RIGHT_BRACKET {
-                        lookahead += 1;
-                    }
-                    lookahead += 1;
+fn literal_body(token: &Token) -> String {
+    let value = token.get_token_value();
+    if value.len() >= 2 { value[1..value.len() - 1].to_string() } else { String::new() }
+}
 
-                    // move lookahead past block
-                    if lexeme[lookahead].get_token_type() == LEFT_CBRACE {
-                        lookahead = skip_block(&lexeme, lookahead + 1);
-                    }
-                    // move lookahead past block for 'if' without braces
-                    else {
-                        lookahead = skip_stmt(&lexeme, lookahead);
-                    }
-                    // collect if block
-                    while head < lookahead {
-                        let l: Token = lexeme[head].clone();
-                        temp_lexeme.push(l);
-                        head += 1;
-                    }
 
-                    // parse if
-                    stream.append(&mut self.parse_if(&temp_lexeme));
-                    temp_lexeme.clear();
-                }
+/**
+ * assignop_of:
+ * returns Some(None) for `=`, Some(Some(op)) for a compound
+ * assignment and None if the token is not an assignment
+ */
+fn assignop_of(token: &Token) -> Option<Option<BinOp>> {
+    match token.get_token_type() {
+        OP_ASSIGN => Some(None),
+        OP_PLUSEQU => Some(Some(BinOp::Add)),
+        OP_MINEQU => Some(Some(BinOp::Sub)),
+        OP_MULEQU => Some(Some(BinOp::Mul)),
+        OP_DIVEQU => Some(Some(BinOp::Div)),
+        OP_MODEQU => Some(Some(BinOp::Rem)),
+        OP_BITANDEQU => Some(Some(BinOp::BitAnd)),
+        OP_BITOREQU => Some(Some(BinOp::BitOr)),
+        OP_BITXOREQU => Some(Some(BinOp::BitXor)),
+        OP_BITLSHIFTEQU => Some(Some(BinOp::Shl)),
+        OP_BITRSHIFTEQU => Some(Some(BinOp::Shr)),
+        _ => None,
+    }
+}
 
-                (_, KEYWORD_ELSE) => {
-                    stream.push("else".to_string());
-                    head += 1;
-                    lookahead = head;
-                    if lexeme[head].get_token_type() == KEYWORD_IF {
-                        continue;
-                    } else {
-                        if lexeme[lookahead].get_token_type() == LEFT_CBRACE {
-                            head += 1;
-                            lookahead = skip_block(&lexeme, head) - 1;
-                        } else {
-                            lookahead = skip_stmt(&lexeme, lookahead);
-                        }
 
-                        while head < lookahead {
-                            let l: Token = lexeme[head].clone();
-                            temp_lexeme.push(l);
-                            head += 1;
-                        }
-                        //** parse else body
-                        stream.push("{".to_string());
-                        stream.append(&mut self.parse_program(&temp_lexeme));
-                        temp_lexeme.clear();
-                        stream.push("}".to_string());
-                    }
+impl Parser {
+    // ---------------------------------------------------------------
+    // token cursor
+    // comments are skipped by every lookup below, statement lists pick
+    // them up explicitly with take_comment
+    // ---------------------------------------------------------------
+
+    fn index_of(&self, n: usize) -> Option<usize> {
+        let mut seen = 0;
+        let mut i = self.head;
+        while i < self.lexeme.len() {
+            if self.lexeme[i].get_base_type() != BASE_COMMENT {
+                if seen == n {
+                    return Some(i);
                 }
+                seen += 1;
+            }
+            i += 1;
+        }
+        None
+    }
 
-                (_, KEYWORD_SWITCH) => {
+    fn peek(&self, n: usize) -> Option<&Token> {
+        self.index_of(n).map(|i| &self.lexeme[i])
+    }
 
-                    while lexeme[lookahead].get_token_type() != LEFT_CBRACE {
-                        lookahead += 1;
-                    }
-                    lookahead += 1;
+    fn peek_type(&self, n: usize) -> Option<lexeme::Type> {
+        self.peek(n).map(|t| t.get_token_type())
+    }
 
-                    lookahead = skip_block(&lexeme, lookahead);
-                    while head < lookahead {
-                        let l: Token = lexeme[head].clone();
-                        temp_lexeme.push(l);
-                        head += 1;
-                    }
-                    self.in_switch = true;
+    fn at(&self, typ: lexeme::Type) -> bool {
+        self.peek_type(0) == Some(typ)
+    }
 
-                    stream.append(&mut self.parse_switch(&temp_lexeme));
-                    temp_lexeme.clear();
-                    self.in_switch = false;
+    fn at_n(&self, n: usize, typ: lexeme::Type) -> bool {
+        self.peek_type(n) == Some(typ)
+    }
 
-                }
+    fn at_value(&self, value: &str) -> bool {
+        self.peek(0).is_some_and(|t| t.get_token_value() == value)
+    }
 
-                (_, KEYWORD_WHILE) => {
-                    // let mut temp_lexeme: Vec<Token> = Vec::new();
+    fn at_end(&self) -> bool {
+        self.index_of(0).is_none()
+    }
 
-                    // move lookahead past conditon
-                    while lexeme[lookahead].get_token_type() != RIGHT_BRACKET {
-                        lookahead += 1;
-                    }
-                    lookahead += 1;
+    fn advance(&mut self) -> Option<Token> {
+        match self.index_of(0) {
+            Some(i) => {
+                self.head = i + 1;
+                Some(self.lexeme[i].clone())
+            }
+            None => {
+                self.head = self.lexeme.len();
+                None
+            }
+        }
+    }
 
-                    // move lookahead past block
-                    if lexeme[lookahead].get_token_type() == LEFT_CBRACE {
-                        lookahead = skip_block(&lexeme, lookahead + 1);
-                    }
-                    // move lookahead past block for 'if' without braces
-                    else {
-                        lookahead = skip_stmt(&lexeme, lookahead);
-                    }
-                    // collect if block
-                    while head < lookahead {
-                        let l: Token = lexeme[head].clone();
-                        temp_lexeme.push(l);
-                        head += 1;
-                    }
+    fn eat(&mut self, typ: lexeme::Type) -> bool {
+        if self.at(typ) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
 
-                    let was_in_switch: bool;
-                    was_in_switch = self.in_switch;
-                    self.in_switch = false;
+    fn expect(&mut self, typ: lexeme::Type, what: &str) -> PResult<Token> {
+        if self.at(typ) {
+            Ok(self.advance().unwrap())
+        } else {
+            self.error(format!("expected {}", what))
+        }
+    }
 
-                    // parse if
-                    stream.append(&mut self.parse_while(&temp_lexeme));
-                    self.in_switch = was_in_switch;
-                    temp_lexeme.clear();
-                }
+    fn pos(&self) -> Pos {
+        match self.peek(0) {
+            Some(t) => pos_of(t),
+            None => self.lexeme.last().map(pos_of).unwrap_or_default(),
+        }
+    }
 
-                // matches do while statement
-                (_, KEYWORD_DO) => {
+    fn error<T>(&self, msg: String) -> PResult<T> {
+        Err(ParseError {
+            msg,
+            pos: self.pos(),
+        })
+    }
 
-                    // move lookahead past block
-                    lookahead = skip_block(&lexeme, lookahead + 2);
-                    lookahead = skip_stmt(&lexeme, lookahead);
+    // error for a keyword at head which has no Rust equivalent
+    fn unsupported<T>(&self) -> PResult<T> {
+        let keyword = self.peek(0).map(|t| t.get_token_value()).unwrap_or_default();
+        self.error(format!("`{}` has no Rust equivalent", keyword))
+    }
 
-                    // collect while block
-                    while head < lookahead {
-                        let l: Token = lexeme[head].clone();
-                        temp_lexeme.push(l);
-                        head += 1;
-                    }
-                    // parse while
-                    let was_in_switch: bool;
-                    was_in_switch = self.in_switch;
-                    self.in_switch = false;
+    // qualifier at head which is left out of the translation
+    fn drop_qualifier(&mut self) {
+        let keyword = self.peek(0).map(|t| t.get_token_value()).unwrap_or_default();
+        let pos = self.pos();
+        self.diagnostics.push(Diagnostic {
+            msg: format!("`{}` has no Rust equivalent, it is dropped", keyword),
+            pos,
+        });
+    }
 
-                    stream.append(&mut self.parse_dowhile(&temp_lexeme));
-                    temp_lexeme.clear();
+    fn report(&mut self, err: ParseError) {
+        self.diagnostics.push(Diagnostic {
+            msg: err.msg,
+            pos: err.pos,
+        });
+    }
 
-                    self.in_switch = was_in_switch;
+    // consumes the comment at head, if any
+    fn take_comment(&mut self) -> Option<Token> {
+        if self.head < self.lexeme.len() &&
+           self.lexeme[self.head].get_base_type() == BASE_COMMENT {
+            self.head += 1;
+            return Some(self.lexeme[self.head - 1].clone());
+        }
+        None
+    }
 
-                }
+    // source text of the tokens from start up to head
+    fn text_from(&self, start: usize) -> String {
+        let words: Vec<String> = self.lexeme[start..self.head]
+            .iter()
+            .filter(|t| t.get_base_type() != BASE_COMMENT)
+            .map(|t| t.get_token_value())
+            .collect();
+        words.join(" ")
+    }
 
-                // matches for statement
-                (_, KEYWORD_FOR) => {
-                    while lexeme[lookahead].get_token_type() != LEFT_CBRACE {
-                        lookahead += 1;
+    /**
+     * recover:
+     * rewinds to start and skips one statement, that is up to the next
+     * semi-colon or the end of a block opened inside the statement
+     * returns the skipped source text
+     */
+    fn recover(&mut self, start: usize) -> String {
+        self.head = start;
+        let mut depth = 0;
+        while self.head < self.lexeme.len() {
+            match self.lexeme[self.head].get_token_type() {
+                LEFT_CBRACE => depth += 1,
+                RIGHT_CBRACE => {
+                    if depth == 0 {
+                        break;
                     }
-                    lookahead += 1;
-                    lookahead = skip_block(&lexeme, lookahead);
-
-                    while head < lookahead {
-                        let l: Token = lexeme[head].clone();
-                        temp_lexeme.push(l);
-                        head += 1;
+                    depth -= 1;
+                    if depth == 0 {
+                        self.head += 1;
+                        self.eat(SEMICOLON);
+                        return self.text_from(start);
                     }
-
-                    let was_in_switch: bool;
-                    was_in_switch = self.in_switch;
-                    self.in_switch = false;
-
-                    stream.append(&mut self.parse_for(&temp_lexeme));
-                    temp_lexeme.clear();
-                    self.in_switch = was_in_switch;
-
                 }
-
-                // matches single and multi-line comment
-                (BASE_COMMENT, _) => {
-                    stream.push(lexeme[head].get_token_value() + "\n");
-                    head += 1;
+                SEMICOLON if depth == 0 => {
+                    self.head += 1;
+                    break;
                 }
+                _ => {}
+            }
+            self.head += 1;
+        }
+        // always make progress, even on a stray closing brace
+        if self.head == start {
+            self.head += 1;
+        }
+        self.text_from(start)
+    }
 
-                // assignment statements
-                (_, IDENTIFIER) => {
-                    // let mut temp_lexeme: Vec<Token> = Vec::new();
-                    //identifier = expr
-                    //identifier()
-                    //identifier+expr
-                    //identifier OP_INC|OP_DEC; =>postfix
-
-
-                    match lexeme[head + 1].get_type() {
-                        (_, IDENTIFIER) => {
-                            if self.typde_def_table.contains(&lexeme[head].get_token_value()) {
-                                lookahead = skip_stmt(&lexeme, lookahead);
-
-                                // collect variable declaration
-                                while head != lookahead {
-                                    let l: Token = lexeme[head].clone();
-                                    temp_lexeme.push(l);
-                                    head += 1;
-                                }
-                                //  println!(" {:?}", temp_lexeme);
-                                // parse declaration
-                                stream.append(&mut self.parse_declaration(&temp_lexeme));
-                            } else {
-                                while lexeme[head].get_token_type() != SEMICOLON {
-                                    temp_lexeme.push(lexeme[head].clone());
-                                    head += 1;
-                                }
-                                temp_lexeme.push(lexeme[head].clone());
-                                head += 1;
-                                stream.append(&mut self.parse_class_decl(&temp_lexeme));
-                            }
-                            temp_lexeme.clear();
+    // skips a balanced {...} block, head must be at the opening brace
+    fn skip_block(&mut self) -> PResult<()> {
+        self.expect(LEFT_CBRACE, "`{`")?;
+        let mut paren = 1;
+        while paren != 0 {
+            match self.advance() {
+                Some(ref t) if t.get_token_type() == LEFT_CBRACE => paren += 1,
+                Some(ref t) if t.get_token_type() == RIGHT_CBRACE => paren -= 1,
+                Some(_) => {}
+                None => return self.error("unterminated block".to_string()),
+            }
+        }
+        Ok(())
+    }
 
-                        }
+    fn is_type_name(&self, name: &str) -> bool {
+        self.typde_def_table.iter().any(|t| t == name) ||
+        self.record_table.iter().any(|t| t == name)
+    }
 
-                        (_, OP_ASSIGN) => {
-                            // move lookahead past statement
-                            if lexeme[head + 3].get_token_type() == COMMA {
-                                lookahead = head + 3;
-                                while head < lookahead + 1 {
-                                    let l: Token = lexeme[head].clone();
-                                    temp_lexeme.push(l);
-                                    head += 1;
-                                }
-                                stream.append(&mut self.parse_assignment(&temp_lexeme));
-                                temp_lexeme.clear();
-                            } else {
-                                lookahead = skip_stmt(&lexeme, lookahead);
-                                // collect statement
-                                while head < lookahead {
-                                    let l: Token = lexeme[head].clone();
-                                    temp_lexeme.push(l);
-                                    head += 1;
-                                }
-
-                                // parse assignment
-                                stream.append(&mut self.parse_assignment(&temp_lexeme));
-                                temp_lexeme.clear();
-                            }
-                        }
-                        (BASE_UNOP, _) => {
-                            println!("UN_OP");
-							if self.in_expr != true {
-                                stream.push(lexeme[head].get_token_value());
-                                stream.push(match lexeme[head + 1].get_token_type() {
-                                    OP_INC => "+=1".to_string(),
-                                    OP_DEC => "-=1".to_string(),
-                                    _ => " ;".to_string(),
-                                });
-                                head += 2;
-                            } else {
-                                head += 2;
-                            }
-                        }
-                        (BASE_BINOP, _) => {
-                            lookahead = skip_stmt(&lexeme, lookahead);
-
-                            //check if overloaded operators is in effect like << >>
-                            if lexeme[head + 2].get_token_type() == STRING ||
-                               lexeme[head + 2].get_token_type() == CHAR_VAL {
-                                stream.push("\n//This statement need to be handled manually \n"
-                                    .to_string());
-                                while head < lookahead {
-                                    stream.push(lexeme[head].get_token_value());
-                                    head += 1;
-                                }
-                            } else {
-
-                                // move lookahead past statement
-                                // collect statement
-                                while head < lookahead {
-                                    let l: Token = lexeme[head].clone();
-                                    temp_lexeme.push(l);
-                                    head += 1;
-                                }
-
-                                // parse assignment
-                                stream.append(&mut self.parse_expr(&temp_lexeme));
-                                temp_lexeme.clear();
-
-                            }
-                        }
-                        (_, LEFT_BRACKET) => {
-                            while lexeme[head].get_token_type() != RIGHT_BRACKET {
-                                stream.push(lexeme[head].get_token_value());
-                                head += 1;
-                            }
-                            stream.push(lexeme[head].get_token_value());
-                            head += 1;
-                        }
-                        // (_, LEFT_SBRACKET) => {
-                        //     while lexeme[head].get_token_type() != RIGHT_SBRACKET {
-                        //         stream.push(lexeme[head].get_token_value());
-                        //         head += 1;
-                        //     }
-                        //     stream.push(lexeme[head].get)
-                        // }
-                        (_, _) => {
-                            if lexeme[head].get_token_type() != RIGHT_CBRACE {
-                                stream.push(lexeme[head].get_token_value());
-                            }
-                            head += 1;
-                        }
-                    };
+    /**
+     * is_decl_start:
+     * checks whether the statement at head is a declaration
+     */
+    fn is_decl_start(&self) -> bool {
+        let tok = match self.peek(0) {
+            Some(t) => t,
+            None => return false,
+        };
+        match tok.get_type() {
+            (BASE_DATATYPE, _) | (BASE_TYPEDEF, _) => true,
+            (_, KEYWORD_STATIC) | (_, KEYWORD_STRUCT) | (_, KEYWORD_UNION) |
+            (_, KEYWORD_ENUM) | (_, KEYWORD_CLASS) | (_, KEYWORD_USING) => true,
+            (_, IDENTIFIER) => {
+                match self.peek_type(1) {
+                    // `A a;`
+                    Some(IDENTIFIER) => true,
+                    // `vector<int> v;` and `std::vector<int> v;`
+                    Some(OP_LT) => headers::is_template(&tok.get_token_value()),
+                    Some(OP_SCOPE) => self.at_std_template(0),
+                    Some(OP_MUL) | Some(OP_BITAND) => self.is_type_name(&tok.get_token_value()),
+                    _ => false,
                 }
+            }
+            _ => false,
+        }
+    }
 
-                (BASE_UNOP, _) => {
-                    println!(" 564 : unop");
-					stream.push(lexeme[head + 1].get_token_value());
-                    stream.push(match lexeme[head].get_token_type() {
-                        OP_INC => "+=1".to_string(),
-                        OP_DEC => "-=1".to_string(),
-                        _ => " ;".to_string(),
-                    });
-                    head += 2;
-                }
 
-                (_, KEYWORD_STRUCT) => {
-                    if lexeme[head + 2].get_token_type() == LEFT_CBRACE {
-                        //struct A{};
-                        while lexeme[head].get_token_type() != RIGHT_CBRACE {
-                            temp_lexeme.push(lexeme[head].clone());
-                            head += 1;
-                        }
-                        //push the right curly brace
-                        temp_lexeme.push(lexeme[head].clone());
-                        stream.append(&mut self.parse_struct(&temp_lexeme));
-                        temp_lexeme.clear();
-                        head += 2; //skip semicolon
-                    } else {
-                        //struct variable declaration
+    /**
+     * parse_program:
+     * parses the whole translation unit into a list of declarations
+     */
+    fn parse_program(&mut self) -> Program {
+        let mut program = Program::default();
+        while self.head < self.lexeme.len() {
+            if let Some(c) = self.take_comment() {
+                program.decls.push(Decl::Comment(c.get_token_value(), pos_of(&c)));
+                continue;
+            }
+            let start = self.head;
+            let pos = self.pos();
+            let parsed = if self.at_value("#") {
+                self.parse_directive().map(|d| vec![d])
+            } else {
+                self.parse_declaration()
+            };
+            match parsed {
+                Ok(mut decls) => program.decls.append(&mut decls),
+                Err(err) => {
+                    self.report(err);
+                    program.decls.push(Decl::Verbatim(self.recover(start), pos));
+                }
+            }
+        }
+        program.diagnostics.append(&mut self.diagnostics);
+        infer_const_types(&mut program.decls);
+        infer_macro_signatures(&mut program.decls, &self.macro_calls);
+        program.externs = self.externs.clone();
+        for decl in &mut program.decls {
+            if let Decl::Macro(ref mut def) = *decl {
+                self.convert_macro(def);
+            }
+        }
+        initialization::check(&mut program);
+        sequencing::check(&mut program);
+        mutability::infer(&mut program);
+        if self.options.naming {
+            let declared = self.declared_names();
+            program.renames = naming::rename_map(&declared);
+            // the map shows the names as they are written in C
+            for rename in &mut program.renames {
+                if let Some(escaped) = self.escaped.iter().find(|e| e.1 == rename.from) {
+                    rename.c_name = escaped.0.clone();
+                }
+            }
+            let mut renames = program.renames.clone();
+            renames.extend(self.options.renames.iter().cloned());
+            naming::apply(&mut program, &renames, &declared);
+        }
+        program
+    }
 
-                        while lexeme[head].get_token_type() != SEMICOLON {
-                            temp_lexeme.push(lexeme[head].clone());
-                            head += 1;
-                        }
-                        temp_lexeme.push(lexeme[head].clone());
-                        head += 1;
-                        stream.append(&mut self.parse_struct_decl(&temp_lexeme));
-                        temp_lexeme.clear();
-                    }
-                },
 
-                (_, KEYWORD_UNION) => {
+    /**
+     * convert_macro:
+     * inserts the conversions of a function body into the body of a
+     * macro which becomes a function, with its parameters typed
+     */
+    fn convert_macro(&mut self, def: &mut MacroDef) {
+        if let MacroBody::Stmt(_) = def.body {
+            return;
+        }
+        // a macro_rules! used with the same types everywhere converts for them
+        let (types, ret) = match def.signature {
+            Some((ref types, ref ret)) => (types.clone(), Some(ret.clone())),
+            None => {
+                match site_types(&def.name, def.params.len(), &self.macro_calls) {
+                    Some(types) => (types, None),
+                    None => return,
+                }
+            }
+        };
+        self.sym_tab.push(Scope {
+            kind: ScopeKind::Function,
+            symbols: Vec::new(),
+        });
+        for (param, ty) in def.params.iter().zip(&types) {
+            self.declare(param, ty, false, Convention::Snake);
+        }
+        if let MacroBody::Expr(ref mut body) = def.body {
+            self.convert(body);
+            if let Some(ref ret) = ret {
+                self.cast_to(body, ret);
+            }
+        }
+        self.sym_tab.pop();
+    }
 
-                    if lexeme[head + 2].get_token_type() == LEFT_CBRACE {
-					stream.push(UNION.get_doc().to_string());
-                        //struct A{};
-                        while lexeme[head].get_token_type() != RIGHT_CBRACE {
-                            temp_lexeme.push(lexeme[head].clone());
-                            head += 1;
-                        }
-                        //push the right curly brace
-                        temp_lexeme.push(lexeme[head].clone());
-                        stream.append(&mut self.parse_union(&temp_lexeme));
-                        temp_lexeme.clear();
-                        head += 2; //skip semicolon
-                    } else {
-                        //struct variable declaration
 
-                        while lexeme[head].get_token_type() != SEMICOLON {
-                            temp_lexeme.push(lexeme[head].clone());
-                            head += 1;
-                        }
-                        temp_lexeme.push(lexeme[head].clone());
-                        head += 1;
-                        stream.append(&mut self.parse_union_decl(&temp_lexeme));
-                        temp_lexeme.clear();
-                    }
-                }
+    // every name declared in this file with the case it should follow
+    fn declared_names(&self) -> Vec<(String, NameSpace, Convention)> {
+        let imported = self.options.type_names.len();
+        let types = self.typde_def_table[imported..].iter().chain(&self.record_table);
+        let mut names: Vec<(String, NameSpace, Convention)> = types.map(|t| (t.clone(), NameSpace::Type, Convention::Camel)).collect();
+        let symbols = self.left_scopes.iter().chain(self.sym_tab.iter().flat_map(|scope| &scope.symbols));
+        names.extend(symbols.map(|s| (s.id_name.clone(), NameSpace::Value, s.case)));
+        names.extend(self.struct_mem.iter().map(|m| (m.identifier.clone(), NameSpace::Member, Convention::Snake)));
+        names
+    }
 
-                (_, KEYWORD_CLASS) => {
 
-                    if lexeme[head + 2].get_token_type() == LEFT_CBRACE {
-                        //struct A{};
-                        while lexeme[head].get_token_type() != RIGHT_CBRACE ||
-                              lexeme[head + 1].get_token_type() != SEMICOLON {
-                            temp_lexeme.push(lexeme[head].clone());
-                            head += 1;
-                        }
+    // records a name in the innermost scope
+    fn declare(&mut self, name: &str, typ: &Type, is_fn: bool, case: Convention) {
+        self.add_symbol(SymbolTable {
+            typ: typ.clone(),
+            id_name: name.to_string(),
+            is_assigned: true,
+            is_ptr: typ.is_pointer(),
+            its_constant: case == Convention::Screaming,
+            is_fn,
+            params: Vec::new(),
+            case,
+        });
+    }
 
-                        //push the right curly brace
-                        temp_lexeme.push(lexeme[head].clone());
-                        stream.append(&mut self.parse_class(&temp_lexeme));
-                        temp_lexeme.clear();
-                        head += 2; //skip semicolon
-                    }
-                }
+    fn add_symbol(&mut self, symbol: SymbolTable) {
+        self.sym_tab.last_mut().unwrap().symbols.push(symbol);
+    }
 
-                (_, KEYWORD_ENUM) => {
-                    while lexeme[head].get_token_type() != SEMICOLON {
-                        stream.push(lexeme[head].get_token_value());
-                        head += 1;
-                    }
-                    head += 1;
-                }
-                (_, KEYWORD_RETURN) => {
-                    let mut t = head;
-                    stream.push(NO_RETURN.get_doc().to_string());
 
-                    while lexeme[t].get_token_type() != SEMICOLON {
-                        t += 1;
-                    }
+    /**
+     * scoped:
+     * runs parse inside a new scope of the given kind, the scope
+     * is left again even when parse fails
+     */
+    fn scoped<T, F>(&mut self, kind: ScopeKind, parse: F) -> PResult<T>
+        where F: FnOnce(&mut Parser) -> PResult<T>
+    {
+        self.sym_tab.push(Scope {
+            kind,
+            symbols: Vec::new(),
+        });
+        let result = parse(self);
+        let scope = self.sym_tab.pop().unwrap();
+        // fields are named as members, not as values
+        if scope.kind != ScopeKind::Record {
+            self.left_scopes.extend(scope.symbols);
+        }
+        result
+    }
 
-                    if t != lexeme.len() - 1 {
-                        while lexeme[head].get_token_type() != SEMICOLON {
-                            // println!("{:?}", lexeme[head]);
 
-                            stream.push(lexeme[head].get_token_value());
-                            head += 1;
-                        }
-                        stream.push(lexeme[head].get_token_value());
-                        head += 1;
-                    } else {
-                        //convert to shorthand notation
-                        head += 1;
-                        if self.in_main {
-                            stream.push("std::process::exit(".to_string());
-                            while lexeme[head].get_token_type() != SEMICOLON {
-                                stream.push(lexeme[head].get_token_value());
-                                head += 1;
-                            }
-                            stream.push(");".to_string());
-                        } else {
-                            while lexeme[head].get_token_type() != SEMICOLON {
-                                stream.push(lexeme[head].get_token_value());
-                                head += 1;
-                            }
-                        }
-                        head += 1;
-                    }
-                }
-                (_, HEADER_INCLUDE) => {
-                    if self.once_warned == false {
-                        stream.push(INCLUDE_STMT.get_doc().to_string());
-                    } else {
-                        stream.pop();
-                        stream.push("* >>>>>>>>".to_string());
-                    }
+    /**
+     * lookup:
+     * finds the declaration a name refers to, searching from the
+     * innermost scope outwards so inner declarations shadow outer ones
+     */
+    fn lookup(&self, name: &str) -> Option<&SymbolTable> {
+        self.sym_tab
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.symbols.iter().rev())
+            .find(|s| s.id_name == name)
+    }
 
-                    while lexeme[head].get_token_type() != OP_GT {
-                        stream.push(lexeme[head].get_token_value());
-                        head += 1;
-                    }
-                    stream.push(lexeme[head].get_token_value() + "\n");
-                    stream.push("**/\n".to_string());
-                    head += 1;
+    // a name the included standard headers declare, unless this file declares it
+    fn std_name(&self, name: &str) -> Option<&'static headers::StdName> {
+        if self.lookup(name).is_some() {
+            return None;
+        }
+        self.std_headers.iter().flat_map(|h| h.names).find(|n| n.name == name)
+    }
 
-                    self.once_warned = true;
+    fn in_function(&self) -> bool {
+        self.sym_tab.iter().any(|scope| scope.kind == ScopeKind::Function)
+    }
 
 
-                }
-                // if all fails
-                (_, _) => {
-                    if lexeme[head].get_token_type() != RIGHT_CBRACE {
-                        if lexeme[head].get_token_type() == COMMA {
-                            stream.push(";".to_string());
-                        } else if lexeme[head].get_token_type() == KEYWORD_BREAK {
-                            if !self.in_switch {
-                                stream.push(lexeme[head].get_token_value());
-                            }
-
-                        } else {
-                            stream.push(lexeme[head].get_token_value());
-                        }
+    /**
+     * parse_directive:
+     * parses a preprocessor line, head is at `#`
+     */
+    fn parse_directive(&mut self) -> PResult<Decl> {
+        let pos = self.pos();
+        let hash = self.advance().unwrap();
+        let mut line: Vec<Token> = Vec::new();
+        while self.peek(0).is_some_and(|t| t.get_token_ln() == hash.get_token_ln()) {
+            line.push(self.advance().unwrap());
+        }
+        let words: Vec<String> = line.iter().map(|t| t.get_token_value()).collect();
+        if words.is_empty() {
+            return Ok(Decl::Directive("#".to_string(), pos));
+        }
+        if words[0] == "include" {
+            let include = format!("#include {}", words[1..].concat());
+            self.std_headers.extend(headers::find_header(&include));
+            return Ok(Decl::Include(include, pos));
+        }
+        // the preprocessor only leaves macros Rust can express in place
+        if words[0] == "define" && line.len() > 2 {
+            let function_like = words[2] == "(" &&
+                                line[2].get_span().start == line[1].get_span().end;
+            let parsed = if function_like {
+                self.parse_macro(&line[1..], pos).map(Decl::Macro)
+            } else {
+                self.declare(&words[1], &Type::Void, false, Convention::Screaming);
+                let parsed = self.parse_tokens(line[2..].to_vec(), Parser::parse_conditional);
+                // uses convert like those of a variable of the constant's type
+                if let Ok(ref value) = parsed {
+                    let known: Vec<(String, Type)> = self.sym_tab[0]
+                        .symbols
+                        .iter()
+                        .filter(|s| s.case == Convention::Screaming && !s.is_fn)
+                        .map(|s| (s.id_name.clone(), s.typ.clone()))
+                        .collect();
+                    let mut ty = const_type(value, &known);
+                    if ty == Type::Bool && matches!(value.kind, ExprKind::Binary(..)) {
+                        ty = Type::Int(Sign::Plain);
+                    }
+                    if let Some(symbol) = self.sym_tab[0].symbols.iter_mut().rev().find(|s| s.id_name == words[1]) {
+                        symbol.typ = ty;
                     }
-                    head += 1;
                 }
-
+                parsed.map(|value| {
+                    Decl::Const(ConstDef {
+                        name: words[1].clone(),
+                        ty: Type::Int(Sign::Plain),
+                        value,
+                        pos,
+                    })
+                })
             };
+            match parsed {
+                Ok(decl) => return Ok(decl),
+                Err(err) => self.report(err),
+            }
         }
-        //return the rust lexeme to main
-        stream
+        Ok(Decl::Directive(format!("#{}", words.join(" ")), pos))
     }
 
 
     /**
- * print_lexemes: DEBUG_ONLY
- * prints the lexemes in the lexeme vector
- * from index start to end
- */
-    fn print_lexemes(lexeme: &Vec<Token>, start: usize, end: usize) {
-        println!("----------lexeme-start------------");
-        for i in start..end {
-            println!("{}> {}", i, lexeme[i].get_token_value());
+     * parse_macro:
+     * parses the parameters and body of a function-like macro,
+     * line starts at the macro name
+     */
+    fn parse_macro(&mut self, line: &[Token], pos: Pos) -> PResult<MacroDef> {
+        let name = line[0].get_token_value();
+        let mut params: Vec<String> = Vec::new();
+        let mut i = 2;
+        while line[i].get_token_value() != ")" {
+            if line[i].get_token_value() != "," {
+                params.push(line[i].get_token_value());
+            }
+            i += 1;
         }
-        println!("----------lexeme-end------------");
+        let mut body = line[i + 1..].to_vec();
+        let body = if body[0].get_token_type() == KEYWORD_DO {
+            let last = body.last().unwrap().clone();
+            body.push(Token::new(";".to_string(), BASE_NONE, SEMICOLON, last.get_token_ln(), last.get_token_id()));
+            MacroBody::Stmt(Box::new(self.parse_tokens(body, Parser::parse_statement)?))
+        } else {
+            MacroBody::Expr(self.parse_tokens(body, Parser::parse_expr)?)
+        };
+        self.macro_table.push(name.clone());
+        self.declare(&name, &Type::Void, true, Convention::Snake);
+        for param in &params {
+            self.declare(param, &Type::Void, false, Convention::Snake);
+        }
+        Ok(MacroDef {
+            name,
+            params,
+            body,
+            signature: None,
+            pos,
+        })
     }
 
 
+    /**
+     * parse_tokens:
+     * runs parse on tokens instead of the main stream,
+     * all of them have to be consumed
+     */
+    fn parse_tokens<T>(&mut self, tokens: Vec<Token>, parse: fn(&mut Parser) -> PResult<T>) -> PResult<T> {
+        let lexeme = std::mem::replace(&mut self.lexeme, tokens);
+        let head = std::mem::replace(&mut self.head, 0);
+        let mut parsed = parse(self);
+        if parsed.is_ok() && !self.at_end() {
+            parsed = self.error(format!("unexpected `{}`", self.peek(0).unwrap().get_token_value()));
+        }
+        self.lexeme = lexeme;
+        self.head = head;
+        parsed
+    }
+
 
     /**
- * parse_function:
- * parse c/c++ function into rust equivalent function
- */
-    fn parse_function(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        let mut head: usize = 3;
-        let mut lookahead: usize = head;
-        let mut stream: Vec<String> = Vec::new();
-
-        stream.push("fn".to_string());
-        stream.push(lexeme[1].get_token_value());
-        stream.push("(".to_string());
-
-        // parse arguments differenly for functions that are not main
-        // since rust does not have arguments or return type for main
-        if lexeme[1].get_token_type() != MAIN {
-
-            // collect arguments
-            while lexeme[lookahead].get_token_type() != RIGHT_BRACKET {
-                lookahead += 1;
-            }
-            while head < lookahead {
-                let l: Token = lexeme[head].clone();
-                temp_lexeme.push(l);
-                head += 1;
-            }
-            // parse arguments
-            stream.append(&mut self.parse_arguments(&temp_lexeme));
-            temp_lexeme.clear();
-
-            stream.push(")".to_string());
-
-            // parse return type
-            if let Some(rust_type) = parse_type(lexeme[0].get_token_type() as i32) {
-                if rust_type != "void" {
-                    stream.push("->".to_string());
-                    stream.push(rust_type);
+     * parse_specifiers:
+     * parses the type and storage specifiers in front of a declarator
+     */
+    fn parse_specifiers(&mut self) -> PResult<Specs> {
+        let mut specs = Specs {
+            ty: Type::Void,
+            is_const: false,
+            is_static: false,
+            is_typedef: false,
+            is_extern: false,
+            defined: None,
+        };
+        let mut sign = Sign::Plain;
+        let mut base: Option<lexeme::Type> = None;
+        let mut short = false;
+        let mut longs = 0;
+        let mut named: Option<Type> = None;
+
+        while let Some(typ) = self.peek_type(0) {
+            let seen = base.is_some() || named.is_some() || short || longs > 0 ||
+                       sign != Sign::Plain;
+            match typ {
+                KEYWORD_CONST | KEYWORD_CONSTEXPR => specs.is_const = true,
+                KEYWORD_STATIC => specs.is_static = true,
+                KEYWORD_EXTERN => {
+                    specs.is_extern = true;
+                    self.advance();
+                    // extern "C"
+                    if self.eat(STRING) && self.at(LEFT_CBRACE) {
+                        return self.error("linkage specification blocks are not supported".to_string());
+                    }
+                    continue;
+                }
+                // no effect on the translation
+                KEYWORD_INLINE | KEYWORD_REGISTER | KEYWORD_RESTRICT | KEYWORD_MUTABLE |
+                KEYWORD_EXPLICIT | KEYWORD_VIRTUAL | KEYWORD_FRIEND | KEYWORD_NORETURN => {}
+                KEYWORD_VOLATILE | KEYWORD_THREAD_LOCAL | KEYWORD_ATOMIC => self.drop_qualifier(),
+                KEYWORD_COMPLEX | KEYWORD_IMAGINARY | KEYWORD_ALIGNAS => return self.unsupported(),
+                PRIMITIVE_TYPEDEF => specs.is_typedef = true,
+                KEYWORD_SIGNED => sign = Sign::Signed,
+                KEYWORD_UNSIGNED => sign = Sign::Unsigned,
+                PRIMITIVE_SHORT => short = true,
+                PRIMITIVE_LONG => longs += 1,
+                PRIMITIVE_INT | PRIMITIVE_CHAR | PRIMITIVE_FLOAT | PRIMITIVE_DOUBLE |
+                PRIMITIVE_BOOL | PRIMITIVE_VOID | KEYWORD_AUTO | TYPE_STRING | PRIMITIVE_WCHAR |
+                PRIMITIVE_CHAR16 | PRIMITIVE_CHAR32 if base.is_none() => {
+                    base = Some(typ)
+                }
+                KEYWORD_STRUCT | KEYWORD_UNION | KEYWORD_CLASS | KEYWORD_ENUM if !seen => {
+                    let (ty, defined) = self.parse_record_specifier()?;
+                    named = Some(ty);
+                    specs.defined = defined;
+                    continue;
+                }
+                IDENTIFIER if !seen => {
+                    named = Some(self.parse_named_type()?);
+                    continue;
                 }
+                _ => break,
             }
+            self.advance();
+        }
+
+        specs.ty = match (named, base) {
+            (Some(ty), _) => ty,
+            (None, Some(PRIMITIVE_CHAR)) => Type::Char(sign),
+            (None, Some(PRIMITIVE_FLOAT)) => Type::Float,
+            (None, Some(PRIMITIVE_DOUBLE)) => Type::Double,
+            (None, Some(PRIMITIVE_BOOL)) => Type::Bool,
+            (None, Some(PRIMITIVE_VOID)) => Type::Void,
+            (None, Some(KEYWORD_AUTO)) => Type::Auto,
+            (None, Some(TYPE_STRING)) => Type::Str,
+            // wchar_t is 32 bits wide and signed on Unix
+            (None, Some(PRIMITIVE_WCHAR)) => Type::Int(Sign::Signed),
+            (None, Some(PRIMITIVE_CHAR16)) => Type::Short(Sign::Unsigned),
+            (None, Some(PRIMITIVE_CHAR32)) => Type::Int(Sign::Unsigned),
+            (None, _) if short => Type::Short(sign),
+            (None, _) if longs == 1 => Type::Long(sign),
+            (None, _) if longs > 1 => Type::LongLong(sign),
+            (None, Some(PRIMITIVE_INT)) => Type::Int(sign),
+            (None, None) if sign != Sign::Plain => Type::Int(sign),
+            _ => return self.error("expected a type".to_string()),
+        };
+        Ok(specs)
+    }
+
 
-            stream.push("{".to_string());
+    /**
+     * parse_named_type:
+     * parses a type name or a standard class template like
+     * `std::vector<int>`, head is at the name
+     */
+    fn parse_named_type(&mut self) -> PResult<Type> {
+        if self.at_std_template(0) {
+            self.advance();
+            self.advance();
         }
-        // declare argc and argv inside main, if required
-        else {
-            //parsing main function
-            self.in_main = true;
-            stream.push(")".to_string());
-            stream.push("{".to_string());
-            if lexeme[head].get_token_type() != RIGHT_BRACKET {
+        let name = self.advance().unwrap().get_token_value();
+        if !headers::is_template(&name) || !self.eat(OP_LT) {
+            return Ok(Type::Named(name));
+        }
+        let mut args = vec![self.parse_type_name()?];
+        while self.eat(COMMA) {
+            args.push(self.parse_type_name()?);
+        }
+        self.expect(OP_GT, "`>`")?;
+        Ok(Type::Template(name, args))
+    }
 
-                if self.strict == false {
-                    stream.push(NO_STRICT.get_doc().to_string());
-                    stream.push("let mut argv: Vec<_> = std::env::args().collect();".to_string());
-                    stream.push("let mut argc = argv.len();".to_string());
-                } else {
-                    stream.push(STRICT.get_doc().to_string());
+    // checks for `std::` followed by a standard class template at n
+    fn at_std_template(&self, n: usize) -> bool {
+        self.peek(n).is_some_and(|t| t.get_token_value() == "std") && self.at_n(n + 1, OP_SCOPE) &&
+        self.peek(n + 2).is_some_and(|t| headers::is_template(&t.get_token_value()))
+    }
 
-                    stream.push("let argv: Vec<_> = std::env::args().collect();".to_string());
-                    stream.push("let argc = argv.len();".to_string());
-                }
 
+    /**
+     * parse_record_specifier:
+     * parses `struct|union|class|enum [name] [{...}]`
+     * returns the named type and the definition, if a body is present
+     */
+    fn parse_record_specifier(&mut self) -> PResult<(Type, Option<Decl>)> {
+        let pos = self.pos();
+        let keyword = self.advance().unwrap().get_token_type();
+        let mut name = String::new();
+        if self.at(IDENTIFIER) {
+            name = self.advance().unwrap().get_token_value();
+            self.record_table.push(name.clone());
+        }
+        // base class list of `class A : public B`
+        if keyword == KEYWORD_CLASS && self.at(COLON) {
+            while !self.at(LEFT_CBRACE) && !self.at_end() {
+                self.advance();
             }
-
-        }
-
-        while lexeme[head].get_token_type() != LEFT_CBRACE {
-            head += 1
         }
-        head += 1;
-
-        // collect function body
-        // len - 1  so that '}' is excluded
-        while head < lexeme.len() - 1 {
-            let l: Token = lexeme[head].clone();
-            temp_lexeme.push(l);
-            head += 1;
+        if !self.at(LEFT_CBRACE) {
+            if name.is_empty() {
+                return self.error("expected a name or a body".to_string());
+            }
+            return Ok((Type::Named(name), None));
         }
-        // parse function body
-        stream.append(&mut self.parse_program(&temp_lexeme));
-        stream.push("}".to_string());
-        self.in_main = false;
-        stream
+        let defined = match keyword {
+            KEYWORD_ENUM => Decl::Enum(self.parse_enum(&name, pos)?),
+            KEYWORD_UNION => Decl::Record(self.parse_union(&name, pos)?),
+            KEYWORD_CLASS => Decl::Record(self.parse_class(&name, pos)?),
+            _ => Decl::Record(self.parse_struct(&name, pos)?),
+        };
+        Ok((Type::Named(name), Some(defined)))
     }
 
 
     /**
- * parse-arguments:
- * parse c/c++ formal arguments in the function signature
- * into rust equivalent arguments
- */
-    fn parse_arguments(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
-        while head < lexeme.len() {
-            if lexeme[head].get_token_type() == COMMA {
-                stream.push(",".to_string());
-                head += 1;
-                continue;
+     * parse_declarator:
+     * parses pointer stars, the declared name and array dimensions
+     * the name is empty for abstract declarators
+     */
+    fn parse_declarator(&mut self, specs: &Specs) -> PResult<(String, Type, Pos)> {
+        let mut ty = specs.ty.clone();
+        // `const char *` points to constant chars
+        if specs.is_const && (self.at(OP_MUL) || self.at(OP_BITAND)) {
+            ty = Type::Const(Box::new(ty));
+        }
+        // references are translated the same way as pointers
+        while self.at(OP_MUL) || self.at(OP_BITAND) {
+            self.advance();
+            ty = Type::Pointer(Box::new(ty));
+            loop {
+                match self.peek_type(0) {
+                    Some(KEYWORD_CONST) | Some(KEYWORD_RESTRICT) => {}
+                    Some(KEYWORD_VOLATILE) => self.drop_qualifier(),
+                    _ => break,
+                }
+                self.advance();
             }
-            // push identifier
-            stream.push(lexeme[head + 1].get_token_value()); //int f(int val)
-            stream.push(":".to_string());
-
-            // parse argument type
-            if let Some(rust_type) = parse_type(lexeme[head].get_token_type() as i32) {
-                stream.push(rust_type);
+        }
+        let pos = self.pos();
+        let name = match self.peek_type(0) {
+            Some(IDENTIFIER) | Some(MAIN) => self.advance().unwrap().get_token_value(),
+            _ => String::new(),
+        };
+        let mut dims: Vec<Option<Box<Expr>>> = Vec::new();
+        while self.eat(LEFT_SBRACKET) {
+            if self.eat(RIGHT_SBRACKET) {
+                dims.push(None);
+                continue;
             }
-            head += 2;
+            dims.push(Some(Box::new(self.parse_expr()?)));
+            self.expect(RIGHT_SBRACKET, "`]`")?;
         }
-        stream
+        for dim in dims.into_iter().rev() {
+            ty = Type::Array(Box::new(ty), dim);
+        }
+        Ok((name, ty, pos))
     }
 
 
     /**
- * parse_declaration:
- * parse c/c++ declaration into rust
- * equivalent statements
- */
-    fn parse_declaration(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-
-        //  let mut sym_tab: Vec<SymbolTable> = Vec::new();
-        //self.sym_tab.clear();
-        let mut sym: SymbolTable = SymbolTable {
-            typ: -1,
-            id_name: "undefined_var".to_string(),
-            is_assigned: false,
-            is_ptr: false,
-            assigned_val: "NONE".to_string(),
-            its_constant: false,
-        };
+     * parse_type_name:
+     * parses a type without a declarator, as used by sizeof
+     */
+    fn parse_type_name(&mut self) -> PResult<Type> {
+        let specs = self.parse_specifiers()?;
+        let (_, ty, _) = self.parse_declarator(&specs)?;
+        Ok(ty)
+    }
 
-        //index modifier from enums
-        let mut type_: usize = 0;
 
-        // find the type accoriding to the modifiers used in input code
-        let type_mod = match lexeme[0].get_token_type() {
+    /**
+     * parse_declaration:
+     * parses a declaration, function definition or typedef
+     */
+    fn parse_declaration(&mut self) -> PResult<Vec<Decl>> {
+        let pos = self.pos();
+        match self.peek_type(0) {
+            Some(KEYWORD_USING) => return self.parse_using(),
+            Some(KEYWORD_NAMESPACE) | Some(KEYWORD_TEMPLATE) | Some(KEYWORD_ASM) |
+            Some(KEYWORD_STATIC_ASSERT) | Some(KEYWORD_EXPORT) | Some(KEYWORD_OPERATOR) => {
+                return self.unsupported()
+            }
+            _ => {}
+        }
+        let mut specs = self.parse_specifiers()?;
+        let mut decls: Vec<Decl> = Vec::new();
+        if let Some(defined) = specs.defined.take() {
+            decls.push(defined);
+        }
+        // struct A {...};
+        if self.eat(SEMICOLON) {
+            return Ok(decls);
+        }
+
+        if specs.is_typedef {
+            decls = self.parse_typdef(decls, &specs)?;
+            return Ok(decls);
+        }
 
-            KEYWORD_UNSIGNED => {
-                type_ = 1;
-                8
+        let (name, ty, npos) = self.parse_declarator(&specs)?;
+        if name.is_empty() {
+            return self.error("expected an identifier".to_string());
+        }
+        if self.at(LEFT_BRACKET) {
+            self.declare(&name, &ty, true, Convention::Snake);
+            decls.push(Decl::Function(self.parse_function(name, ty, pos)?));
+            return Ok(decls);
+        }
+
+        let mut vars: Vec<VarDecl> = Vec::new();
+        let (mut name, mut ty, mut npos) = (name, ty, npos);
+        loop {
+            let init = if self.eat(OP_ASSIGN) {
+                let mut init = self.parse_initializer()?;
+                self.convert(&mut init);
+                self.cast_to(&mut init, &ty);
+                Some(init)
+            } else {
+                None
+            };
+            // the `const` of `const char *s` is the pointee's, not the variable's
+            let is_const = specs.is_const && !ty.innermost().is_pointer();
+            let case = if is_const || !self.in_function() { Convention::Screaming } else { Convention::Snake };
+            self.add_symbol(SymbolTable {
+                typ: ty.clone(),
+                id_name: name.clone(),
+                is_assigned: init.is_some(),
+                is_ptr: ty.is_pointer(),
+                its_constant: is_const,
+                is_fn: false,
+                params: Vec::new(),
+                // statics and constants are upper case in Rust
+                case,
+            });
+            // extern declarations refer to a definition elsewhere
+            if specs.is_extern && init.is_none() && !self.in_function() {
+                self.externs.push(name.clone());
             }
-            KEYWORD_SIGNED => {
-                type_ = 1;
-                0
+            if !specs.is_extern || init.is_some() {
+                vars.push(VarDecl {
+                    name,
+                    ty,
+                    init,
+                    is_const,
+                    is_static: specs.is_static,
+                    is_mut: false,
+                    default_init: false,
+                    pos: npos,
+                });
             }
-            KEYWORD_CONST => {
-                type_ = 1;
-                sym.its_constant = true;
-                0
-            } 
-            _ => 0,
-        };
-        let typdef_type = lexeme[type_].get_token_value(); //get the type name
-
-        let mut head: usize = type_ + 1;
-        //let sym_idx:usize=0;
-        while head < lexeme.len() {
-
-            match lexeme[head].get_token_type() {
-
-                IDENTIFIER => sym.id_name = lexeme[head].get_token_value(),
-
-                OP_ASSIGN => {
-                    sym.is_assigned = true;
-                    sym.assigned_val = String::from("");
-                    head += 1;
-                    let mut br = 0;
-                    if sym.is_ptr == true {
-                        if lexeme[head].get_token_type() == NULL {
-                            while lexeme[head].get_token_type() != SEMICOLON &&
-                                  lexeme[head].get_token_type() != COMMA {
-                                head += 1;
-                            }
-                            sym.is_assigned = false;
-                        } else {
-                            head += 1;
-                        }
-                    }
-                    let mut temp_lex:Vec<Token> = Vec::new();
-					while lexeme[head].get_token_type() != SEMICOLON &&
-                          !(br == 0 && lexeme[head].get_token_type() == COMMA) {
-                        if lexeme[head].get_token_type() == LEFT_BRACKET {
-                            br += 1;
-                        }
-                        if lexeme[head].get_token_type() == RIGHT_BRACKET {
-                            br -= 1;
-                        }
-						temp_lex.push(lexeme[head].clone());
-					//parse assigned value for expression
+            if !self.eat(COMMA) {
+                break;
+            }
+            let next = self.parse_declarator(&specs)?;
+            name = next.0;
+            ty = next.1;
+            npos = next.2;
+        }
+        self.expect(SEMICOLON, "`;`")?;
+        if !vars.is_empty() {
+            decls.push(Decl::Var(vars));
+        }
+        Ok(decls)
+    }
+
+
+    /**
+     * parse_using:
+     * `using T = type;` is a typedef, using directives and declarations
+     * only bring names into scope and are dropped
+     */
+    fn parse_using(&mut self) -> PResult<Vec<Decl>> {
+        self.advance();
+        if self.at(IDENTIFIER) && self.at_n(1, OP_ASSIGN) {
+            let tok = self.advance().unwrap();
+            self.advance();
+            let ty = self.parse_type_name()?;
+            self.expect(SEMICOLON, "`;`")?;
+            self.typde_def_table.push(tok.get_token_value());
+            return Ok(vec![Decl::Typedef(Typedef {
+                name: tok.get_token_value(),
+                ty,
+                pos: pos_of(&tok),
+            })]);
+        }
+        while !self.eat(SEMICOLON) {
+            if self.advance().is_none() {
+                return self.error("expected `;`".to_string());
+            }
+        }
+        Ok(Vec::new())
+    }
 
-                        head += 1;
+    /* parse typedef definitions of form
+     * typedef typename newtype[, newtype];
+     * an anonymous struct takes the name of the typedef
+     */
+    fn parse_typdef(&mut self, mut decls: Vec<Decl>, specs: &Specs) -> PResult<Vec<Decl>> {
+        loop {
+            let (name, ty, pos) = self.parse_declarator(specs)?;
+            if name.is_empty() {
+                return self.error("expected a typedef name".to_string());
+            }
+            self.typde_def_table.push(name.clone());
+            let mut renamed = false;
+            if let Some(&mut Decl::Record(ref mut record)) = decls.last_mut() {
+                if ty == Type::Named(record.name.clone()) {
+                    if record.name.is_empty() {
+                        record.name = name.clone();
                     }
-					temp_lex.push(lexeme[head].clone());
-					let a_val = self.parse_expr(&temp_lex);
-					let mut a_value = String::new();
-					for val in a_val{
-						a_value = a_value+&val;
-					}
-					sym.assigned_val.push_str(a_value.as_str());
-					 
-                    continue;
+                    renamed = record.name == name;
                 }
+            }
+            if !renamed {
+                decls.push(Decl::Typedef(Typedef {
+                    name,
+                    ty,
+                    pos,
+                }));
+            }
+            if !self.eat(COMMA) {
+                break;
+            }
+        }
+        self.expect(SEMICOLON, "`;`")?;
+        Ok(decls)
+    }
 
-                SEMICOLON | COMMA => {
-                    // used enum value in the symbol table
-                    sym.typ = (lexeme[type_].get_token_type() as i32) + type_mod;
-                    //       println!("SYM TYPE {}",sym.typ);
-                    self.sym_tab.push(sym.clone());
-                }
-                OP_MUL => {
-                    sym.is_ptr = true;
-                }
-                _ => {
-                    sym.assigned_val.push_str(&lexeme[head].get_token_value());
-                }
 
-            };
-            head += 1;
+    /**
+     * parse_initializer:
+     * parses the value assigned in a declaration
+     */
+    fn parse_initializer(&mut self) -> PResult<Expr> {
+        if !self.at(LEFT_CBRACE) {
+            return self.parse_assignment();
+        }
+        let pos = self.pos();
+        self.advance();
+        let mut values: Vec<Expr> = Vec::new();
+        while !self.at(RIGHT_CBRACE) {
+            values.push(self.parse_initializer()?);
+            if !self.eat(COMMA) {
+                break;
+            }
         }
+        self.expect(RIGHT_CBRACE, "`}`")?;
+        Ok(Expr::new(ExprKind::InitList(values), pos))
+    }
+
 
-        if self.strict == false {
-            stream.push(NO_STRICT.get_doc().to_string());
+    /**
+     * parse_function:
+     * parse c/c++ function, head is at the argument list
+     */
+    fn parse_function(&mut self, name: String, ret: Type, pos: Pos) -> PResult<Function> {
+        self.scoped(ScopeKind::Function, |parser| parser.parse_function_scope(name, ret, pos))
+    }
+
+    // parses the parameters and body inside the function scope
+    fn parse_function_scope(&mut self, name: String, ret: Type, pos: Pos) -> PResult<Function> {
+        let (params, variadic) = self.parse_arguments()?;
+        // const qualified method
+        self.eat(KEYWORD_CONST);
+
+        for (i, param) in params.iter().enumerate() {
+            // main gets `argv: Vec<String>` and `argc = argv.len()`
+            let ty = match i {
+                0 if name == "main" => Type::Size,
+                1 if name == "main" => Type::Template("vector".to_string(), vec![Type::Str]),
+                _ => param.ty.clone(),
+            };
+            self.declare(&param.name, &ty, false, Convention::Snake);
+        }
+        // the function itself is declared in the enclosing scope
+        let outer = self.sym_tab.len() - 2;
+        if let Some(func) = self.sym_tab[outer].symbols.iter_mut().rev().find(|s| s.is_fn && s.id_name == name) {
+            func.params = params.iter().map(|p| p.ty.clone()).collect();
+        }
+        let body = if self.at(LEFT_CBRACE) {
+            let outer = self.ret_type.replace(ret.clone());
+            let body = self.parse_block();
+            self.ret_type = outer;
+            Some(body?)
         } else {
-            stream.push(STRICT.get_doc().to_string());
+            // prototype, possibly pure virtual
+            while !self.at(SEMICOLON) && !self.at_end() {
+                self.advance();
+            }
+            self.expect(SEMICOLON, "`;`")?;
+            None
+        };
+        Ok(Function {
+            name,
+            ret,
+            params,
+            variadic,
+            body,
+            pos,
+        })
+    }
+
+
+    /**
+     * parse-arguments:
+     * parse c/c++ formal arguments in the function signature
+     */
+    fn parse_arguments(&mut self) -> PResult<(Vec<Param>, bool)> {
+        let mut params: Vec<Param> = Vec::new();
+        let mut variadic = false;
+        self.expect(LEFT_BRACKET, "`(`")?;
+        if self.eat(RIGHT_BRACKET) {
+            return Ok((params, variadic));
+        }
+        // int f(void)
+        if self.at(PRIMITIVE_VOID) && self.at_n(1, RIGHT_BRACKET) {
+            self.advance();
+            self.advance();
+            return Ok((params, variadic));
+        }
+        loop {
+            if self.eat(OP_ELLIPSIS) {
+                variadic = true;
+                break;
+            }
+            let specs = self.parse_specifiers()?;
+            let (name, ty, pos) = self.parse_declarator(&specs)?;
+            // array parameters are pointers in C
+            let ty = match ty {
+                Type::Array(inner, _) if specs.is_const => Type::Pointer(Box::new(Type::Const(inner))),
+                Type::Array(inner, _) => Type::Pointer(inner),
+                ty => ty,
+            };
+            params.push(Param {
+                name,
+                ty,
+                is_mut: false,
+                pos,
+            });
+            if !self.eat(COMMA) {
+                break;
+            }
         }
+        self.expect(RIGHT_BRACKET, "`)`")?;
+        Ok((params, variadic))
+    }
 
-        //from `from` start declaration statement generation
-        let (_, sym_table_right) = self.sym_tab.split_at(self.from);
-        for i in sym_table_right {
-            // get identifier
-            //for declaration out of any blocks(global)
-            self.from += 1;
-            if i.its_constant {
-                stream.push("const".to_string());
 
-            } else {
+    /**
+     * parse_struct:
+     * parses the body of a struct, head is at the opening brace
+     */
+    fn parse_struct(&mut self, name: &str, pos: Pos) -> PResult<Record> {
+        self.scoped(ScopeKind::Record, |parser| parser.parse_record_body(RecordKind::Struct, name, pos))
+    }
 
-                if self.strict == false {
-                    if self.in_block_stmnt == true {
-                        stream.push("let mut".to_string());
-                    } else {
-                        stream.push("static mut".to_string());
+    //parse tagged union
+    fn parse_union(&mut self, name: &str, pos: Pos) -> PResult<Record> {
+        self.scoped(ScopeKind::Record, |parser| parser.parse_record_body(RecordKind::Union, name, pos))
+    }
+
+    fn parse_class(&mut self, name: &str, pos: Pos) -> PResult<Record> {
+        self.scoped(ScopeKind::Record, |parser| parser.parse_record_body(RecordKind::Class, name, pos))
+    }
+
+    /**
+     * parse_record_body:
+     * parses fields, methods and access labels of a struct, union or class
+     */
+    fn parse_record_body(&mut self, kind: RecordKind, name: &str, pos: Pos) -> PResult<Record> {
+        let mut record = Record {
+            kind,
+            name: name.to_string(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+            ctors: Vec::new(),
+            pos,
+        };
+        let mut access = Access::Default;
+        self.expect(LEFT_CBRACE, "`{`")?;
+        while !self.eat(RIGHT_CBRACE) {
+            if self.at_end() {
+                return self.error("unterminated body".to_string());
+            }
+            let tok = self.peek(0).unwrap().clone();
+            match tok.get_type() {
+                (BASE_MODIFIER, _) => {
+                    self.advance();
+                    self.expect(COLON, "`:`")?;
+                    access = match tok.get_token_type() {
+                        KEYWORD_PUBLIC => Access::Public,
+                        KEYWORD_PRIVATE => Access::Private,
+                        _ => Access::Protected,
+                    };
+                    continue;
+                }
+                // constructor or destructor
+                (_, IDENTIFIER) | (_, OP_BITNEG)
+                    if (tok.get_token_value() == name && self.at_n(1, LEFT_BRACKET)) ||
+                       (tok.get_token_type() == OP_BITNEG &&
+                        self.peek(1).is_some_and(|t| t.get_token_value() == name)) => {
+                    let start = self.index_of(0).unwrap();
+                    while !self.at(LEFT_CBRACE) && !self.at(SEMICOLON) && !self.at_end() {
+                        self.advance();
                     }
-                } else {
-                    if self.in_block_stmnt == true {
-                        stream.push("let".to_string());
+                    if self.at(LEFT_CBRACE) {
+                        self.skip_block()?;
                     } else {
-                        stream.push("static".to_string());
+                        self.expect(SEMICOLON, "`;`")?;
                     }
+                    record.ctors.push(self.text_from(start));
+                    continue;
                 }
+                _ => {}
             }
-            stream.push(i.id_name.clone());
-            stream.push(":".to_string());
-
-            if i.is_ptr == true {
-                stream.push("&".to_string());
 
-
-                if self.strict == false {
-                    stream.push("mut".to_string());
-                }
+            let specs = self.parse_specifiers()?;
+            if self.eat(SEMICOLON) {
+                continue;
             }
-            // get the rust type
-            if let Some(rust_type) = parse_type(i.typ) {
-                if rust_type == "_" {
-                    stream.pop();
-                } else {
-                    stream.push(rust_type);
+            let (fname, ty, fpos) = self.parse_declarator(&specs)?;
+            if self.at(LEFT_BRACKET) {
+                self.declare(&fname, &ty, true, Convention::Snake);
+                self.struct_mem.push(StructMem {
+                    name: name.to_string(),
+                    typ: ty.clone(),
+                    identifier: fname.clone(),
+                });
+                let func = self.parse_function(fname, ty, fpos)?;
+                record.methods.push(Method {
+                    access,
+                    func,
+                });
+                continue;
+            }
+            let (mut fname, mut ty, mut fpos) = (fname, ty, fpos);
+            loop {
+                self.declare(&fname, &ty, false, Convention::Snake);
+                self.struct_mem.push(StructMem {
+                    name: name.to_string(),
+                    typ: ty.clone(),
+                    identifier: fname.clone(),
+                });
+                record.fields.push(Field {
+                    name: fname,
+                    ty,
+                    access,
+                    pos: fpos,
+                });
+                if !self.eat(COMMA) {
+                    break;
                 }
-            } else {
-                // if type parser dint return Some type check in typedef table
-                stream.push(typdef_type.clone());
-                //stream.push("UNKNOWN_TYPE".to_string());
+                let next = self.parse_declarator(&specs)?;
+                fname = next.0;
+                ty = next.1;
+                fpos = next.2;
             }
+            self.expect(SEMICOLON, "`;`")?;
+        }
+        Ok(record)
+    }
 
 
-            // take care of assignment
-            if i.is_assigned {
-                stream.push("=".to_string());
-                if i.is_ptr == true {
-                    stream.push("&".to_string());
-                }
-                if self.strict == false && i.is_ptr == true {
-                    stream.push("mut".to_string());
-                }
+    /**
+     * parse_enum:
+     * parses the enumerator list, head is at the opening brace
+     */
+    fn parse_enum(&mut self, name: &str, pos: Pos) -> PResult<EnumDef> {
+        let mut def = EnumDef {
+            name: name.to_string(),
+            variants: Vec::new(),
+            pos,
+        };
+        self.expect(LEFT_CBRACE, "`{`")?;
+        while !self.at(RIGHT_CBRACE) {
+            let vpos = self.pos();
+            let vname = self.expect(IDENTIFIER, "an enumerator")?.get_token_value();
+            self.declare(&vname, &Type::Named(name.to_string()), false, Convention::Camel);
+            let value = if self.eat(OP_ASSIGN) {
+                Some(self.parse_assignment()?)
+            } else {
+                None
+            };
+            def.variants.push(Enumerator {
+                name: vname,
+                value,
+                pos: vpos,
+            });
+            if !self.eat(COMMA) {
+                break;
+            }
+        }
+        self.expect(RIGHT_CBRACE, "`}`")?;
+        Ok(def)
+    }
 
-                stream.push((&i.assigned_val).to_string());
 
+    /**
+     * parse_block:
+     * parses `{ statements }`
+     */
+    fn parse_block(&mut self) -> PResult<Vec<Stmt>> {
+        self.scoped(ScopeKind::Block, Parser::parse_block_body)
+    }
+
+    fn parse_block_body(&mut self) -> PResult<Vec<Stmt>> {
+        self.expect(LEFT_CBRACE, "`{`")?;
+        let mut stmts: Vec<Stmt> = Vec::new();
+        loop {
+            if let Some(c) = self.take_comment() {
+                stmts.push(Stmt::new(StmtKind::Comment(c.get_token_value()), pos_of(&c)));
+                continue;
+            }
+            if self.eat(RIGHT_CBRACE) {
+                break;
+            }
+            if self.at_end() {
+                return self.error("expected `}`".to_string());
             }
-            stream.push(";".to_string());
+            stmts.append(&mut self.parse_block_item());
         }
-        // println!("{:?}",stream);
-        stream
+        Ok(stmts)
     }
 
-    /* parse simple typedef definition of form
-     * typedef typename newtype;
+
+    /**
+     * parse_block_item:
+     * parses one statement or declaration inside a block,
+     * falling back to verbatim source on errors
      */
-    fn parse_typdef(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        stream.push("type".to_string());
-        stream.push(lexeme[2].get_token_value() + "=");
-        self.typde_def_table.push(lexeme[2].get_token_value());
-        if let Some(typ) = parse_type(lexeme[1].get_token_type() as i32) {
-            stream.push(typ);
+    fn parse_block_item(&mut self) -> Vec<Stmt> {
+        let start = self.head;
+        let pos = self.pos();
+        let parsed = if self.is_decl_start() {
+            self.parse_declaration()
+                .map(|decls| decls.into_iter().map(|d| Stmt::new(StmtKind::Decl(d), pos)).collect())
         } else {
-            stream.push("UNKNOWN_TYPE".to_string());
+            self.parse_statement().map(|s| vec![s])
+        };
+        match parsed {
+            Ok(stmts) => stmts,
+            Err(err) => {
+                self.report(err);
+                vec![Stmt::new(StmtKind::Verbatim(self.recover(start)), pos)]
+            }
         }
-        stream.push(";".to_string());
-        return stream;
     }
 
+
     /**
- * parse_if:
- * parse c/c++ if statements into rust
- * equivalent statements
- */
-    fn parse_if(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
+     * parse_statement:
+     * parses a single statement
+     */
+    fn parse_statement(&mut self) -> PResult<Stmt> {
+        let pos = self.pos();
+        let typ = match self.peek_type(0) {
+            Some(t) => t,
+            None => return self.error("unexpected end of input".to_string()),
+        };
+        let kind = match typ {
+            LEFT_CBRACE => StmtKind::Block(self.parse_block()?),
+            // selection and iteration statements are scopes of their own
+            KEYWORD_IF => return self.scoped(ScopeKind::Block, Parser::parse_if),
+            KEYWORD_WHILE => return self.scoped(ScopeKind::Block, Parser::parse_while),
+            KEYWORD_DO => return self.scoped(ScopeKind::Block, Parser::parse_dowhile),
+            KEYWORD_FOR => return self.scoped(ScopeKind::Block, Parser::parse_for),
+            KEYWORD_SWITCH => return self.scoped(ScopeKind::Block, Parser::parse_switch),
+            KEYWORD_BREAK => {
+                self.advance();
+                self.expect(SEMICOLON, "`;`")?;
+                StmtKind::Break
+            }
+            KEYWORD_CONTINUE => {
+                self.advance();
+                self.expect(SEMICOLON, "`;`")?;
+                StmtKind::Continue
+            }
+            KEYWORD_RETURN => {
+                self.advance();
+                let mut value = if self.at(SEMICOLON) {
+                    None
+                } else {
+                    Some(self.parse_expr()?)
+                };
+                if let (Some(value), Some(ret)) = (value.as_mut(), self.ret_type.as_ref()) {
+                    self.cast_to(value, ret);
+                }
+                self.expect(SEMICOLON, "`;`")?;
+                StmtKind::Return(value)
+            }
+            SEMICOLON => {
+                self.advance();
+                StmtKind::Empty
+            }
+            HEADER_INCLUDE if self.at_value("#") => StmtKind::Decl(self.parse_directive()?),
+            _ if self.is_decl_start() => {
+                let mut decls = self.parse_declaration()?;
+                if decls.len() == 1 {
+                    StmtKind::Decl(decls.pop().unwrap())
+                } else {
+                    StmtKind::Block(decls.into_iter()
+                        .map(|d| Stmt::new(StmtKind::Decl(d), pos))
+                        .collect())
+                }
+            }
+            _ => {
+                let expr = self.parse_expr()?;
+                self.expect(SEMICOLON, "`;`")?;
+                StmtKind::Expr(expr)
+            }
+        };
+        Ok(Stmt::new(kind, pos))
+    }
 
-        stream.push("if".to_string());
-        stream.push("(".to_string());
-        head += 1;
 
-        //skip '('
-        head += 1;
+    // parses `( expr )` of if, while, do-while and switch
+    fn parse_condition(&mut self) -> PResult<Expr> {
+        self.expect(LEFT_BRACKET, "`(`")?;
+        let cond = self.parse_expr()?;
+        self.expect(RIGHT_BRACKET, "`)`")?;
+        Ok(cond)
+    }
 
-        // condition
-        while lexeme[head].get_token_type() != RIGHT_BRACKET {
-            stream.push(lexeme[head].get_token_value());
-            head += 1;
-        }
-        head += 1;
-        stream.push(")".to_string());
-        stream.push("== true".to_string());
-        stream.push("{".to_string());
 
-        if lexeme[head].get_token_type() == LEFT_CBRACE {
-            head += 1;
-        }
+    /**
+     * parse_if:
+     * parse c/c++ if statements along with the else branch
+     */
+    fn parse_if(&mut self) -> PResult<Stmt> {
+        let pos = self.pos();
+        self.advance();
+        let mut cond = self.parse_condition()?;
+        self.condition(&mut cond);
+        let then = self.parse_statement()?;
+        let otherwise = if self.eat(KEYWORD_ELSE) {
+            Some(Box::new(self.parse_statement()?))
+        } else {
+            None
+        };
+        Ok(Stmt::new(StmtKind::If(cond, Box::new(then), otherwise), pos))
+    }
 
-        // collect if body
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        while head < lexeme.len() {
-            let l: Token = lexeme[head].clone();
-            temp_lexeme.push(l);
-            head += 1;
-        }
-        // parse if body
-        stream.append(&mut self.parse_program(&temp_lexeme));
 
-        stream.push("}".to_string());
-        stream
+    /**
+     * parse_while:
+     * parse c/c++ while statements
+     */
+    fn parse_while(&mut self) -> PResult<Stmt> {
+        let pos = self.pos();
+        self.advance();
+        let mut cond = self.parse_condition()?;
+        self.condition(&mut cond);
+        let body = self.parse_statement()?;
+        Ok(Stmt::new(StmtKind::While(cond, Box::new(body)), pos))
     }
 
 
     /**
- * parse_while:
- * parse c/c++ while statements into rust
- * equivalent statements
- */
-    fn parse_while(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
-        let mut no_cond = false;
-        head += 1;
+     * parse_dowhile:
+     * parse c/c++ do while statements
+     */
+    fn parse_dowhile(&mut self) -> PResult<Stmt> {
+        let pos = self.pos();
+        self.advance();
+        let body = self.parse_statement()?;
+        self.expect(KEYWORD_WHILE, "`while`")?;
+        let mut cond = self.parse_condition()?;
+        self.condition(&mut cond);
+        self.expect(SEMICOLON, "`;`")?;
+        Ok(Stmt::new(StmtKind::DoWhile(Box::new(body), cond), pos))
+    }
 
-        //skip '('
-        head += 1;
-        // condition
-        let mut cond_stream: Vec<String> = Vec::new();
-        while lexeme[head].get_token_type() != RIGHT_BRACKET {
-            cond_stream.push(lexeme[head].get_token_value());
-            head += 1;
-        }
-        if cond_stream.len() == 1 && (cond_stream[0] == "1" || cond_stream[0] == "true") {
-            no_cond = true;
-        }
-        head += 1;
 
-        if lexeme[head].get_token_type() == LEFT_CBRACE {
-            head += 1;
-        }
+    /**
+     * parse_for:
+     * parse c/c++ for statements, any of the three clauses may be empty
+     */
+    fn parse_for(&mut self) -> PResult<Stmt> {
+        let pos = self.pos();
+        self.advance();
+        self.expect(LEFT_BRACKET, "`(`")?;
+
+        let init = if self.eat(SEMICOLON) {
+            None
+        } else if self.is_decl_start() {
+            let ipos = self.pos();
+            let mut decls = self.parse_declaration()?;
+            Some(Box::new(Stmt::new(StmtKind::Decl(decls.pop().unwrap()), ipos)))
+        } else {
+            let ipos = self.pos();
+            let expr = self.parse_expr()?;
+            self.expect(SEMICOLON, "`;`")?;
+            Some(Box::new(Stmt::new(StmtKind::Expr(expr), ipos)))
+        };
 
-        // collect while body
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        while head < lexeme.len() {
-            let l: Token = lexeme[head].clone();
-            temp_lexeme.push(l);
-            head += 1;
-        }
-        // parse while body
-        let mut body_stream = &mut self.parse_program(&temp_lexeme);
+        let cond = if self.at(SEMICOLON) {
+            None
+        } else {
+            let mut cond = self.parse_expr()?;
+            self.condition(&mut cond);
+            Some(cond)
+        };
+        self.expect(SEMICOLON, "`;`")?;
 
-        if no_cond == true {
-            stream.push("loop".to_string());
+        let update = if self.at(RIGHT_BRACKET) {
+            None
         } else {
-            stream.push("while".to_string());
-            stream.push("(".to_string());
-            stream.append(&mut cond_stream);
-            stream.push(")".to_string());
-            stream.push("== true".to_string());
-        }
-        stream.push("{".to_string());
-        stream.append(&mut body_stream);
+            Some(self.parse_expr()?)
+        };
+        self.expect(RIGHT_BRACKET, "`)`")?;
 
-        stream.push("}".to_string());
-        stream
+        let body = self.parse_statement()?;
+        Ok(Stmt::new(StmtKind::For(init, cond, update, Box::new(body)), pos))
     }
 
 
     /**
- * parse_dowhile:
- * parse c/c++ do while statements into rust
- * equivalent statements
- */
-    fn parse_dowhile(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut temp_stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
-        let mut lookahead: usize;
-
-        head += 2;
-        lookahead = head;
-
-        lookahead = skip_block(&lexeme, lookahead) - 1;
-        // collect while body
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        while head < lookahead {
-            let l: Token = lexeme[head].clone();
-            temp_lexeme.push(l);
-            head += 1;
-        }
-        // parse while body
-
-        temp_stream.append(&mut self.parse_program(&temp_lexeme));
-        temp_lexeme.clear();
-
-        head += 3;
-        if (lexeme[head].get_token_value() == "1" || lexeme[head].get_token_value() == "true") &&
-           lexeme[head + 1].get_token_type() == RIGHT_BRACKET {
-            stream.push("loop".to_string());
-            stream.push("{".to_string());
-            stream.append(&mut temp_stream);
-
-            stream.push("}".to_string());
-        } else {
-            stream.push("while".to_string());
-            stream.push("{".to_string());
-            stream.append(&mut temp_stream);
-            stream.push("(".to_string());
-            while lexeme[head].get_token_type() != RIGHT_BRACKET {
-                stream.push(lexeme[head].get_token_value());
-                head += 1;
-            }
-            stream.push(")".to_string());
-            stream.push("== true".to_string());
-            stream.push("}".to_string());
-            stream.push("{".to_string());
-            stream.push("}".to_string());
-        }
-        stream.push(";".to_string());
-        stream
-    }
-
-
-    fn parse_switch(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut head: usize = 2;
-        let mut lookahead: usize = 2;
-        let mut stream: Vec<String> = Vec::new();
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-
-        stream.push("match".to_string());
-
-        // find starting of switch block
-        while lexeme[lookahead].get_token_type() != LEFT_CBRACE {
-            lookahead += 1;
-        }
-        // {
-        // move back to find the variable/result to be matched
-        lookahead -= 1;
-        // single variable
-        if lookahead - head == 1 {
-            stream.push(lexeme[lookahead - 1].get_token_value());
-        }
-        // expression
-        else {
-            while head < lookahead {
-                let l: Token = lexeme[head].clone();
-                temp_lexeme.push(l);
-                head += 1;
-            }
-            head -= 1;
-            stream.append(&mut self.parse_program(&temp_lexeme));
-
-
-            temp_lexeme.clear();
-        }
-        // move forward to the starting of the block
-        head += 3;
-        stream.push("{".to_string());
-
-        //head is at case
-        lookahead = skip_block(&lexeme, head) - 1;
-        while head < lookahead {
-            let l: Token = lexeme[head].clone();
-            temp_lexeme.push(l);
-            head += 1;
-        }
-        stream.append(&mut self.parse_case(&temp_lexeme));
-        stream.push("}".to_string());
-        stream
-    }
-
-
-    fn parse_case(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        //head is at case
-        let mut head: usize = 0;
-        let mut lookahead: usize;
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        let mut def: bool = false;
-        //look whether default case is handled for exaustive search
-        while head < lexeme.len() {
-            if lexeme[head].get_token_type() == KEYWORD_DEFAULT {
-                stream.push("_".to_string());
-                def = true;
-            } else {
-                head += 1; //head is at matching value
-                stream.push(lexeme[head].get_token_value());
-            }
+     * parse_switch:
+     * parse c/c++ switch statement
+     */
+    fn parse_switch(&mut self) -> PResult<Stmt> {
+        let pos = self.pos();
+        self.advance();
+        let cond = self.parse_condition()?;
+        let cases = self.parse_case()?;
+        Ok(Stmt::new(StmtKind::Switch(cond, cases), pos))
+    }
 
-            head += 1; // head is at :
-            stream.push("=>".to_string());
 
-            // either brace or no brace
-            head += 1;
-            if lexeme[head].get_token_type() == LEFT_CBRACE {
-                head += 1;
-                lookahead = skip_block(&lexeme, head) - 1;
-            } else {
-                lookahead = head;
-                while lookahead < lexeme.len() &&
-                      lexeme[lookahead].get_token_type() != KEYWORD_CASE &&
-                      lexeme[lookahead].get_token_type() != KEYWORD_DEFAULT {
-                    lookahead += 1;
+    /**
+     * parse_case:
+     * parses the switch body into its case labels,
+     * every label starts a new case
+     */
+    fn parse_case(&mut self) -> PResult<Vec<Case>> {
+        let mut cases: Vec<Case> = Vec::new();
+        self.expect(LEFT_CBRACE, "`{`")?;
+        loop {
+            if let Some(c) = self.take_comment() {
+                if let Some(case) = cases.last_mut() {
+                    case.body.push(Stmt::new(StmtKind::Comment(c.get_token_value()), pos_of(&c)));
                 }
+                continue;
             }
-            while head < lookahead {
-                let l: Token = lexeme[head].clone();
-                temp_lexeme.push(l);
-                head += 1;
+            if self.eat(RIGHT_CBRACE) {
+                break;
             }
-            stream.push("{".to_string());
-            stream.append(&mut self.parse_program(&temp_lexeme));
-            stream.push("}".to_string());
-
-
-            if head < lexeme.len() && lexeme[head].get_token_type() == RIGHT_CBRACE {
-                head += 1;
+            let pos = self.pos();
+            let label = match self.peek_type(0) {
+                Some(KEYWORD_CASE) => {
+                    self.advance();
+                    Some(self.parse_expr()?)
+                }
+                Some(KEYWORD_DEFAULT) => {
+                    self.advance();
+                    None
+                }
+                None => return self.error("expected `}`".to_string()),
+                _ => {
+                    let mut stmts = self.parse_block_item();
+                    match cases.last_mut() {
+                        Some(case) => case.body.append(&mut stmts),
+                        None => return self.error("statement before the first case".to_string()),
+                    }
+                    continue;
+                }
+            };
+            self.expect(COLON, "`:`")?;
+            // a label directly after another one shares its body
+            let stacked = cases.last_mut().filter(|case| {
+                case.body.iter().all(|s| matches!(s.kind, StmtKind::Comment(_)))
+            });
+            let case = match stacked {
+                Some(case) => case,
+                None => {
+                    cases.push(Case {
+                        labels: Vec::new(),
+                        is_default: false,
+                        body: Vec::new(),
+                        pos,
+                    });
+                    cases.last_mut().unwrap()
+                }
+            };
+            match label {
+                Some(label) => case.labels.push(label),
+                None => case.is_default = true,
             }
-            temp_lexeme.clear();
-        }
-        if def == false {
-            stream.push("_".to_string());
-            stream.push("=>".to_string());
-            stream.push("{".to_string());
-            stream.push("}".to_string());
         }
-        stream
+        Ok(cases)
     }
 
 
-    /**
- * parse_for:
- * parse c/c++ do while statements into rust
- * equivalent statements
- *
- * Identify infinite loops and replace for with loop{} 
- */
-    fn parse_for(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
-        let mut lookahead: usize;
-        let mut temp_lexeme: Vec<Token> = Vec::new();
+    /* parse_expr:
+     * parse c/c++ expression, including the comma operator
+     */
+    fn parse_expr(&mut self) -> PResult<Expr> {
+        let mut first = self.parse_assignment()?;
+        if !self.at(COMMA) {
+            self.convert(&mut first);
+            return Ok(first);
+        }
+        let pos = first.pos;
+        let mut list = vec![first];
+        while self.eat(COMMA) {
+            list.push(self.parse_assignment()?);
+        }
+        let mut expr = Expr::new(ExprKind::Comma(list), pos);
+        self.convert(&mut expr);
+        Ok(expr)
+    }
 
-        while lexeme[head].get_token_type() != LEFT_BRACKET {
-            head += 1;
-        }
-        head += 1;
-        lookahead = head;
 
-        //for (int i =0; )
-        let decl: bool = if lexeme[head].get_base_type() == BASE_DATATYPE {
-            true
-        } else {
-            false
+    /* parse_assignment:
+     * parse c/c++ assignment expressions, right associative
+     */
+    fn parse_assignment(&mut self) -> PResult<Expr> {
+        let lhs = self.parse_conditional()?;
+        let op = match self.peek(0).and_then(assignop_of) {
+            Some(op) => op,
+            None => return Ok(lhs),
         };
-        // let mut no_init:bool; //no initialization
-        let mut no_cond: bool = false; //if no condition to terminate
-        let mut no_updation: bool = false; //no inc/dec of loop counter
+        self.advance();
+        let rhs = self.parse_assignment()?;
+        let pos = lhs.pos;
+        Ok(Expr::new(ExprKind::Assign(op, Box::new(lhs), Box::new(rhs)), pos))
+    }
 
 
+    /* parse_conditional:
+     * parse the ternary operator, right associative
+     * so `a ? b : c ? d : e` nests in the else branch
+     */
+    fn parse_conditional(&mut self) -> PResult<Expr> {
+        let cond = self.parse_binary(1)?;
+        if !self.eat(OP_TERNARY) {
+            return Ok(cond);
+        }
+        let then = self.parse_expr()?;
+        self.expect(COLON, "`:`")?;
+        let otherwise = self.parse_assignment()?;
+        let pos = cond.pos;
+        Ok(Expr::new(ExprKind::Cond(Box::new(cond), Box::new(then), Box::new(otherwise)), pos))
+    }
 
-        let mut body: Vec<String> = Vec::new();
-        let mut updation: Vec<String> = Vec::new();
-        let mut term_cond: Vec<String> = Vec::new();
-        // initial assignment
-        lookahead = skip_stmt(&lexeme, lookahead);
 
-        //incase of initialization expressio for (;i<10;i++) ; common case
-        if head + 1 < lookahead {
-            while head < lookahead {
-                let l: Token = lexeme[head].clone();
-                temp_lexeme.push(l);
-                head += 1;
-            }
+    /* parse_binary:
+     * precedence climbing over the binary operators
+     * binding at least as tight as min_prec
+     */
+    fn parse_binary(&mut self, min_prec: u8) -> PResult<Expr> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let (op, prec) = match self.peek(0).and_then(binop_of) {
+                Some((op, prec)) if prec >= min_prec => (op, prec),
+                _ => break,
+            };
+            self.advance();
+            let rhs = self.parse_binary(prec + 1)?;
+            let pos = lhs.pos;
+            lhs = Expr::new(ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)), pos);
+        }
+        Ok(lhs)
+    }
 
-            if decl == true {
-                stream.append(&mut self.parse_declaration(&temp_lexeme));
-            } else {
-                stream.append(&mut self.parse_assignment(&temp_lexeme));
+
+    // checks whether the token at n begins a type name, as in a cast or sizeof
+    fn is_type_start(&self, n: usize) -> bool {
+        match self.peek(n) {
+            Some(t) => {
+                match t.get_type() {
+                    (BASE_DATATYPE, _) | (_, KEYWORD_STRUCT) | (_, KEYWORD_UNION) |
+                    (_, KEYWORD_CLASS) | (_, KEYWORD_ENUM) => true,
+                    (_, IDENTIFIER) => self.is_type_name(&t.get_token_value()),
+                    _ => false,
+                }
             }
-        } else {
-            head += 1;
-            // no_init = true;
+            None => false,
         }
-        temp_lexeme.clear();
-
-        // terminating condition
-        lookahead = skip_stmt(&lexeme, lookahead);
+    }
 
 
-        if head + 1 < lookahead {
-            while head < lookahead - 1 {
-                term_cond.push(lexeme[head].get_token_value());
-                head += 1;
+    /* parse_unary:
+     * parse prefix operators, casts and sizeof
+     */
+    fn parse_unary(&mut self) -> PResult<Expr> {
+        let pos = self.pos();
+        if self.at(LEFT_BRACKET) && self.is_type_start(1) {
+            self.advance();
+            let ty = self.parse_type_name()?;
+            self.expect(RIGHT_BRACKET, "`)`")?;
+            let operand = self.parse_unary()?;
+            return Ok(Expr::new(ExprKind::Cast(ty, Box::new(operand)), pos));
+        }
+        let op = match self.peek_type(0) {
+            Some(OP_MINUS) => UnOp::Neg,
+            Some(OP_PLUS) => UnOp::Plus,
+            Some(OP_LOGNOT) => UnOp::Not,
+            Some(OP_BITNEG) => UnOp::BitNot,
+            Some(OP_BITAND) => UnOp::AddrOf,
+            Some(OP_MUL) => UnOp::Deref,
+            Some(OP_INC) => UnOp::PreInc,
+            Some(OP_DEC) => UnOp::PreDec,
+            Some(OP_SIZEOF) => {
+                self.advance();
+                return self.parse_sizeof(pos);
             }
-        } else {
-            no_cond = true;
-        }
-        head += 1;
-        temp_lexeme.clear();
+            _ => return self.parse_postfix(),
+        };
+        self.advance();
+        let operand = self.parse_unary()?;
+        Ok(Expr::new(ExprKind::Unary(op, Box::new(operand)), pos))
+    }
 
-        lookahead = head;
-        // update expression
-        while lexeme[lookahead].get_token_type() != RIGHT_BRACKET {
-            let l: Token = lexeme[lookahead].clone();
-            temp_lexeme.push(l);
-            lookahead += 1;
-        }
-        //no_updation
-        if head == lookahead {
-            no_updation = true;
-        } else {
 
-            temp_lexeme.push(Token::new(String::from(";"), BASE_NONE, SEMICOLON, 0, 0));
-            updation.append(&mut self.parse_program(&temp_lexeme));
-            temp_lexeme.clear();
-        }
-        head = lookahead;
-        head += 1;
-        if lexeme[head].get_token_type() == LEFT_CBRACE {
-            head += 1;
-            lookahead = skip_block(&lexeme, head);
-        } else {
-            lookahead = skip_stmt(&lexeme, head);
+    // sizeof(type) or sizeof expr, head is past the sizeof keyword
+    fn parse_sizeof(&mut self, pos: Pos) -> PResult<Expr> {
+        if self.at(LEFT_BRACKET) && self.is_type_start(1) {
+            self.advance();
+            let ty = self.parse_type_name()?;
+            self.expect(RIGHT_BRACKET, "`)`")?;
+            return Ok(Expr::new(ExprKind::SizeofType(ty), pos));
         }
+        let operand = self.parse_unary()?;
+        Ok(Expr::new(ExprKind::SizeofExpr(Box::new(operand)), pos))
+    }
 
-        // lookahead = skip_block(&lexeme, lookahead);
-        while head < lookahead {
-            let l: Token = lexeme[head].clone();
-            temp_lexeme.push(l);
-            head += 1;
-        }
-        body.append(&mut self.parse_program(&temp_lexeme));
 
-        if no_cond == true {
-            stream.push("loop".to_string());
-        } else {
-            stream.push("while".to_string());
-            stream.append(&mut term_cond); //append termianating condition
+    /* parse_postfix:
+     * parse calls, indexing, member access and postfix inc/dec
+     */
+    fn parse_postfix(&mut self) -> PResult<Expr> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            let pos = expr.pos;
+            let kind = match self.peek_type(0) {
+                Some(LEFT_BRACKET) => {
+                    self.advance();
+                    let mut args: Vec<Expr> = Vec::new();
+                    while !self.at(RIGHT_BRACKET) {
+                        args.push(self.parse_assignment()?);
+                        if !self.eat(COMMA) {
+                            break;
+                        }
+                    }
+                    self.expect(RIGHT_BRACKET, "`)`")?;
+                    if let ExprKind::Ident(ref name) = expr.kind {
+                        if self.macro_table.contains(name) {
+                            let types = args.iter().map(|a| self.arg_type(a)).collect();
+                            self.macro_calls.push((name.clone(), types));
+                        }
+                    }
+                    ExprKind::Call(Box::new(expr), args)
+                }
+                Some(LEFT_SBRACKET) => {
+                    self.advance();
+                    let index = self.parse_expr()?;
+                    self.expect(RIGHT_SBRACKET, "`]`")?;
+                    ExprKind::Index(Box::new(expr), Box::new(index))
+                }
+                Some(OP_DOT) => {
+                    self.advance();
+                    let member = self.expect(IDENTIFIER, "a member name")?;
+                    ExprKind::Member(Box::new(expr), member.get_token_value())
+                }
+                // `p->x` is `(*p).x`, `this` becomes `self`, which is no pointer
+                Some(OP_ARROW) => {
+                    self.advance();
+                    let member = self.expect(IDENTIFIER, "a member name")?;
+                    let base = if expr.kind == ExprKind::This {
+                        expr
+                    } else {
+                        Expr::new(ExprKind::Unary(UnOp::Deref, Box::new(expr)), pos)
+                    };
+                    ExprKind::Member(Box::new(base), member.get_token_value())
+                }
+                Some(OP_INC) => {
+                    self.advance();
+                    ExprKind::Unary(UnOp::PostInc, Box::new(expr))
+                }
+                Some(OP_DEC) => {
+                    self.advance();
+                    ExprKind::Unary(UnOp::PostDec, Box::new(expr))
+                }
+                _ => break,
+            };
+            expr = Expr::new(kind, pos);
         }
-        stream.push("{".to_string());
-        stream.append(&mut body);
-        if no_updation != true {
-            stream.append(&mut updation);
+        Ok(expr)
+    }
+
+
+    // type of a macro argument, if it is a literal or a plain variable
+    fn arg_type(&self, arg: &Expr) -> Option<Type> {
+        match arg.kind {
+            ExprKind::Ident(ref name) => {
+                // functions, macro parameters and constants have no known type
+                self.lookup(name)
+                    .filter(|s| !s.is_fn && s.typ != Type::Void)
+                    .map(|s| s.typ.clone())
+                    .filter(|ty| !matches!(*ty, Type::Pointer(_) | Type::Array(..)))
+            }
+            ExprKind::IntLit(_) | ExprKind::FloatLit(_) | ExprKind::CharLit(_) | ExprKind::BoolLit(_) => {
+                Some(const_type(arg, &[]))
+            }
+            ExprKind::Unary(UnOp::Neg, ref e) => self.arg_type(e),
+            _ => None,
         }
+    }
 
-        stream.push("}".to_string());
 
-        stream
+    /**
+     * expr_type:
+     * C type of an expression from the declarations in scope, once its
+     * operands are converted. None when it is not known
+     */
+    fn expr_type(&self, expr: &Expr) -> Option<Type> {
+        match expr.kind {
+            ExprKind::IntLit(_) | ExprKind::FloatLit(_) | ExprKind::CharLit(_) | ExprKind::BoolLit(_) => {
+                Some(const_type(expr, &[]))
+            }
+            ExprKind::Ident(ref name) => {
+                match self.lookup(name) {
+                    Some(s) => Some(s.typ.clone()).filter(|ty| !s.is_fn && *ty != Type::Void),
+                    None => self.std_name(name).filter(|n| n.params.is_empty() && n.ty != Type::Void).map(|n| n.ty.clone()),
+                }
+            }
+            ExprKind::Unary(op, ref e) => {
+                match op {
+                    UnOp::Not => Some(Type::Bool),
+                    UnOp::AddrOf => self.expr_type(e).map(|ty| Type::Pointer(Box::new(ty))),
+                    UnOp::Deref => self.expr_type(e).and_then(element_type),
+                    UnOp::Neg | UnOp::Plus | UnOp::BitNot => {
+                        self.expr_type(e).filter(is_arithmetic).map(|ty| if is_literal(e) { ty } else { promote(ty) })
+                    }
+                    _ => self.expr_type(e),
+                }
+            }
+            ExprKind::Binary(op, ref l, ref r) => {
+                match op {
+                    BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::Eq | BinOp::Ne | BinOp::And |
+                    BinOp::Or => Some(Type::Bool),
+                    BinOp::Shl | BinOp::Shr => self.shifted_type(l),
+                    _ => self.operand_type(op, l, r),
+                }
+            }
+            ExprKind::Assign(_, ref l, _) => self.expr_type(l),
+            // both branches convert like the operands of ==
+            ExprKind::Cond(_, ref t, ref o) => self.operand_type(BinOp::Eq, t, o),
+            ExprKind::Cast(ref ty, _) => Some(ty.clone()),
+            ExprKind::Call(ref f, _) => {
+                match f.kind {
+                    ExprKind::Ident(ref name) => {
+                        match self.lookup(name) {
+                            Some(s) => Some(s.typ.clone()).filter(|ty| s.is_fn && *ty != Type::Void),
+                            None => self.std_name(name).map(|n| n.ty.clone()).filter(|ty| *ty != Type::Void),
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            ExprKind::Index(ref base, _) => self.expr_type(base).and_then(element_type),
+            ExprKind::Member(ref base, ref member) => {
+                let record = match self.expr_type(base) {
+                    Some(Type::Named(name)) => name,
+                    Some(Type::Pointer(inner)) => {
+                        match inner.unqualified() {
+                            Type::Named(name) => name.clone(),
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                };
+                self.struct_mem
+                    .iter()
+                    .rev()
+                    .find(|m| m.name == record && m.identifier == *member)
+                    .map(|m| m.typ.clone())
+            }
+            // size_t, usize in Rust, is cast wherever it is used
+            ExprKind::SizeofType(_) | ExprKind::SizeofExpr(_) => Some(Type::Long(Sign::Unsigned)),
+            ExprKind::Comma(ref list) => list.last().and_then(|e| self.expr_type(e)),
+            _ => None,
+        }
     }
 
 
+    /**
+     * operand_type:
+     * type both operands of an arithmetic, bitwise or comparison operator
+     * are converted to, following C's usual arithmetic conversions.
+     * Integers narrower than int are promoted, but comparisons and bitwise
+     * operators on two values of the same type give the same result without.
+     * Literals take the type of the other operand, unless that would turn
+     * a floating point literal into an integer
+     */
+    fn operand_type(&self, op: BinOp, l: &Expr, r: &Expr) -> Option<Type> {
+        let mut lt = self.expr_type(l).filter(is_arithmetic)?;
+        let mut rt = self.expr_type(r).filter(is_arithmetic)?;
+        match (is_literal(l), is_literal(r)) {
+            (true, false) if !is_float(&lt) || is_float(&rt) => lt = rt.clone(),
+            (false, true) if !is_float(&rt) || is_float(&lt) => rt = lt.clone(),
+            _ => {}
+        }
+        let keeps_type = matches!(op,
+                                  BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::Eq | BinOp::Ne |
+                                  BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor);
+        if keeps_type && same_repr(&lt, &rt) {
+            return Some(lt);
+        }
+        Some(wider(promote(lt), promote(rt)))
+    }
 
+    // type of the left operand of a shift, which is promoted on its own
+    fn shifted_type(&self, l: &Expr) -> Option<Type> {
+        let ty = self.expr_type(l).filter(is_arithmetic)?;
+        Some(if is_literal(l) { ty } else { promote(ty) })
+    }
 
-    /* parse_assignment:
-     * parse c/c++ assignment statements into rust equivalent code
-     * compound assignments must be converted to declarations
-     * as rust doesnt support compound assignment
+
+    /**
+     * truth:
+     * turns a C condition into a Rust bool, `n` gives `n != 0`, a pointer
+     * `p` gives `!p.is_null()` and negate gives `n == 0` and `p.is_null()`.
+     * An array is never null. Conditions of unknown type are kept as they are
      */
-    fn parse_assignment(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        // let mut lookahead = lexeme.len();
-        let mut thead: usize = 2;
-        let mut lexeme1: Vec<Token> = Vec::new();
-
-        let mut n = 2;
-        let m = 3;
-
-        let mut tstream: Vec<String> = Vec::new();
-        // if lexeme[0].get_token_type() == OP_MUL {println!("DEBUG @1272 "); m+=1; n+=1;thead+=1;}
-        if lexeme[n].get_base_type() == BASE_UNOP {
-
-            while lexeme[thead].get_token_type() != SEMICOLON {
-                lexeme1.push(lexeme[thead].clone());
-                thead += 1;
-            }
-            lexeme1.push(lexeme[thead].clone());
-            stream.push(lexeme[0].get_token_value());
-            stream.push(lexeme[1].get_token_value());
-            stream.append(&mut self.parse_expr(&lexeme1));
-        } else if lexeme[m].get_base_type() == BASE_UNOP {
-
-            while lexeme[thead].get_token_type() != SEMICOLON {
-                lexeme1.push(lexeme[thead].clone());
-                thead += 1;
-            }
-            lexeme1.push(lexeme[thead].clone());
-            stream.push(lexeme[0].get_token_value());
-            stream.push(lexeme[1].get_token_value());
-            stream.append(&mut self.parse_expr(&lexeme1));
-        } else if lexeme[m].get_base_type() == BASE_BINOP {
-            while lexeme[thead].get_token_type() != SEMICOLON {
-                lexeme1.push(lexeme[thead].clone());
-                thead += 1;
-            }
-            lexeme1.push(lexeme[thead].clone());
-            stream.push(lexeme[0].get_token_value());
-            stream.push(lexeme[1].get_token_value());
-            stream.append(&mut self.parse_expr(&lexeme1));
-        } else if lexeme[n].get_token_type() == OP_BITAND {
-            stream.push(lexeme[0].get_token_value());
-            stream.push(lexeme[1].get_token_value());
-
-            while lexeme[thead].get_token_type() != SEMICOLON {
-                stream.push(lexeme[thead].get_token_value());
-                thead += 1;
+    fn truth(&self, cond: Expr, negate: bool) -> Expr {
+        let pos = cond.pos;
+        if let ExprKind::IntLit(ref lit) = cond.kind {
+            if let Some(value) = lit.value() {
+                return Expr::new(ExprKind::BoolLit((value != 0) != negate), pos);
             }
-        } else {
+        }
+        let ty = self.expr_type(&cond);
+        let truth = match ty {
+            Some(Type::Array(..)) if is_pure(&cond) => ExprKind::BoolLit(!negate),
+            Some(Type::Pointer(_)) => {
+                let is_null = method_call(cond, "is_null");
+                return if negate { is_null } else { Expr::new(ExprKind::Unary(UnOp::Not, Box::new(is_null)), pos) };
+            }
+            Some(ref ty) if is_arithmetic(ty) && *ty != Type::Bool => {
+                let digits = if is_float(ty) { "0.0" } else { "0" };
+                let zero = NumLit {
+                    digits: digits.to_string(),
+                    radix: Radix::Decimal,
+                    suffix: String::new(),
+                };
+                let zero = if is_float(ty) { ExprKind::FloatLit(zero) } else { ExprKind::IntLit(zero) };
+                let op = if negate { BinOp::Eq } else { BinOp::Ne };
+                ExprKind::Binary(op, Box::new(cond), Box::new(Expr::new(zero, pos)))
+            }
+            _ if negate => ExprKind::Unary(UnOp::Not, Box::new(cond)),
+            _ => return cond,
+        };
+        Expr::new(truth, pos)
+    }
 
-            if lexeme[m].get_token_type() == OP_ASSIGN {
-                while lexeme[thead].get_token_type() != SEMICOLON &&
-                      lexeme[thead].get_token_type() != COMMA {
-                    lexeme1.push(lexeme[thead].clone());
-                    thead += 1;
+
+    // replaces a condition by its truth in place
+    fn condition(&self, cond: &mut Expr) {
+        let pos = cond.pos;
+        let value = std::mem::replace(cond, Expr::new(ExprKind::Null, pos));
+        *cond = self.truth(value, false);
+    }
+
+
+    /**
+     * convert:
+     * inserts the casts C does implicitly in arithmetic, comparisons,
+     * assignments and arguments, so the Rust operands have the same type
+     */
+    fn convert(&self, expr: &mut Expr) {
+        match expr.kind {
+            ExprKind::Unary(_, ref mut e) | ExprKind::Cast(_, ref mut e) | ExprKind::Member(ref mut e, _) |
+            ExprKind::SizeofExpr(ref mut e) => self.convert(e),
+            ExprKind::Binary(_, ref mut l, ref mut r) | ExprKind::Assign(_, ref mut l, ref mut r) |
+            ExprKind::Index(ref mut l, ref mut r) => {
+                self.convert(l);
+                self.convert(r);
+            }
+            ExprKind::Cond(ref mut c, ref mut t, ref mut o) => {
+                self.convert(c);
+                self.convert(t);
+                self.convert(o);
+            }
+            ExprKind::Call(ref mut f, ref mut args) => {
+                self.convert(f);
+                for a in args {
+                    self.convert(a);
                 }
-                lexeme1.push(lexeme[thead].clone());
-                stream.append(&mut self.parse_program(&lexeme1));
             }
-            stream.push(lexeme[0].get_token_value());
-            stream.push(lexeme[1].get_token_value());
-            if lexeme[n].get_base_type() == BASE_UNOP {
-                stream.push(lexeme[m].get_token_value());
-            } else {
-                stream.push(lexeme[n].get_token_value());
-                n += 1;
-                if lexeme[n].get_token_type() == LEFT_BRACKET ||
-                   lexeme[n].get_token_type() == LEFT_SBRACKET {
-                    while lexeme[n].get_token_type() != SEMICOLON {
-                        stream.push(lexeme[n].get_token_value());
-                        n += 1;
+            ExprKind::InitList(ref mut list) | ExprKind::Comma(ref mut list) => {
+                for e in list {
+                    self.convert(e);
+                }
+            }
+            _ => {}
+        }
+
+        let pos = expr.pos;
+        match expr.kind {
+            // arrays and vectors are indexed with usize
+            ExprKind::Index(ref base, ref mut index) => {
+                let base_type = self.expr_type(base);
+                let indexed = match base_type {
+                    Some(Type::Array(..)) | Some(Type::Pointer(_)) => true,
+                    Some(Type::Template(ref name, _)) => name == "vector",
+                    _ => false,
+                };
+                let literal = matches!(index.kind, ExprKind::IntLit(ref lit) if lit.suffix.is_empty());
+                if indexed && !literal {
+                    let value = std::mem::replace(&mut **index, Expr::new(ExprKind::Null, pos));
+                    **index = Expr::new(ExprKind::Cast(Type::Size, Box::new(value)), pos);
+                }
+                // `p[i]` is `*p.add(i)`
+                if let Some(Type::Pointer(_)) = base_type {
+                    if let ExprKind::Index(base, index) = std::mem::replace(&mut expr.kind, ExprKind::Null) {
+                        let add = Expr::new(ExprKind::Member(base, "add".to_string()), pos);
+                        let element = Expr::new(ExprKind::Call(Box::new(add), vec![*index]), pos);
+                        expr.kind = ExprKind::Unary(UnOp::Deref, Box::new(element));
                     }
                 }
-                stream.push(";".to_string());
             }
+            ExprKind::Unary(UnOp::Neg, ref mut e) | ExprKind::Unary(UnOp::Plus, ref mut e) |
+            ExprKind::Unary(UnOp::BitNot, ref mut e) => {
+                if let Some(ty) = self.expr_type(e).filter(is_arithmetic) {
+                    self.cast_to(e, &promote(ty));
+                }
+            }
+            ExprKind::Unary(UnOp::Not, _) => {
+                if let ExprKind::Unary(_, e) = std::mem::replace(&mut expr.kind, ExprKind::Null) {
+                    *expr = self.truth(*e, true);
+                }
+            }
+            ExprKind::Binary(BinOp::And, ref mut l, ref mut r) | ExprKind::Binary(BinOp::Or, ref mut l, ref mut r) => {
+                self.condition(l);
+                self.condition(r);
+            }
+            ExprKind::Binary(BinOp::Shl, ref mut l, _) | ExprKind::Binary(BinOp::Shr, ref mut l, _) => {
+                if let Some(ty) = self.shifted_type(l) {
+                    self.cast_to(l, &ty);
+                }
+            }
+            ExprKind::Binary(op, ref mut l, ref mut r) => {
+                if let Some(ty) = self.operand_type(op, l, r) {
+                    self.cast_to(l, &ty);
+                    self.cast_to(r, &ty);
+                }
+            }
+            ExprKind::Cond(ref mut c, ref mut t, ref mut o) => {
+                self.condition(c);
+                if let Some(ty) = self.operand_type(BinOp::Eq, t, o) {
+                    self.cast_to(t, &ty);
+                    self.cast_to(o, &ty);
+                }
+            }
+            ExprKind::Assign(None, ref l, ref mut r) => {
+                if let Some(ty) = self.expr_type(l) {
+                    self.cast_to(r, &ty);
+                }
+            }
+            // Rust shifts take any integer on the right
+            ExprKind::Assign(Some(BinOp::Shl), ..) | ExprKind::Assign(Some(BinOp::Shr), ..) => {}
+            ExprKind::Assign(Some(op), ref mut l, ref mut r) => {
+                let (lt, ty) = match (self.expr_type(l).filter(is_arithmetic), self.operand_type(op, l, r)) {
+                    (Some(Type::Bool), _) => return,
+                    (Some(lt), Some(ty)) => (lt, ty),
+                    _ => return,
+                };
+                if same_repr(&lt, &ty) || !is_pure(l) {
+                    self.cast_to(r, &lt);
+                    return;
+                }
+                // i += 0.5 computes in double and converts back
+                let mut left = (**l).clone();
+                self.cast_to(&mut left, &ty);
+                self.cast_to(r, &ty);
+                let value = Expr::new(ExprKind::Binary(op, Box::new(left), r.clone()), pos);
+                **r = Expr::new(ExprKind::Cast(lt, Box::new(value)), pos);
+                expr.kind = match std::mem::replace(&mut expr.kind, ExprKind::Null) {
+                    ExprKind::Assign(_, l, r) => ExprKind::Assign(None, l, r),
+                    kind => kind,
+                };
+            }
+            ExprKind::Call(ref f, ref mut args) => {
+                let params = match f.kind {
+                    ExprKind::Ident(ref name) => {
+                        match self.lookup(name) {
+                            Some(s) => Some(s.params.clone()).filter(|_| s.is_fn),
+                            None => self.std_name(name).map(|n| n.params.to_vec()),
+                        }
+                    }
+                    _ => None,
+                };
+                for (arg, ty) in args.iter_mut().zip(params.unwrap_or_default()) {
+                    self.cast_to(arg, &ty);
+                }
+                // the pointer a `%s` of printf reads is a `const char *`
+                let format = match (&f.kind, args.first().map(|a| &a.kind)) {
+                    (ExprKind::Ident(name), Some(ExprKind::StrLit(format)))
+                        if self.lookup(name).is_none() && self.std_name(name).is_some_and(|n| n.rust.contains("$*")) => {
+                        headers::rust_format(format)
+                    }
+                    _ => None,
+                };
+                let c_string = Type::Pointer(Box::new(Type::Const(Box::new(Type::Char(Sign::Plain)))));
+                for (arg, conv) in args.iter_mut().skip(1).zip(format.map(|f| f.1).unwrap_or_default()) {
+                    let pointer = self.expr_type(arg).and_then(element_type);
+                    if conv == 's' && matches!(pointer, Some(Type::Char(_))) {
+                        let value = std::mem::replace(arg, Expr::new(ExprKind::Null, pos));
+                        *arg = Expr::new(ExprKind::Cast(c_string.clone(), Box::new(value)), pos);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 
+
+    /**
+     * cast_to:
+     * casts an expression to the given arithmetic type, unless it has that
+     * type already or is a literal Rust infers the type of. A suffixed
+     * literal loses its suffix if its value fits. Conversions to bool
+     * compare with zero instead
+     */
+    fn cast_to(&self, expr: &mut Expr, ty: &Type) {
+        if let (ExprKind::InitList(list), Type::Array(inner, _)) = (&mut expr.kind, ty) {
+            for e in list {
+                self.cast_to(e, inner);
+            }
+            return;
+        }
+        // a literal 0 is the null pointer
+        if ty.is_pointer() && matches!(expr.kind, ExprKind::IntLit(ref lit) if lit.value() == Some(0)) {
+            expr.kind = ExprKind::Null;
+            return;
+        }
+        if let Type::Pointer(ref inner) = *ty {
+            let pos = expr.pos;
+            let constant = matches!(**inner, Type::Const(_));
+            // a string literal for a `char *` is a NUL terminated byte string
+            if let (ExprKind::StrLit(text), &Type::Char(sign)) = (&expr.kind, inner.unqualified()) {
+                let mut bytes = text.clone().into_bytes();
+                bytes.push(0);
+                let mut value = method_call(Expr::new(ExprKind::ByteStrLit(bytes), pos), "as_ptr");
+                if !constant || sign == Sign::Signed {
+                    value = Expr::new(ExprKind::Cast(ty.clone(), Box::new(value)), pos);
+                }
+                *expr = value;
+                return;
+            }
+            // an array decays to a pointer to its first element
+            if let Some(Type::Array(..)) = self.expr_type(expr) {
+                let array = std::mem::replace(expr, Expr::new(ExprKind::Null, pos));
+                *expr = method_call(array, if constant { "as_ptr" } else { "as_mut_ptr" });
+                return;
+            }
+        }
+        if !is_arithmetic(ty) {
+            return;
+        }
+        let from = match self.expr_type(expr).filter(is_arithmetic) {
+            Some(from) => from,
+            None => return,
+        };
+        if *ty == Type::Bool {
+            if from != Type::Bool {
+                self.condition(expr);
+            }
+            return;
+        }
+        let sizeof = matches!(expr.kind, ExprKind::SizeofType(_) | ExprKind::SizeofExpr(_));
+        if same_repr(&from, ty) && !sizeof {
+            return;
+        }
+        if is_literal(expr) && is_float(&from) == is_float(ty) && unsuffix(expr, ty) {
+            return;
+        }
+        // 2 becomes 2.0 rather than 2 as f64
+        if let ExprKind::IntLit(ref mut lit) = expr.kind {
+            if is_float(ty) && lit.radix == Radix::Decimal && lit.suffix.is_empty() {
+                let digits = format!("{}.0", lit.digits);
+                expr.kind = ExprKind::FloatLit(NumLit {
+                    digits,
+                    radix: Radix::Decimal,
+                    suffix: String::new(),
+                });
+                return;
+            }
         }
-        if tstream.len() > 0 {
-            stream.append(&mut tstream);
+        let pos = expr.pos;
+        let mut value = std::mem::replace(expr, Expr::new(ExprKind::Null, pos));
+        // bool only converts to integers
+        if from == Type::Bool && is_float(ty) {
+            value = Expr::new(ExprKind::Cast(Type::Int(Sign::Plain), Box::new(value)), pos);
         }
-        stream
+        *expr = Expr::new(ExprKind::Cast(ty.clone(), Box::new(value)), pos);
     }
 
 
-    /* parse_expr:
-     * parse c/c++ expression statements into rust equivalent code
+    /* parse_primary:
+     * parse identifiers, literals and parenthesized expressions
      */
-    fn parse_expr(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        // let mut lookahead = lexeme.len();
-        let mut tstream: Vec<String> = Vec::new();
-        let mut thead: usize = 0;
-
-        let mut prev_id = " ".to_string();
-        let mut typ = OTHER;
-        //a=b+c++;
-
-        while lexeme[thead].get_token_type() != SEMICOLON {
-
-            if lexeme[thead].get_base_type() == BASE_UNOP {
-			if lexeme[thead].get_token_type() == OP_SIZEOF{
-				//println!(" {:?} ",lexeme);
-				stream.push("std::mem::size_of(".to_string());
-				thead+=2;
-				if lexeme[thead].get_base_type() == BASE_DATATYPE{
-					if let Some(t) = parse_type(lexeme[thead].get_token_type() as i32){
-						stream.push(t)
-					}
-				} else{
-					stream.push(lexeme[thead].get_token_value());
-				}
-				stream.push(")".to_string());
-				thead+=1;
-			}else{
-			//println!(" 1542 :unop");
-                //incase of post
-                if typ == IDENTIFIER {
-                    tstream.push(prev_id.clone());
-                    tstream.push(match lexeme[thead].get_token_type() {
-                        OP_INC => "+=1".to_string(),
-                        OP_DEC => "-=1".to_string(),
-                        _ => " ;".to_string(),
+    fn parse_primary(&mut self) -> PResult<Expr> {
+        let pos = self.pos();
+        let tok = match self.peek(0) {
+            Some(t) => t.clone(),
+            None => return self.error("expected an expression".to_string()),
+        };
+        let mut kind = match tok.get_token_type() {
+            IDENTIFIER | MAIN => ExprKind::Ident(tok.get_token_value()),
+            // `ns::name`, Rust paths use the same separator
+            OP_SCOPE if self.at_n(1, IDENTIFIER) => {
+                self.advance();
+                return self.parse_primary();
+            }
+            NUM_INT | NUM_FLOAT => {
+                let mut lit = num_lit(&tok);
+                let float = tok.get_token_type() == NUM_FLOAT;
+                if !is_c_suffix(&lit.suffix, float) {
+                    self.diagnostics.push(Diagnostic {
+                        msg: format!("the user-defined literal suffix `{}` of `{}` has no Rust equivalent, \
+                                      it is dropped",
+                                     lit.suffix,
+                                     tok.get_token_value()),
+                        pos,
                     });
-                    tstream.push(";".to_string());
-
-                    thead + 1;
-                    //continue;
+                    lit.suffix.clear();
                 }
-                // incase of pre
-                else {
-                    stream.push("(".to_string());
-                    stream.push(lexeme[thead + 1].get_token_value());
-                    stream.push(match lexeme[thead].get_token_type() {
-                        OP_INC => "+=1".to_string(),
-                        OP_DEC => "-=1".to_string(),
-                        _ => " ;".to_string(),
-                    });
-                    stream.push(")".to_string());
-                    thead += 1;
+                if float { ExprKind::FloatLit(lit) } else { ExprKind::IntLit(lit) }
+            }
+            CHAR_VAL => ExprKind::CharLit(literal_text(&tok)),
+            STRING => {
+                // adjacent string literals are concatenated
+                let mut bytes = literal_bytes(&tok);
+                self.advance();
+                while self.at(STRING) {
+                    bytes.extend(literal_bytes(&self.advance().unwrap()));
                 }
+                let kind = match String::from_utf8(bytes) {
+                    Ok(text) => ExprKind::StrLit(text),
+                    Err(err) => {
+                        self.diagnostics.push(Diagnostic {
+                            msg: "the string literal holds bytes which are not UTF-8, it becomes a byte string"
+                                .to_string(),
+                            pos,
+                        });
+                        ExprKind::ByteStrLit(err.into_bytes())
+                    }
+                };
+                return Ok(Expr::new(kind, pos));
+            }
+            TRUE_VAL => ExprKind::BoolLit(true),
+            FALSE_VAL => ExprKind::BoolLit(false),
+            NULL => ExprKind::Null,
+            KEYWORD_THIS => ExprKind::This,
+            // static_cast<T>(e)
+            KEYWORD_STATIC_CAST | KEYWORD_CONST_CAST | KEYWORD_REINTERPRET_CAST => {
+                self.advance();
+                self.expect(OP_LT, "`<`")?;
+                let ty = self.parse_type_name()?;
+                self.expect(OP_GT, "`>`")?;
+                self.expect(LEFT_BRACKET, "`(`")?;
+                let operand = self.parse_expr()?;
+                self.expect(RIGHT_BRACKET, "`)`")?;
+                return Ok(Expr::new(ExprKind::Cast(ty, Box::new(operand)), pos));
+            }
+            KEYWORD_GOTO | KEYWORD_TRY | KEYWORD_CATCH | KEYWORD_THROW | KEYWORD_DELETE |
+            KEYWORD_NOEXCEPT | KEYWORD_DECLTYPE | KEYWORD_TYPEID | KEYWORD_DYNAMIC_CAST |
+            KEYWORD_ALIGNOF | KEYWORD_GENERIC | KEYWORD_SIZEOF_PACK | KEYWORD_TYPENAME |
+            KEYWORD_TEMPLATE | KEYWORD_OPERATOR | KEYWORD_ASM | KEYWORD_STATIC_ASSERT |
+            KEYWORD_NAMESPACE => return self.unsupported(),
+            LEFT_BRACKET => {
+                self.advance();
+                // grouping is kept in the tree structure, the generator
+                // adds parentheses back wherever Rust precedence needs them
+                let inner = self.parse_expr()?;
+                self.expect(RIGHT_BRACKET, "`)`")?;
+                return Ok(inner);
+            }
+            _ => {
+                return self.error(format!("unexpected `{}` in expression", tok.get_token_value()))
+            }
+        };
+        self.advance();
+        if let ExprKind::Ident(ref mut path) = kind {
+            while self.at(OP_SCOPE) && self.at_n(1, IDENTIFIER) {
+                self.advance();
+                path.push_str("::");
+                path.push_str(&self.advance().unwrap().get_token_value());
+            }
+        }
+        Ok(Expr::new(kind, pos))
+    }
+} //close impl
 
-			}
-            } else {
-                stream.push(lexeme[thead].get_token_value());
+
+// ---------------------------------------------------------------
+// types of the constants made from #define
+// ---------------------------------------------------------------
+
+/**
+ * infer_const_types:
+ * a constant takes the type of its literal suffix or of the constants
+ * it is made of. A bare integer literal takes the integer type of the
+ * variables it initializes and the functions returning it, if they agree
+ * and it is used nowhere else
+ */
+fn infer_const_types(decls: &mut [Decl]) {
+    let mut uses: Vec<(String, Type)> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for decl in decls.iter() {
+        decl_uses(decl, &mut uses);
+        decl_names(decl, &mut names);
+    }
+    let mut known: Vec<(String, Type)> = Vec::new();
+    for decl in decls.iter_mut() {
+        let def = match *decl {
+            Decl::Const(ref mut def) => def,
+            _ => continue,
+        };
+        def.ty = const_type(&def.value, &known);
+        // comparisons and logical operators give an int in C
+        if def.ty == Type::Bool && matches!(def.value.kind, ExprKind::Binary(..)) {
+            let pos = def.value.pos;
+            let value = std::mem::replace(&mut def.value, Expr { kind: ExprKind::BoolLit(false), pos });
+            def.value = Expr {
+                kind: ExprKind::Cast(Type::Int(Sign::Plain), Box::new(value)),
+                pos,
+            };
+            def.ty = Type::Int(Sign::Plain);
+        }
+        let literal = match def.value.kind {
+            ExprKind::Unary(UnOp::Neg, ref e) => match e.kind {
+                ExprKind::IntLit(ref lit) => lit.value().map(|v| -(v as i128)),
+                _ => None,
+            },
+            ExprKind::IntLit(ref lit) => lit.value().map(|v| v as i128),
+            _ => None,
+        };
+        if let (Type::Int(Sign::Plain), Some(value)) = (&def.ty, literal) {
+            let mut used = uses.iter().filter(|u| u.0 == def.name).map(|u| &u.1);
+            if let Some(first) = used.next() {
+                let fits = int_range(first).is_some_and(|(min, max)| min <= value && value <= max);
+                let bare = uses.iter().filter(|u| u.0 == def.name).count();
+                let everywhere = bare == names.iter().filter(|n| **n == def.name).count();
+                if fits && everywhere && used.all(|t| t == first) {
+                    def.ty = first.clone();
+                }
             }
+        }
+        known.push((def.name.clone(), def.ty.clone()));
+    }
+}
 
-            typ = lexeme[thead].get_token_type();
-            prev_id = lexeme[thead].get_token_value();
+// the values the Rust type of an integer type holds
+fn int_range(ty: &Type) -> Option<(i128, i128)> {
+    let bits = match *ty {
+        Type::Char(_) => 8,
+        Type::Short(_) => 16,
+        Type::Int(_) => 32,
+        Type::Long(_) | Type::LongLong(_) => 64,
+        _ => return None,
+    };
+    let unsigned = match *ty {
+        Type::Char(sign) => sign != Sign::Signed,
+        Type::Short(sign) | Type::Int(sign) | Type::Long(sign) | Type::LongLong(sign) => sign == Sign::Unsigned,
+        _ => false,
+    };
+    Some(if unsigned {
+        (0, (1 << bits) - 1)
+    } else {
+        (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+    })
+}
 
-            thead += 1;
+// name of the constant a plain `N` or `-N` refers to, also when converted
+fn bare_name(expr: &Expr) -> Option<String> {
+    match expr.kind {
+        ExprKind::Ident(ref name) => Some(name.clone()),
+        ExprKind::Unary(UnOp::Neg, ref e) | ExprKind::Cast(_, ref e) => bare_name(e),
+        _ => None,
+    }
+}
+
+// collects the types names are used with in initializers and returns
+fn decl_uses(decl: &Decl, uses: &mut Vec<(String, Type)>) {
+    match *decl {
+        Decl::Var(ref vars) => {
+            for var in vars {
+                if let Some(name) = var.init.as_ref().and_then(bare_name) {
+                    uses.push((name, var.ty.clone()));
+                }
+            }
         }
-        stream.push(";".to_string());
-        if tstream.len() > 0 {
-            stream.append(&mut tstream);
+        Decl::Function(ref func) => {
+            for stmt in func.body.iter().flatten() {
+                stmt_uses(stmt, &func.ret, uses);
+            }
         }
-        stream
+        Decl::Record(ref record) => {
+            for method in &record.methods {
+                decl_uses(&Decl::Function(method.func.clone()), uses);
+            }
+        }
+        _ => {}
     }
+}
 
+// collects every identifier the initializers and function bodies refer to
+fn decl_names(decl: &Decl, names: &mut Vec<String>) {
+    match *decl {
+        Decl::Var(ref vars) => {
+            for init in vars.iter().filter_map(|v| v.init.as_ref()) {
+                init.names(names);
+            }
+        }
+        Decl::Function(ref func) => {
+            for stmt in func.body.iter().flatten() {
+                stmt.names(names);
+            }
+        }
+        Decl::Record(ref record) => {
+            for stmt in record.methods.iter().flat_map(|m| m.func.body.iter().flatten()) {
+                stmt.names(names);
+            }
+        }
+        _ => {}
+    }
+}
 
-    fn parse_array_declaration(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut typ: String = " ".to_string();
-
-        //int a[10];
-        if let Some(t) = parse_type(lexeme[0].get_token_type() as i32) {
-            typ = t;
+fn stmt_uses(stmt: &Stmt, ret: &Type, uses: &mut Vec<(String, Type)>) {
+    match stmt.kind {
+        StmtKind::Decl(ref decl) => decl_uses(decl, uses),
+        StmtKind::Return(Some(ref e)) => {
+            if let Some(name) = bare_name(e) {
+                uses.push((name, ret.clone()));
+            }
         }
-        if self.strict == true {
-            stream.push(STRICT.get_doc().to_string());
-            stream.push("let".to_string());
-        } else {
-            stream.push(NO_STRICT.get_doc().to_string());
-            stream.push("let mut".to_string());
-
-        }
-
-        let mut head = 0;
-        stream.push(lexeme[head + 1].get_token_value());
-        stream.push(":".to_string());
-        stream.push("[".to_string() + &typ[..] + ";" + &lexeme[head + 3].get_token_value()[..] + "]");
-        head = 5;
-        let mut lookahead = head;
-        while lexeme[lookahead].get_token_type() != SEMICOLON {
-            lookahead += 1;
-        }
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        if lexeme[head].get_token_type() == COMMA {
-            temp_lexeme.push(lexeme[0].clone());
-            //move to next
-            head += 1;
-            while lexeme[head].get_token_type() != SEMICOLON {
-                temp_lexeme.push(lexeme[head].clone());
-                head += 1;
-            }
-            stream.push(";".to_string());
-            temp_lexeme.push(lexeme[head].clone());
-            stream.append(&mut self.parse_program(&temp_lexeme));
-
-        } else if lexeme[head].get_token_type() == OP_ASSIGN {
-            while lexeme[head].get_token_type() != SEMICOLON &&
-                  lexeme[head].get_token_type() != RIGHT_CBRACE {
-                stream.push(match lexeme[head].get_token_type() {
-                    LEFT_CBRACE => "[".to_string(),
-
-                    _ => lexeme[head].get_token_value(),
-                });
-                head += 1;
+        StmtKind::Block(ref stmts) => {
+            for s in stmts {
+                stmt_uses(s, ret, uses);
             }
-            stream.push("]".to_string());
-            stream.push(";".to_string());
-        } else {
-            stream.push(";".to_string());
-        }
-
-        stream
-    }
-
-    // not tested
-    fn parse_struct(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
-        stream.push(lexeme[head].get_token_value()); //push the keyword struct
-        head += 1;
-        //push the struct id_name
-        stream.push(lexeme[head].get_token_value()); //push the struct name
-		let name = lexeme[head].get_token_value();
-        stream.push("{".to_string());
-        head += 2;
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        while lexeme[head].get_token_type() != RIGHT_CBRACE {
-            while lexeme[head].get_token_type() != SEMICOLON {
-                temp_lexeme.push(lexeme[head].clone());
-                head += 1
-            }
-            temp_lexeme.push(lexeme[head].clone());
-            head += 1;
-            stream.append(&mut self.parse_struct_inbody_decl(&temp_lexeme, &name));
-            temp_lexeme.clear();
-        }
-        stream.push(lexeme[head].get_token_value() + "\n");
-
-
-        stream
-    }
-
-    // not tested
-    fn parse_struct_inbody_decl(&mut self, lexeme: &Vec<Token>, name: &String) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head = 0;
-        let mut rust_type: String = "RUST_TYPE".to_string();
-        //push the identifier
-        stream.push(lexeme[head + 1].get_token_value());
-        stream.push(":".to_string());
-        let mut struct_memt = StructMem {
-            identifier: "NONE".to_string(),
-            typ: 0,
-            name: name.clone(),
-        };
+        }
+        StmtKind::If(_, ref t, ref o) => {
+            stmt_uses(t, ret, uses);
+            if let Some(ref o) = *o {
+                stmt_uses(o, ret, uses);
+            }
+        }
+        StmtKind::While(_, ref body) | StmtKind::DoWhile(ref body, _) => stmt_uses(body, ret, uses),
+        StmtKind::For(ref init, _, _, ref body) => {
+            if let Some(ref init) = *init {
+                stmt_uses(init, ret, uses);
+            }
+            stmt_uses(body, ret, uses);
+        }
+        StmtKind::Switch(_, ref cases) => {
+            for s in cases.iter().flat_map(|case| case.body.iter()) {
+                stmt_uses(s, ret, uses);
+            }
+        }
+        _ => {}
+    }
+}
 
-        if let Some(rust_typ) = parse_type(lexeme[head].get_token_type() as i32) {
-            rust_type = rust_typ.clone();
-            stream.push(rust_typ);
-            struct_memt.typ = lexeme[head].get_token_type() as i32;
-            struct_memt.identifier = lexeme[head + 1].get_token_value();
+/**
+ * const_type:
+ * C type of a constant expression, suffixes map like they do for
+ * literals and arithmetic takes the wider operand type
+ */
+fn const_type(expr: &Expr, known: &[(String, Type)]) -> Type {
+    match expr.kind {
+        ExprKind::IntLit(ref lit) => {
+            let suffix = lit.suffix.to_lowercase();
+            let value = lit.value().unwrap_or(0);
+            match (suffix.contains('u'), suffix.contains('l')) {
+                (true, false) if value <= u32::MAX as u64 => Type::Int(Sign::Unsigned),
+                (true, _) => Type::Long(Sign::Unsigned),
+                (false, false) if value <= i32::MAX as u64 => Type::Int(Sign::Plain),
+                (false, _) => Type::Long(Sign::Plain),
+            }
         }
-        head += 2;
-        stream.push(",".to_string());
-        self.struct_mem.push(struct_memt.clone());
-        while lexeme[head].get_token_type() != SEMICOLON {
-            if lexeme[head].get_token_type() == COMMA {
-                head += 1;
+        ExprKind::FloatLit(ref lit) => {
+            if lit.suffix.eq_ignore_ascii_case("f") {
+                Type::Float
+            } else {
+                Type::Double
             }
-            struct_memt.identifier = lexeme[head].get_token_value();
-            stream.push(lexeme[head].get_token_value());
-            stream.push(":".to_string());
-            stream.push(rust_type.clone());
-            head += 1;
-            stream.push(",".to_string());
-            self.struct_mem.push(struct_memt.clone());
         }
-        stream
+        ExprKind::CharLit(_) => Type::Char(Sign::Plain),
+        ExprKind::StrLit(_) => Type::Str,
+        ExprKind::BoolLit(_) => Type::Bool,
+        ExprKind::Ident(ref name) => {
+            known.iter().rev().find(|k| k.0 == *name).map_or(Type::Int(Sign::Plain), |k| k.1.clone())
+        }
+        ExprKind::Unary(_, ref e) => const_type(e, known),
+        ExprKind::Binary(op, ref l, ref r) => match op {
+            BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge | BinOp::Eq | BinOp::Ne | BinOp::And |
+            BinOp::Or => Type::Bool,
+            BinOp::Shl | BinOp::Shr => const_type(l, known),
+            _ => wider(const_type(l, known), const_type(r, known)),
+        },
+        ExprKind::Cond(_, ref t, _) => const_type(t, known),
+        ExprKind::Cast(ref ty, _) => ty.clone(),
+        _ => Type::Int(Sign::Plain),
     }
+}
 
+fn is_arithmetic(ty: &Type) -> bool {
+    matches!(*ty,
+             Type::Bool | Type::Char(_) | Type::Short(_) | Type::Int(_) | Type::Long(_) | Type::LongLong(_) |
+             Type::Float | Type::Double)
+}
 
+fn is_float(ty: &Type) -> bool {
+    *ty == Type::Float || *ty == Type::Double
+}
 
+// integer promotion, types narrower than int become int
+fn promote(ty: Type) -> Type {
+    match ty {
+        Type::Bool | Type::Char(_) | Type::Short(_) => Type::Int(Sign::Plain),
+        ty => ty,
+    }
+}
+
+// whether two C types are the same Rust type
+fn same_repr(a: &Type, b: &Type) -> bool {
+    let repr = |ty: &Type| match *ty {
+        Type::Char(Sign::Plain) => Type::Char(Sign::Unsigned),
+        Type::Short(Sign::Plain) => Type::Short(Sign::Signed),
+        Type::Int(Sign::Plain) => Type::Int(Sign::Signed),
+        Type::Long(Sign::Plain) | Type::LongLong(Sign::Plain) => Type::Long(Sign::Signed),
+        Type::LongLong(sign) => Type::Long(sign),
+        ref ty => ty.clone(),
+    };
+    repr(a) == repr(b)
+}
 
-    // not tested
-    fn parse_struct_decl(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
+// type of the elements of an array or the target of a pointer
+fn element_type(ty: Type) -> Option<Type> {
+    match ty {
+        Type::Pointer(inner) | Type::Array(inner, _) => Some(inner.unqualified().clone()),
+        _ => None,
+    }
+}
 
-        stream.push(STRUCT_INIT.get_doc().to_string());
-        stream.push("let".to_string());
-        let mut head = 1;
-        //struct FilePointer fp;
-        let struct_name = lexeme[head].get_token_value();
-        head += 1;
-        stream.push(lexeme[head].get_token_value()); //push the identifer => let a
-        stream.push("=".to_string());
-        stream.push(struct_name.clone());
-        stream.push("{".to_string());
+// `receiver.name()`
+fn method_call(receiver: Expr, name: &str) -> Expr {
+    let pos = receiver.pos;
+    let method = Expr::new(ExprKind::Member(Box::new(receiver), name.to_string()), pos);
+    Expr::new(ExprKind::Call(Box::new(method), Vec::new()), pos)
+}
 
-        for row in &self.struct_mem {
-            if row.name == struct_name {
-                stream.push(row.identifier.clone());
-                stream.push(":".to_string());
-                stream.push(get_default_value_for(row.typ));
-                stream.push(",".to_string());
-            }
+// numeric literals, whose type Rust infers from the other operand
+fn is_literal(expr: &Expr) -> bool {
+    match expr.kind {
+        ExprKind::IntLit(_) | ExprKind::FloatLit(_) => true,
+        ExprKind::Unary(UnOp::Neg, ref e) | ExprKind::Unary(UnOp::Plus, ref e) => is_literal(e),
+        ExprKind::Binary(op, ref l, ref r) => {
+            matches!(op, BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem) && is_literal(l) &&
+            is_literal(r)
         }
-        stream.push("};".to_string());
-
-        stream
+        _ => false,
     }
+}
 
-    //parse tagged union
-    fn parse_union(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
-        stream.push("enum".to_string()); //push the keyword union
-        head += 1;
-        //push the struct id_name
-        stream.push(lexeme[head].get_token_value()); //push the struct name
-		let name = lexeme[head].get_token_value();
-        stream.push("{".to_string());
-        head += 2;
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        while lexeme[head].get_token_type() != RIGHT_CBRACE {
-            while lexeme[head].get_token_type() != SEMICOLON {
-                temp_lexeme.push(lexeme[head].clone());
-                head += 1
-            }
-            temp_lexeme.push(lexeme[head].clone());
-            head += 1;
-            stream.append(&mut self.parse_union_inbody_decl(&temp_lexeme, &name));
-            temp_lexeme.clear();
-        }
-        stream.push(lexeme[head].get_token_value() + "\n");
-
-
-        stream
-    }
-
-	/* parse union type declarations
-	 * input : union tag_name var [;= ...]
-	 * output : let [mut] variant_name = Sometype_variant
-	 */
-    fn parse_union_decl(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-		let mut stream:Vec<String> = Vec::new();
-		let mut head:usize = 0;
-		
-		stream.push(UNION_DECL.get_doc().to_string());
-
-		//push the keyword let
-		stream.push("let".to_string());
-		if !self.strict{
-			stream.push("mut".to_string());
-		}
-
-		stream.push(lexeme[head+2].get_token_value());
-			head+=3;
-			while lexeme[head].get_token_type() != SEMICOLON{
-				stream.push(lexeme[head].get_token_value());
-				head+=1;	
-			}
-		
-		stream.push(";".to_string());
-
-		
-		stream
-	}
-	/* parse union body into Some type body 
-	 * return rust stream
-	 */
-    fn parse_union_inbody_decl(&mut self, lexeme: &Vec<Token>, name: &String) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head = 0;
-        let mut rust_type: String = "RUST_TYPE".to_string();
-        //push the identifier
-        stream.push(lexeme[head + 1].get_token_value());
-        stream.push("(".to_string());
-        let mut struct_memt = StructMem {
-            identifier: "NONE".to_string(),
-            typ: 0,
-            name: name.clone(),
-        };
-		//push the type
-        if let Some(rust_typ) = parse_type(lexeme[head].get_token_type() as i32) {
-            rust_type = rust_typ.clone();
-            stream.push(rust_typ);
-            struct_memt.typ = lexeme[head].get_token_type() as i32;
-            struct_memt.identifier = lexeme[head + 1].get_token_value();
-        }
-        head += 2;
-        stream.push("),".to_string());
-        //update struct member table (may require for analysis
-
-		self.struct_mem.push(struct_memt.clone());
-        while lexeme[head].get_token_type() != SEMICOLON {
-            if lexeme[head].get_token_type() == COMMA {
-                head += 1;
-            }
-            struct_memt.identifier = lexeme[head].get_token_value();
-            stream.push(lexeme[head].get_token_value());
-            stream.push("(".to_string());
-            stream.push(rust_type.clone());
-            head += 1;
-            stream.push("),".to_string());
-            self.struct_mem.push(struct_memt.clone());
-        }
-        stream
-    }
-
-	// not tested
-    fn parse_class(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head: usize = 0;
-        let mut method_stream: Vec<String> = Vec::new();
-        stream.push("struct".to_string()); //push the keyword struct
-        head += 1;
-        //push the struct id_name
-        let class_name = lexeme[head].get_token_value();
-        stream.push(class_name.clone()); //push the class name
-        let name = lexeme[head].get_token_value();
-        stream.push("{".to_string());
-        head += 2;
-        let mut modifier: String = " ".to_string();
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        let mut tstream: Vec<String> = Vec::new();
-
-        while lexeme[head].get_token_type() != RIGHT_CBRACE &&
-              lexeme[head + 1].get_token_type() != SEMICOLON {
-            match lexeme[head].get_type() {
-                (BASE_MODIFIER, _) => {
-                    match lexeme[head].get_token_type() {
-                        KEYWORD_PUBLIC => {
-                            head += 2;
-                            modifier = "pub".to_string();
-                        }
-                        KEYWORD_PROTECTED |
-                        KEYWORD_PRIVATE => {
-                            head += 2;
-                            modifier = "".to_string();
-                        }
-                        _ => {}
-                    };
-                }
-                (_, IDENTIFIER) => {
-                    if lexeme[head].get_token_value() == class_name {
-                        tstream.push(CONSTRUCTOR.get_doc().to_string());
-                        let mut lookahead = head;
-                        while lexeme[lookahead].get_token_type() != LEFT_CBRACE {
-                            lookahead += 1;
-                        }
-                        lookahead += 1;
-                        lookahead = skip_block(lexeme, lookahead);
-                        while head < lookahead {
-                            tstream.push(lexeme[head].get_token_value());
-                            head += 1;
-                        }
-                        tstream.push("\n **/\n".to_string());
-                        continue;
+/**
+ * unsuffix:
+ * drops the suffixes of a literal expression so Rust infers ty for
+ * it, unless an integer would not fit ty. Returns whether it did
+ */
+fn unsuffix(expr: &mut Expr, ty: &Type) -> bool {
+    fn fits(expr: &Expr, ty: &Type) -> bool {
+        match expr.kind {
+            ExprKind::IntLit(ref lit) => {
+                let range = int_range(ty);
+                is_float(ty) || lit.value().is_some_and(|v| range.is_some_and(|(_, max)| v as i128 <= max))
+            }
+            ExprKind::Unary(UnOp::Neg, ref e) if !is_float(ty) => {
+                let range = int_range(ty);
+                match e.kind {
+                    ExprKind::IntLit(ref lit) => {
+                        lit.value().is_some_and(|v| range.is_some_and(|(min, _)| -(v as i128) >= min))
                     }
+                    _ => range.is_some_and(|(min, _)| min < 0) && fits(e, ty),
                 }
-
-                _ => {}
             }
+            ExprKind::Unary(_, ref e) => fits(e, ty),
+            ExprKind::Binary(_, ref l, ref r) => fits(l, ty) && fits(r, ty),
+            _ => true,
+        }
+    }
+    fn strip(expr: &mut Expr) {
+        match expr.kind {
+            ExprKind::IntLit(ref mut lit) | ExprKind::FloatLit(ref mut lit) => lit.suffix.clear(),
+            ExprKind::Unary(_, ref mut e) => strip(e),
+            ExprKind::Binary(_, ref mut l, ref mut r) => {
+                strip(l);
+                strip(r);
+            }
+            _ => {}
+        }
+    }
+    if !fits(expr, ty) {
+        return false;
+    }
+    strip(expr);
+    true
+}
 
-            if lexeme[head + 2].get_token_type() == LEFT_BRACKET {
+// lvalues which can be read again without side effects
+fn is_pure(expr: &Expr) -> bool {
+    match expr.kind {
+        ExprKind::Ident(_) | ExprKind::IntLit(_) | ExprKind::This => true,
+        ExprKind::Member(ref e, _) | ExprKind::Unary(UnOp::Deref, ref e) => is_pure(e),
+        ExprKind::Index(ref b, ref i) => is_pure(b) && is_pure(i),
+        _ => false,
+    }
+}
 
-                while lexeme[head].get_token_type() != RIGHT_CBRACE {
+// the operand type C arithmetic converts both operands to
+fn wider(a: Type, b: Type) -> Type {
+    let rank = |t: &Type| match *t {
+        Type::Bool => 0,
+        Type::Char(_) => 1,
+        Type::Short(_) => 2,
+        Type::Long(_) | Type::LongLong(_) => 4,
+        Type::Float => 5,
+        Type::Double => 6,
+        _ => 3,
+    };
+    let unsigned = |t: &Type| match *t {
+        Type::Char(sign) | Type::Short(sign) | Type::Int(sign) | Type::Long(sign) |
+        Type::LongLong(sign) => sign == Sign::Unsigned,
+        _ => false,
+    };
+    if rank(&b) > rank(&a) || (rank(&b) == rank(&a) && unsigned(&b)) {
+        b
+    } else {
+        a
+    }
+}
 
-                    temp_lexeme.push(lexeme[head].clone());
-                    head += 1;
-                }
-                temp_lexeme.push(lexeme[head].clone());
-                head += 1;
-                method_stream.append(&mut self.parse_method_decl(&temp_lexeme, &modifier));
-                temp_lexeme.clear();
 
-            } else {
-                while lexeme[head].get_token_type() != RIGHT_CBRACE &&
-                      lexeme[head].get_base_type() != BASE_MODIFIER {
-                    while lexeme[head].get_token_type() != SEMICOLON {
-                        temp_lexeme.push(lexeme[head].clone());
-                        head += 1
-                    }
-                    temp_lexeme.push(lexeme[head].clone());
-                    head += 1;
-                    stream.append(&mut self.parse_class_inbody_decl(&temp_lexeme,
-                                                                  &name,
-                                                                  &modifier));
-                    temp_lexeme.clear();
+/**
+ * infer_macro_signatures:
+ * a function-like macro becomes an inline function when its body is a
+ * pure expression using each parameter once and naming nothing but its
+ * parameters, constants and functions, and every use passes arguments
+ * of the same known types
+ */
+fn infer_macro_signatures(decls: &mut [Decl], calls: &[(String, Vec<Option<Type>>)]) {
+    let mut known: Vec<(String, Type)> = Vec::new();
+    for decl in decls.iter_mut() {
+        match *decl {
+            Decl::Const(ref def) => known.push((def.name.clone(), def.ty.clone())),
+            Decl::Macro(ref mut def) => {
+                if let MacroBody::Expr(ref body) = def.body {
+                    def.signature = macro_signature(&def.name, &def.params, body, calls, &known);
                 }
             }
-
+            _ => {}
         }
-        stream.push(lexeme[head].get_token_value());
-        stream.push("\n\n/**Method declarations are wrapped inside the impl block \
-    \n * Which implements the corresponding structure\
-    \n **/\n"
-            .to_string());
-        stream.push("impl".to_string());
-        stream.push(name.clone());
-        stream.push("{\n".to_string());
-        if tstream.len() > 0 {
-            stream.append(&mut tstream);
-        }
-        stream.append(&mut method_stream);
-
-        stream.push("}\n".to_string());
-        stream
-    }
-
-    // not tested
-    fn parse_method_decl(&mut self, lexeme: &Vec<Token>, modifier: &String) -> Vec<String> {
-        let mut temp_lexeme: Vec<Token> = Vec::new();
-        let mut head: usize = 3;
-        let mut lookahead: usize = head;
-        let mut stream: Vec<String> = Vec::new();
-        if modifier.len() > 1 {
-            stream.push(modifier.clone());
-        }
-        stream.push("fn".to_string());
-        stream.push(lexeme[1].get_token_value());
-        stream.push("(".to_string());
-        stream.push("&self".to_string()); //first argument of method must be self, for sefety we consider reference/borrow
-        // parse arguments differenly for functions that are not main
-        // collect arguments
-        while lexeme[lookahead].get_token_type() != RIGHT_BRACKET {
-            lookahead += 1;
-        }
-        if head < lookahead {
-            stream.push(",".to_string());
-        }
-        while head < lookahead {
-            let l: Token = lexeme[head].clone();
-            temp_lexeme.push(l);
-            head += 1;
-        }
-
-        // parse arguments
-        stream.append(&mut self.parse_arguments(&temp_lexeme));
-        temp_lexeme.clear();
-
-        stream.push(")".to_string());
-
-        // parse return type
-        if let Some(rust_type) = parse_type(lexeme[0].get_token_type() as i32) {
-            if rust_type != "void" {
-                stream.push("->".to_string());
-                stream.push(rust_type);
-            }
-        }
-
-        stream.push("{".to_string());
-        while lexeme[head].get_token_type() != LEFT_CBRACE {
-            head += 1
-        }
-        head += 1;
-
-        // collect function body
-        // len - 1  so that '}' is excluded
-        while head < lexeme.len() - 1 {
-            let l: Token = lexeme[head].clone();
-            temp_lexeme.push(l);
-            head += 1;
-        }
-        // parse function body
-        stream.append(&mut self.parse_program(&temp_lexeme));
-        stream.push("}".to_string());
-        stream
-    }
-
-    // not tested
-    fn parse_class_inbody_decl(&mut self,
-                               lexeme: &Vec<Token>,
-                               name: &String,
-                               modifier: &String)
-                               -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-        let mut head = 0;
-        //push the identifier
-        if modifier.len() > 1 {
-            stream.push(modifier.clone());
-        }
-        stream.push(lexeme[head + 1].get_token_value());
-        stream.push(":".to_string());
-        let mut struct_memt = StructMem {
-            identifier: "NONE".to_string(),
-            typ: 0,
-            name: name.clone(),
-        };
+    }
+}
 
-        let mut rust_type: String = " ".to_string();
-        if let Some(rust_typ) = parse_type(lexeme[0].get_token_type() as i32) {
-            stream.push(rust_typ.clone());
-            rust_type = rust_typ.clone();
-            struct_memt.typ = lexeme[0].get_token_type() as i32;
-            struct_memt.identifier = lexeme[1].get_token_value();
+// the types of the arguments of a macro, when every use passes the same ones
+fn site_types(name: &str, arity: usize, calls: &[(String, Vec<Option<Type>>)]) -> Option<Vec<Type>> {
+    let mut types: Vec<Type> = Vec::new();
+    for i in 0..arity {
+        let mut sites = calls.iter().filter(|c| c.0 == name).map(|c| c.1.get(i).cloned().flatten());
+        let first = sites.next()??;
+        if !sites.all(|t| t.as_ref() == Some(&first)) {
+            return None;
         }
+        types.push(first);
+    }
+    Some(types)
+}
 
-        stream.push(",".to_string());
-        self.struct_mem.push(struct_memt.clone());
-        head += 2;
-        while lexeme[head].get_token_type() != SEMICOLON {
-            if lexeme[head].get_token_type() == COMMA {
-                head += 1;
+fn macro_signature(name: &str,
+                   params: &[String],
+                   body: &Expr,
+                   calls: &[(String, Vec<Option<Type>>)],
+                   known: &[(String, Type)])
+                   -> Option<(Vec<Type>, Type)> {
+    for param in params {
+        let uses = Cell::new(0);
+        body.any(&|e: &Expr| {
+            if matches!(e.kind, ExprKind::Ident(ref n) if n == param) {
+                uses.set(uses.get() + 1);
             }
-            stream.push(lexeme[head].get_token_value());
-            stream.push(":".to_string());
-            stream.push(rust_type.clone());
-
-            struct_memt.identifier = lexeme[head].get_token_value();
-            self.struct_mem.push(struct_memt.clone());
-            head += 1;
+            false
+        });
+        if uses.get() != 1 {
+            return None;
         }
-        stream
     }
+    let impure = body.any(&|e: &Expr| match e.kind {
+        ExprKind::Assign(..) => true,
+        ExprKind::Unary(op, _) => {
+            matches!(op, UnOp::PreInc | UnOp::PreDec | UnOp::PostInc | UnOp::PostDec | UnOp::AddrOf)
+        }
+        ExprKind::Ident(ref n) => {
+            let called = body.any(&|c: &Expr| match c.kind {
+                ExprKind::Call(ref f, _) => matches!(f.kind, ExprKind::Ident(ref g) if g == n),
+                _ => false,
+            });
+            !params.contains(n) && !known.iter().any(|k| k.0 == *n) && !called
+        }
+        _ => false,
+    });
+    if impure {
+        return None;
+    }
+    let types = site_types(name, params.len(), calls)?;
+    let mut scope = known.to_vec();
+    scope.extend(params.iter().cloned().zip(types.iter().cloned()));
+    let ret = const_type(body, &scope);
+    Some((types, ret))
+}
 
 
-    // not tested
-    fn parse_class_decl(&mut self, lexeme: &Vec<Token>) -> Vec<String> {
-        let mut stream: Vec<String> = Vec::new();
-
-        stream.push(STRUCT_INIT.get_doc().to_string());
-        stream.push("let".to_string());
-        let mut head = 0;
-        //struct FilePointer fp;
-        let struct_name = lexeme[head].get_token_value();
-        head += 1;
-        stream.push(lexeme[head].get_token_value()); //push the identifer => let a
-        stream.push("=".to_string());
-        stream.push(struct_name.clone());
-        stream.push("{".to_string());
+#[cfg(test)]
+mod test {
+    use library::lexer;
+    use library::parser::{escape_identifiers, init_parser, Options};
+    use library::ast::NameSpace;
 
-        for row in &self.struct_mem {
-            if row.name == struct_name {
-                stream.push(row.identifier.clone());
-                stream.push(":".to_string());
-                stream.push(get_default_value_for(row.typ));
-                stream.push(",".to_string());
-            }
-        }
-        stream.push("};".to_string());
+    // warnings the parser gives for source
+    fn diagnostics(source: &str) -> Vec<String> {
+        let tokens = lexer::Tokenizer::new(source).tokenize().unwrap();
+        init_parser(&tokens, &Options::default()).diagnostics.iter().map(|d| d.to_string()).collect()
+    }
 
-        stream
+    #[test]
+    fn test_escape_identifiers() {
+        let mut tokens = lexer::Tokenizer::new("int type = match(self, super_);\n\
+                                                Self *crate; int super, loop, type2; type = loop;")
+            .tokenize()
+            .unwrap();
+        let escapes = escape_identifiers(&mut tokens);
+        let values: Vec<String> = tokens.iter().map(|t| t.get_token_value()).collect();
+        assert_eq!(values,
+                   vec!["int", "r#type", "=", "r#match", "(", "self_", ",", "super_", ")", ";", "Self_", "*", "crate_",
+                        ";", "int", "super__", ",", "r#loop", ",", "type2", ";", "r#type", "=", "r#loop", ";"]);
+        // each name is listed once, with its escape
+        let escapes: Vec<(&str, &str)> = escapes.iter().map(|e| (&e.0[..], &e.1[..])).collect();
+        assert_eq!(escapes,
+                   vec![("type", "r#type"), ("match", "r#match"), ("self", "self_"), ("Self", "Self_"),
+                        ("crate", "crate_"), ("super", "super__"), ("loop", "r#loop")]);
     }
-} //close impl
 
+    #[test]
+    fn test_byte_strings() {
+        assert!(diagnostics("char *s = \"\\xC3\\xA9\\x41\";").is_empty());
+        assert_eq!(diagnostics("char *s = \"ok\" \"\\xFF\";"),
+                   vec!["1:11: warning: the string literal holds bytes which are not UTF-8, it becomes a byte string"]);
+    }
 
+    #[test]
+    fn test_literal_suffixes() {
+        assert!(diagnostics("long a = 10UL; float b = 2.5f; long c = 3llu;").is_empty());
+        assert_eq!(diagnostics("int k = 100_km;\ndouble t = 1.5_h;"),
+                   vec!["1:9: warning: the user-defined literal suffix `_km` of `100_km` has no Rust equivalent, \
+                         it is dropped",
+                        "2:12: warning: the user-defined literal suffix `_h` of `1.5_h` has no Rust equivalent, \
+                         it is dropped"]);
+    }
 
-fn get_default_value_for(c_type: i32) -> String {
-    match c_type {
-        0 => "0i32".to_string(),
-        1 => "0i16".to_string(),
-        2 => "0i64".to_string(),
-        3 => "0.0f32".to_string(),
-        4 => "0.0f64".to_string(),
-        5 => "'_'".to_string(),
-        6 => "false".to_string(),
-        _ => "_".to_string(),
+    #[test]
+    fn test_rename_map() {
+        let source = "struct match { int x; };\nint Some(int inValue) { return inValue; }\n";
+        let tokens = lexer::Tokenizer::new(source).tokenize().unwrap();
+        let options = Options {
+            naming: true,
+            ..Default::default()
+        };
+        let renames: Vec<(String, String, String, NameSpace)> = init_parser(&tokens, &options)
+            .renames
+            .into_iter()
+            .map(|r| (r.c_name, r.from, r.to, r.space))
+            .collect();
+        // the old side is the C name rather than its escape
+        assert_eq!(renames,
+                   vec![("match".to_string(), "r#match".to_string(), "Match".to_string(), NameSpace::Type),
+                        ("inValue".to_string(), "inValue".to_string(), "in_value".to_string(), NameSpace::Value),
+                        ("Some".to_string(), "Some_".to_string(), "some_".to_string(), NameSpace::Value)]);
     }
 }
@@ -0,0 +1,1094 @@
+#![allow(dead_code)]
+
+use library::ast::Diagnostic;
+use library::lexeme::Radix;
+use library::lexeme::Span;
+use library::lexeme::Token;
+use library::lexeme::Type::*;
+use library::lexer;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::mem;
+use std::path::Path;
+use std::path::PathBuf;
+
+// Preprocessing stage run between the Tokenizer and the Parser.
+// Directives are recognised on logical lines (tokens sharing a line
+// number), conditional groups are evaluated and macros are expanded.
+// Headers named by a quoted #include are preprocessed on their own and
+// kept aside to be translated into modules, <...> includes, #pragma and
+// unknown directives are passed on to the parser.
+// Macros defined outside of braces that Rust can express are passed on as
+// well and their uses are left unexpanded to refer to them: object-like
+// macros with a constant body become `const` items, function-like ones
+// become inline functions or macro_rules!.
+
+// error that stops preprocessing, line and column are 1-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessError {
+    pub msg: String,
+    pub line: u32,
+    pub col: u32,
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: error: {}", self.line, self.col, self.msg)
+    }
+}
+
+// object-like macros have no parameter list, a variadic macro
+// ends its parameter list with __VA_ARGS__
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    pub params: Option<Vec<String>>,
+    pub variadic: bool,
+    pub body: Vec<Token>,
+    // translated into a Rust item instead of being expanded
+    pub translated: bool,
+    pub pos: Span,
+}
+
+// one #if/#ifdef/#ifndef group
+struct Cond {
+    active: bool, // lines of the current branch are kept
+    taken: bool, // some branch of the group has been kept
+    outer: bool, // the enclosing group is active
+    seen_else: bool,
+    pos: Span,
+}
+
+// header found through a quoted #include, preprocessed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    pub path: PathBuf,
+    pub tokens: Vec<Token>,
+    // headers it includes, directly or not
+    pub uses: Vec<PathBuf>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+// a token with the names of the macros it came out of,
+// those are not expanded again while rescanning it
+type Hidden = (Token, Vec<String>);
+
+pub struct Preprocessor {
+    macros: HashMap<String, Macro>,
+    conds: Vec<Cond>,
+    depth: i32, // braces open in the text lines
+    translated: Vec<String>, // names passed on to be translated
+    include_paths: Vec<PathBuf>, // -I directories
+    dirs: Vec<PathBuf>, // directories of the files being preprocessed, innermost last
+    opened: Vec<PathBuf>, // headers being preprocessed
+    // every header included so far, after the headers it includes
+    pub headers: Vec<Header>,
+    // headers included by the file being preprocessed, directly or not
+    pub uses: Vec<PathBuf>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+fn error(msg: &str, pos: Span) -> PreprocessError {
+    PreprocessError {
+        msg: msg.to_string(),
+        line: pos.line,
+        col: pos.col,
+    }
+}
+
+// identifiers and keywords can both name a macro
+fn is_name(tok: &Token) -> bool {
+    tok.get_token_type() != STRING && tok.get_token_type() != CHAR_VAL &&
+    tok.get_token_value().chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+}
+
+// copy of tok placed where the macro was invoked
+fn relocate(tok: &Token, at: &Token) -> Token {
+    let mut tok = tok.clone();
+    tok.set_token_ln(at.get_token_ln());
+    tok.set_span(at.get_span());
+    tok
+}
+
+fn spelling(tokens: &[Token]) -> Vec<String> {
+    tokens.iter().map(|t| t.get_token_value()).collect()
+}
+
+// `##` is lexed as two adjacent `#` tokens
+fn is_paste(body: &[Token], i: usize) -> bool {
+    match (body.get(i), body.get(i + 1)) {
+        (Some(a), Some(b)) => {
+            a.get_token_value() == "#" && b.get_token_value() == "#" &&
+            a.get_span().end == b.get_span().start
+        }
+        _ => false,
+    }
+}
+
+impl Preprocessor {
+    pub fn new() -> Preprocessor {
+        Preprocessor {
+            macros: HashMap::new(),
+            conds: Vec::new(),
+            depth: 0,
+            translated: Vec::new(),
+            include_paths: Vec::new(),
+            dirs: Vec::new(),
+            opened: Vec::new(),
+            headers: Vec::new(),
+            uses: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /**
+     * define:
+     * defines a macro given on the command line as NAME or NAME=VALUE,
+     * NAME alone is defined to 1
+     */
+    pub fn define(&mut self, definition: &str) -> Result<(), PreprocessError> {
+        let text = match definition.find('=') {
+            Some(at) => format!("{} {}", &definition[..at], &definition[at + 1..]),
+            None => format!("{} 1", definition),
+        };
+        let tokens = match lexer::Tokenizer::new(&text).tokenize() {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                return Err(PreprocessError {
+                    msg: err.msg,
+                    line: err.line,
+                    col: err.col,
+                })
+            }
+        };
+        self.define_macro(&tokens, Span::default()).map(|_| ())
+    }
+
+    pub fn undefine(&mut self, name: &str) {
+        self.macros.remove(name);
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.macros.contains_key(name)
+    }
+
+    // directory searched by quoted includes after the one of the including file
+    pub fn add_include_path(&mut self, dir: &Path) {
+        self.include_paths.push(dir.to_path_buf());
+    }
+
+    /**
+     * run_file:
+     * preprocesses the tokens of the file at path, quoted includes are
+     * searched for next to it first
+     */
+    pub fn run_file(&mut self, tokens: &[Token], path: &Path) -> Result<Vec<Token>, PreprocessError> {
+        self.dirs.push(path.parent().map_or(PathBuf::new(), Path::to_path_buf));
+        let result = self.run(tokens);
+        self.dirs.pop();
+        result
+    }
+
+    /**
+     * run:
+     * preprocesses a token stream, returning the tokens the parser should see
+     */
+    pub fn run(&mut self, tokens: &[Token]) -> Result<Vec<Token>, PreprocessError> {
+        let mut out = Vec::new();
+        // text lines are expanded together so macro arguments can span lines
+        let mut text = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let ln = tokens[i].get_token_ln();
+            let line_start = i == 0 || tokens[i - 1].get_token_ln() != ln;
+            if line_start && tokens[i].get_token_value() == "#" {
+                let mut end = i + 1;
+                while end < tokens.len() && tokens[end].get_token_ln() == ln {
+                    end += 1;
+                }
+                out.extend(self.expand(text.split_off(0), true)?);
+                self.directive(&tokens[i..end], &mut out)?;
+                i = end;
+                continue;
+            }
+            if self.active() {
+                match &tokens[i].get_token_value()[..] {
+                    "{" => self.depth += 1,
+                    "}" => self.depth -= 1,
+                    _ => {}
+                }
+                text.push(tokens[i].clone());
+            }
+            i += 1;
+        }
+        out.extend(self.expand(text, true)?);
+        if let Some(cond) = self.conds.pop() {
+            self.conds.clear();
+            return Err(error("unterminated conditional directive", cond.pos));
+        }
+        Ok(out)
+    }
+
+    fn active(&self) -> bool {
+        self.conds.last().is_none_or(|cond| cond.active)
+    }
+
+    /**
+     * directive:
+     * handles one directive line starting at its `#`
+     */
+    fn directive(&mut self, line: &[Token], out: &mut Vec<Token>) -> Result<(), PreprocessError> {
+        let pos = line[0].get_span();
+        // comments do not take part in directives
+        let words: Vec<Token> = line[1..]
+            .iter()
+            .filter(|t| t.get_base_type() != BASE_COMMENT)
+            .cloned()
+            .collect();
+        // a lone `#` is the null directive
+        let name = match words.first() {
+            Some(word) => word.get_token_value(),
+            None => return Ok(()),
+        };
+        let rest = &words[1..];
+        match &name[..] {
+            "if" | "ifdef" | "ifndef" => {
+                let outer = self.active();
+                let active = outer &&
+                             match &name[..] {
+                    "ifdef" => self.defined_name(rest, &name, pos)?,
+                    "ifndef" => !self.defined_name(rest, &name, pos)?,
+                    _ => self.eval(rest, pos)?,
+                };
+                self.conds.push(Cond {
+                    active,
+                    taken: active,
+                    outer,
+                    seen_else: false,
+                    pos,
+                });
+            }
+            "elif" => {
+                let (outer, taken) = match self.conds.last() {
+                    Some(cond) if cond.seen_else => return Err(error("#elif after #else", pos)),
+                    Some(cond) => (cond.outer, cond.taken),
+                    None => return Err(error("#elif without #if", pos)),
+                };
+                let active = outer && !taken && self.eval(rest, pos)?;
+                let cond = self.conds.last_mut().unwrap();
+                cond.active = active;
+                cond.taken = taken || active;
+            }
+            "else" => {
+                let cond = match self.conds.last_mut() {
+                    Some(cond) => cond,
+                    None => return Err(error("#else without #if", pos)),
+                };
+                if cond.seen_else {
+                    return Err(error("#else after #else", pos));
+                }
+                cond.seen_else = true;
+                cond.active = cond.outer && !cond.taken;
+                cond.taken = true;
+            }
+            "endif" => {
+                if self.conds.pop().is_none() {
+                    return Err(error("#endif without #if", pos));
+                }
+            }
+            _ if !self.active() => {}
+            "define" => {
+                if let Some(name) = self.define_macro(rest, pos)? {
+                    let mac = self.macros[&name].clone();
+                    if self.depth == 0 && (self.is_constant(&mac) || self.is_translatable(&mac)) {
+                        self.macros.get_mut(&name).unwrap().translated = true;
+                        self.translated.push(name);
+                        let head = words.len() - mac.body.len();
+                        out.push(line[0].clone());
+                        out.extend(words[..head].iter().cloned());
+                        out.extend(self.expand(mac.body, true)?);
+                    }
+                }
+            }
+            "undef" => {
+                match rest.first() {
+                    Some(word) if is_name(word) => self.undefine(&word.get_token_value()),
+                    _ => return Err(error("no macro name given in #undef directive", pos)),
+                }
+            }
+            "error" => return Err(error(&format!("#error {}", spelling(rest).join(" ")), pos)),
+            "warning" => {
+                self.diagnostics.push(Diagnostic {
+                    msg: format!("#warning {}", spelling(rest).join(" ")),
+                    pos,
+                });
+            }
+            // line markers only matter to the C compiler
+            "line" => {}
+            // headers become modules, which are only included once anyway
+            "pragma" if rest.first().is_some_and(|t| t.get_token_value() == "once") => {}
+            "include" if rest.first().is_some_and(|t| t.get_token_type() == STRING) => {
+                self.include(line, &rest[0].get_token_value(), pos, out)?;
+            }
+            // #include, #pragma and the rest are left to the parser
+            _ => out.extend(line.iter().cloned()),
+        }
+        Ok(())
+    }
+
+    /**
+     * include:
+     * preprocesses the header named by a quoted #include on its own,
+     * it is translated into a module instead of being pasted in place.
+     * Its macros stay defined for the including file
+     */
+    fn include(&mut self,
+               line: &[Token],
+               literal: &str,
+               pos: Span,
+               out: &mut Vec<Token>)
+               -> Result<(), PreprocessError> {
+        let name = &literal[1..literal.len() - 1];
+        let found = self.dirs
+            .last()
+            .into_iter()
+            .chain(self.include_paths.iter())
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file());
+        let path = match found {
+            Some(path) => path.canonicalize().unwrap_or(path),
+            None => {
+                self.diagnostics.push(Diagnostic {
+                    msg: format!("`{}` is not in the include path, it is left untranslated", name),
+                    pos,
+                });
+                out.extend(line.iter().cloned());
+                return Ok(());
+            }
+        };
+        if let Some(uses) = self.headers.iter().find(|h| h.path == path).map(|h| h.uses.clone()) {
+            self.add_uses(path, uses);
+            return Ok(());
+        }
+        // included from itself
+        if self.opened.contains(&path) {
+            self.add_uses(path, Vec::new());
+            return Ok(());
+        }
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) => return Err(error(&format!("cannot read `{}`: {}", name, err), pos)),
+        };
+        let tokens = match lexer::Tokenizer::new(&text).tokenize() {
+            Ok(tokens) => tokens,
+            Err(err) => return Err(error(&format!("{}:{}", name, err), pos)),
+        };
+        // the header starts outside of any conditional or braces
+        let conds = mem::take(&mut self.conds);
+        let depth = mem::replace(&mut self.depth, 0);
+        let outer_uses = mem::take(&mut self.uses);
+        let outer_diagnostics = mem::take(&mut self.diagnostics);
+        self.opened.push(path.clone());
+        let result = self.run_file(&tokens, &path);
+        self.opened.pop();
+        self.conds = conds;
+        self.depth = depth;
+        let uses = mem::replace(&mut self.uses, outer_uses);
+        let diagnostics = mem::replace(&mut self.diagnostics, outer_diagnostics);
+        let tokens = match result {
+            Ok(tokens) => tokens,
+            Err(err) => return Err(error(&format!("{}:{}", name, err), pos)),
+        };
+        self.headers.push(Header {
+            path: path.clone(),
+            tokens,
+            uses: uses.clone(),
+            diagnostics,
+        });
+        self.add_uses(path, uses);
+        Ok(())
+    }
+
+    // the including file sees a header and everything it includes
+    fn add_uses(&mut self, path: PathBuf, uses: Vec<PathBuf>) {
+        for path in Some(path).into_iter().chain(uses) {
+            if !self.uses.contains(&path) {
+                self.uses.push(path);
+            }
+        }
+    }
+
+    // name tested by #ifdef or #ifndef
+    fn defined_name(&self, words: &[Token], directive: &str, pos: Span) -> Result<bool, PreprocessError> {
+        match words.first() {
+            Some(word) if is_name(word) => Ok(self.is_defined(&word.get_token_value())),
+            _ => Err(error(&format!("no macro name given in #{} directive", directive), pos)),
+        }
+    }
+
+    /**
+     * define_macro:
+     * records `NAME body` or `NAME(params) body`, the parameter list
+     * must follow the name without whitespace. Returns the name unless
+     * the macro was already defined the same way
+     */
+    fn define_macro(&mut self, words: &[Token], pos: Span) -> Result<Option<String>, PreprocessError> {
+        let name_tok = match words.first() {
+            Some(word) if is_name(word) => word,
+            Some(_) => return Err(error("macro names must be identifiers", pos)),
+            None => return Err(error("no macro name given in #define directive", pos)),
+        };
+        let name = name_tok.get_token_value();
+        let mut params = None;
+        let mut variadic = false;
+        let mut i = 1;
+        if words.get(1).is_some_and(|t| {
+            t.get_token_value() == "(" && t.get_span().start == name_tok.get_span().end
+        }) {
+            let mut list = Vec::new();
+            i = 2;
+            if words.get(i).is_some_and(|t| t.get_token_value() == ")") {
+                i += 1;
+            } else {
+                loop {
+                    match words.get(i) {
+                        Some(t) if t.get_token_value() == "..." => {
+                            variadic = true;
+                            list.push("__VA_ARGS__".to_string());
+                        }
+                        Some(t) if is_name(t) => list.push(t.get_token_value()),
+                        _ => return Err(error("expected parameter name in macro parameter list", pos)),
+                    }
+                    i += 1;
+                    match words.get(i).map(|t| t.get_token_value()) {
+                        Some(ref v) if v == ")" => {
+                            i += 1;
+                            break;
+                        }
+                        Some(ref v) if v == "," && !variadic => i += 1,
+                        _ => return Err(error("expected ',' or ')' in macro parameter list", pos)),
+                    }
+                }
+            }
+            params = Some(list);
+        }
+        let body = words[i..].to_vec();
+        if is_paste(&body, 0) || (body.len() >= 2 && is_paste(&body, body.len() - 2)) {
+            return Err(error("'##' cannot appear at either end of a macro expansion", pos));
+        }
+        if let Some(ref list) = params {
+            for (k, t) in body.iter().enumerate() {
+                let stringized = t.get_token_value() == "#" && !is_paste(&body, k) &&
+                                 (k == 0 || !is_paste(&body, k - 1));
+                if stringized &&
+                   !body.get(k + 1).is_some_and(|p| list.contains(&p.get_token_value())) {
+                    return Err(error("'#' is not followed by a macro parameter", pos));
+                }
+            }
+        }
+        let mac = Macro {
+            name: name.clone(),
+            params,
+            variadic,
+            body,
+            translated: false,
+            pos,
+        };
+        if let Some(old) = self.macros.get(&name) {
+            if old.params == mac.params && spelling(&old.body) == spelling(&mac.body) {
+                return Ok(None);
+            }
+            self.diagnostics.push(Diagnostic {
+                msg: format!("`{}` redefined", name),
+                pos,
+            });
+        }
+        self.macros.insert(name.clone(), mac);
+        Ok(Some(name))
+    }
+
+    /**
+     * is_constant:
+     * checks whether an object-like macro is a constant expression made of
+     * literals, operators, casts to basic types and other constant macros
+     */
+    fn is_constant(&self, mac: &Macro) -> bool {
+        if mac.params.is_some() || mac.body.is_empty() || self.translated.contains(&mac.name) {
+            return false;
+        }
+        mac.body.iter().all(|t| match t.get_token_type() {
+            NUM_INT | NUM_FLOAT | CHAR_VAL | STRING | TRUE_VAL | FALSE_VAL => true,
+            PRIMITIVE_INT | PRIMITIVE_SHORT | PRIMITIVE_LONG | PRIMITIVE_CHAR |
+            PRIMITIVE_FLOAT | PRIMITIVE_DOUBLE | PRIMITIVE_BOOL | KEYWORD_SIGNED |
+            KEYWORD_UNSIGNED => true,
+            _ if is_name(t) => {
+                self.macros.get(&t.get_token_value()).is_some_and(|m| m.translated && m.params.is_none())
+            }
+            _ => binop_prec(&t.get_token_value()).is_some() ||
+                 ["!", "~", "(", ")", "?", ":"].contains(&&t.get_token_value()[..]),
+        })
+    }
+
+    /**
+     * is_translatable:
+     * checks whether a function-like macro can become an inline function
+     * or macro_rules!, its body has to be an expression or a
+     * `do { } while (0)` statement
+     */
+    fn is_translatable(&mut self, mac: &Macro) -> bool {
+        if mac.params.is_none() || mac.variadic || mac.body.is_empty() ||
+           self.translated.contains(&mac.name) {
+            return false;
+        }
+        if mac.body.iter().any(|t| t.get_token_value() == "#") {
+            let what = if (0..mac.body.len()).any(|k| is_paste(&mac.body, k)) {
+                "token pasting (##)"
+            } else {
+                "stringizing (#)"
+            };
+            self.diagnostics.push(Diagnostic {
+                msg: format!("macro `{}` uses {} which has no Rust equivalent, \
+                              its uses are expanded in place",
+                             mac.name,
+                             what),
+                pos: mac.pos,
+            });
+            return false;
+        }
+        let values = spelling(&mac.body);
+        let statement = values[0] == "do" && values.ends_with(&["while", "(", "0", ")"].map(String::from));
+        statement || !values.iter().any(|v| v == ";" || v == "{" || v == "}")
+    }
+
+    // translated macros are left alone in text lines but not in #if
+    fn expand(&self, tokens: Vec<Token>, keep_translated: bool) -> Result<Vec<Token>, PreprocessError> {
+        let tokens = tokens.into_iter().map(|t| (t, Vec::new())).collect();
+        Ok(self.rescan(tokens, keep_translated)?.into_iter().map(|(t, _)| t).collect())
+    }
+
+    /**
+     * rescan:
+     * expands the macros in tokens, what a macro expands to is
+     * scanned again together with the tokens following it
+     */
+    fn rescan(&self, tokens: Vec<Hidden>, keep_translated: bool) -> Result<Vec<Hidden>, PreprocessError> {
+        // kept reversed so the next token is popped off the end
+        let mut input = tokens;
+        input.reverse();
+        let mut out = Vec::new();
+        while let Some((tok, hidden)) = input.pop() {
+            let name = tok.get_token_value();
+            let mac = match self.macros.get(&name) {
+                Some(mac) if is_name(&tok) && !hidden.contains(&name) &&
+                             !(keep_translated && mac.translated) => mac,
+                _ => {
+                    out.push((tok, hidden));
+                    continue;
+                }
+            };
+            let mut hide = hidden.clone();
+            hide.push(name);
+            let expansion = match mac.params {
+                None => mac.body.iter().map(|t| (relocate(t, &tok), hide.clone())).collect(),
+                Some(ref params) => {
+                    // a function-like macro name without arguments is left alone
+                    if input.last().is_none_or(|(t, _)| t.get_token_value() != "(") {
+                        out.push((tok, hidden));
+                        continue;
+                    }
+                    let args = collect_args(&mut input, mac, &tok)?;
+                    self.substitute(mac, params, &args, &tok, &hide, keep_translated)?
+                }
+            };
+            input.extend(expansion.into_iter().rev());
+        }
+        Ok(out)
+    }
+
+    /**
+     * substitute:
+     * replaces the parameters in the body of a function-like macro,
+     * arguments are macro expanded unless they are stringized or pasted
+     */
+    fn substitute(&self,
+                  mac: &Macro,
+                  params: &[String],
+                  args: &[Vec<Hidden>],
+                  at: &Token,
+                  hide: &[String],
+                  keep_translated: bool)
+                  -> Result<Vec<Hidden>, PreprocessError> {
+        let body = &mac.body;
+        let param = |t: &Token| if is_name(t) {
+            params.iter().position(|p| *p == t.get_token_value())
+        } else {
+            None
+        };
+        let hidden = |items: &[Hidden]| -> Vec<Hidden> {
+            items.iter()
+                .map(|(t, h)| {
+                    let mut h = h.clone();
+                    h.extend(hide.iter().cloned());
+                    (t.clone(), h)
+                })
+                .collect()
+        };
+        let mut out: Vec<Hidden> = Vec::new();
+        let mut i = 0;
+        while i < body.len() {
+            let t = &body[i];
+            if is_paste(body, i) {
+                let right = match body.get(i + 2) {
+                    Some(r) => match param(r) {
+                        Some(p) => hidden(&args[p]),
+                        None => vec![(relocate(r, at), hide.to_vec())],
+                    },
+                    None => Vec::new(),
+                };
+                paste(&mut out, right, at)?;
+                i += 3;
+                continue;
+            }
+            if t.get_token_value() == "#" {
+                if let Some(p) = body.get(i + 1).and_then(&param) {
+                    out.push((stringize(&args[p], at), hide.to_vec()));
+                    i += 2;
+                    continue;
+                }
+            }
+            match param(t) {
+                Some(p) if is_paste(body, i + 1) => out.extend(hidden(&args[p])),
+                Some(p) => out.extend(hidden(&self.rescan(args[p].clone(), keep_translated)?)),
+                None => out.push((relocate(t, at), hide.to_vec())),
+            }
+            i += 1;
+        }
+        Ok(out)
+    }
+
+    /**
+     * eval:
+     * evaluates the controlling expression of #if and #elif
+     */
+    fn eval(&self, words: &[Token], pos: Span) -> Result<bool, PreprocessError> {
+        // `defined` is resolved before the line is macro expanded
+        let mut line = Vec::new();
+        let mut i = 0;
+        while i < words.len() {
+            if words[i].get_token_value() != "defined" {
+                line.push(words[i].clone());
+                i += 1;
+                continue;
+            }
+            let parens = words.get(i + 1).is_some_and(|t| t.get_token_value() == "(");
+            let at = if parens { i + 2 } else { i + 1 };
+            let defined = match words.get(at) {
+                Some(t) if is_name(t) => self.is_defined(&t.get_token_value()),
+                _ => return Err(error("operator \"defined\" requires an identifier", pos)),
+            };
+            if parens && words.get(at + 1).is_none_or(|t| t.get_token_value() != ")") {
+                return Err(error("missing ')' after \"defined\"", pos));
+            }
+            let value = if defined { "1" } else { "0" };
+            line.push(relocate(&Token::new(value.to_string(), BASE_VALUE, NUM_INT, 0, 0), &words[i]));
+            i = if parens { at + 2 } else { at + 1 };
+        }
+        let line = self.expand(line, false)?;
+        if line.is_empty() {
+            return Err(error("#if with no expression", pos));
+        }
+        let mut eval = Eval {
+            tokens: &line,
+            head: 0,
+        };
+        let value = eval.cond().map_err(|msg| error(&msg, pos))?;
+        if let Some(t) = line.get(eval.head) {
+            let msg = format!("missing binary operator before token \"{}\"", t.get_token_value());
+            return Err(error(&msg, pos));
+        }
+        Ok(value != 0)
+    }
+}
+
+/**
+ * collect_args:
+ * pops the parenthesized arguments of a function-like macro invocation,
+ * the extra arguments of a variadic macro are kept together
+ */
+fn collect_args(input: &mut Vec<Hidden>, mac: &Macro, at: &Token) -> Result<Vec<Vec<Hidden>>, PreprocessError> {
+    let count = mac.params.as_ref().map_or(0, |p| p.len());
+    input.pop();
+    let mut args: Vec<Vec<Hidden>> = vec![Vec::new()];
+    let mut depth = 0;
+    loop {
+        let (t, h) = match input.pop() {
+            Some(item) => item,
+            None => {
+                let msg = format!("unterminated argument list invoking macro \"{}\"", mac.name);
+                return Err(error(&msg, at.get_span()));
+            }
+        };
+        match &t.get_token_value()[..] {
+            ")" if depth == 0 => break,
+            "," if depth == 0 && !(mac.variadic && args.len() == count) => {
+                args.push(Vec::new());
+                continue;
+            }
+            "(" => depth += 1,
+            ")" => depth -= 1,
+            _ => {}
+        }
+        if t.get_base_type() != BASE_COMMENT {
+            args.last_mut().unwrap().push((t, h));
+        }
+    }
+    if count == 0 && args.len() == 1 && args[0].is_empty() {
+        args.clear();
+    }
+    if mac.variadic && args.len() + 1 == count {
+        args.push(Vec::new());
+    }
+    if args.len() != count {
+        let msg = format!("macro \"{}\" requires {} arguments, but {} given",
+                          mac.name,
+                          count,
+                          args.len());
+        return Err(error(&msg, at.get_span()));
+    }
+    Ok(args)
+}
+
+// `#param` turns the spelling of an argument into a string literal
+fn stringize(arg: &[Hidden], at: &Token) -> Token {
+    let mut text = String::from("\"");
+    for (k, (t, _)) in arg.iter().enumerate() {
+        if k > 0 && arg[k - 1].0.get_span().end != t.get_span().start {
+            text.push(' ');
+        }
+        let value = t.get_token_value();
+        match t.get_token_type() {
+            STRING | CHAR_VAL => text.push_str(&value.replace('\\', "\\\\").replace('"', "\\\"")),
+            _ => text.push_str(&value),
+        }
+    }
+    text.push('"');
+    relocate(&Token::new(text, BASE_VALUE, STRING, 0, at.get_token_id()), at)
+}
+
+/**
+ * paste:
+ * joins the last token of out and the first of right into one token,
+ * GNU `, ## __VA_ARGS__` drops the comma when there are no extra arguments
+ */
+fn paste(out: &mut Vec<Hidden>, right: Vec<Hidden>, at: &Token) -> Result<(), PreprocessError> {
+    let (left, hide) = match out.pop() {
+        Some(item) => item,
+        None => {
+            out.extend(right);
+            return Ok(());
+        }
+    };
+    if left.get_token_value() == "," {
+        if !right.is_empty() {
+            out.push((left, hide));
+            out.extend(right);
+        }
+        return Ok(());
+    }
+    let mut right = right.into_iter();
+    let first = match right.next() {
+        Some((first, _)) => first,
+        None => {
+            out.push((left, hide));
+            return Ok(());
+        }
+    };
+    let text = left.get_token_value() + &first.get_token_value();
+    let msg = format!("pasting \"{}\" and \"{}\" does not give a valid preprocessing token",
+                      left.get_token_value(),
+                      first.get_token_value());
+    let pasted = match lexer::Tokenizer::new(&text).tokenize() {
+        Ok(ref tokens) if tokens.len() == 1 => relocate(&tokens[0], at),
+        _ => return Err(error(&msg, at.get_span())),
+    };
+    out.push((pasted, hide));
+    out.extend(right);
+    Ok(())
+}
+
+// value of an integer literal, whatever its radix and suffix
+fn int_value(tok: &Token) -> Option<i64> {
+    let value = tok.get_token_value();
+    let digits: String = value[..value.len() - tok.get_suffix().len()]
+        .chars()
+        .filter(|&c| c != '\'')
+        .collect();
+    let (digits, radix) = match tok.get_radix() {
+        Radix::Hex => (&digits[2..], 16),
+        Radix::Binary => (&digits[2..], 2),
+        Radix::Octal => (&digits[1..], 8),
+        Radix::Decimal => (&digits[..], 10),
+    };
+    u64::from_str_radix(digits, radix).ok().map(|v| v as i64)
+}
+
+fn binop_prec(op: &str) -> Option<u8> {
+    match op {
+        "*" | "/" | "%" => Some(10),
+        "+" | "-" => Some(9),
+        "<<" | ">>" => Some(8),
+        "<" | ">" | "<=" | ">=" => Some(7),
+        "==" | "!=" => Some(6),
+        "&" => Some(5),
+        "^" => Some(4),
+        "|" => Some(3),
+        "&&" => Some(2),
+        "||" => Some(1),
+        _ => None,
+    }
+}
+
+// integer constant expression of #if, on macro expanded tokens
+struct Eval<'a> {
+    tokens: &'a [Token],
+    head: usize,
+}
+
+impl<'a> Eval<'a> {
+    fn peek(&self) -> String {
+        self.tokens.get(self.head).map_or(String::new(), |t| t.get_token_value())
+    }
+
+    fn expect(&mut self, value: &str) -> Result<(), String> {
+        if self.peek() != value {
+            return Err(format!("expected '{}' in preprocessor expression", value));
+        }
+        self.head += 1;
+        Ok(())
+    }
+
+    fn cond(&mut self) -> Result<i64, String> {
+        let c = self.binary(1)?;
+        if self.peek() != "?" {
+            return Ok(c);
+        }
+        self.head += 1;
+        let then = self.cond()?;
+        self.expect(":")?;
+        let other = self.cond()?;
+        Ok(if c != 0 { then } else { other })
+    }
+
+    fn binary(&mut self, min: u8) -> Result<i64, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = self.peek();
+            let prec = match binop_prec(&op) {
+                Some(prec) if prec >= min => prec,
+                _ => return Ok(lhs),
+            };
+            self.head += 1;
+            let rhs = self.binary(prec + 1)?;
+            lhs = match &op[..] {
+                "*" => lhs.wrapping_mul(rhs),
+                "/" | "%" if rhs == 0 => return Err("division by zero in #if".to_string()),
+                "/" => lhs.wrapping_div(rhs),
+                "%" => lhs.wrapping_rem(rhs),
+                "+" => lhs.wrapping_add(rhs),
+                "-" => lhs.wrapping_sub(rhs),
+                "<<" => lhs.wrapping_shl(rhs as u32),
+                ">>" => lhs.wrapping_shr(rhs as u32),
+                "<" => (lhs < rhs) as i64,
+                ">" => (lhs > rhs) as i64,
+                "<=" => (lhs <= rhs) as i64,
+                ">=" => (lhs >= rhs) as i64,
+                "==" => (lhs == rhs) as i64,
+                "!=" => (lhs != rhs) as i64,
+                "&" => lhs & rhs,
+                "^" => lhs ^ rhs,
+                "|" => lhs | rhs,
+                "&&" => (lhs != 0 && rhs != 0) as i64,
+                _ => (lhs != 0 || rhs != 0) as i64,
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        let tok = match self.tokens.get(self.head) {
+            Some(tok) => tok.clone(),
+            None => return Err("expected value in expression".to_string()),
+        };
+        self.head += 1;
+        let value = tok.get_token_value();
+        match &value[..] {
+            "-" => return Ok(self.unary()?.wrapping_neg()),
+            "+" => return self.unary(),
+            "!" => return Ok((self.unary()? == 0) as i64),
+            "~" => return Ok(!self.unary()?),
+            "(" => {
+                let v = self.cond()?;
+                self.expect(")")?;
+                return Ok(v);
+            }
+            "true" => return Ok(1),
+            _ => {}
+        }
+        match tok.get_token_type() {
+            NUM_INT => int_value(&tok).ok_or_else(|| format!("integer constant {} is too large", value)),
+            NUM_FLOAT => Err("floating constant in preprocessor expression".to_string()),
+            CHAR_VAL => {
+                let body = &value[1..value.len() - 1];
+                Ok(lexer::unescape(body).chars().next().map_or(0, |c| c as i64))
+            }
+            // identifiers left after macro expansion are 0
+            _ if is_name(&tok) => Ok(0),
+            _ => Err(format!("token \"{}\" is not valid in preprocessor expressions", value)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use library::lexer;
+    use library::preprocessor::Preprocessor;
+    use std::path::PathBuf;
+    use std::{env, fs, process};
+
+    fn preprocess(pp: &mut Preprocessor, text: &str) -> Result<String, String> {
+        let tokens = lexer::Tokenizer::new(text).tokenize().unwrap();
+        match pp.run(&tokens) {
+            Ok(tokens) => {
+                Ok(tokens.iter().map(|t| t.get_token_value()).collect::<Vec<_>>().join(" "))
+            }
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_conditionals() {
+        let mut pp = Preprocessor::new();
+        pp.define("DEBUG").unwrap();
+        let text = "#ifdef DEBUG\na;\n#else\nb;\n#endif\n\
+                    #if defined(RELEASE) || LEVEL > 2\nc;\n#elif DEBUG && 1 << 2 == 4\nd;\n#else\ne;\n#endif\n\
+                    #ifndef DEBUG\n#if 1\nf;\n#endif\n#endif\n";
+        assert_eq!(preprocess(&mut pp, text), Ok("a ; d ;".to_string()));
+
+        pp.undefine("DEBUG");
+        let text = "#if 0\n#error not reached\n#elif 0x10 > 'a' ? 0 : 1\ng;\n#endif\n";
+        assert_eq!(preprocess(&mut pp, text), Ok("g ;".to_string()));
+    }
+
+    #[test]
+    fn test_expansion() {
+        let mut pp = Preprocessor::new();
+        pp.define("N=10").unwrap();
+        // macros defined inside braces are always expanded
+        let text = "void f() {\n#define TWICE(x) ((x) * 2)\n#define MAX(a, b) ((a) > (b) ? (a) : (b))\n\
+                    int v[N] = TWICE(N);\nint m = MAX(TWICE(1),\n N);\n\
+                    #undef N\nint n = N; int t = TWICE;\n}\n";
+        assert_eq!(preprocess(&mut pp, text),
+                   Ok("void f ( ) { int v [ 10 ] = ( ( 10 ) * 2 ) ; \
+                       int m = ( ( ( ( 1 ) * 2 ) ) > ( 10 ) ? ( ( ( 1 ) * 2 ) ) : ( 10 ) ) ; \
+                       int n = N ; int t = TWICE ; }"
+                       .to_string()));
+
+        // a macro is not expanded again inside its own expansion
+        let text = "#define foo foo + 1\n#define a b\n#define b a\nfoo; a; b;\n";
+        assert_eq!(preprocess(&mut pp, text), Ok("foo + 1 ; a ; b ;".to_string()));
+    }
+
+    #[test]
+    fn test_stringize_and_paste() {
+        let mut pp = Preprocessor::new();
+        let text = "#define STR(x) #x\n#define CAT(a, b) a ## b\n#define LOG(fmt, ...) printf(fmt, ## __VA_ARGS__)\n\
+                    STR(a + \"b\"); CAT(var, 1) = CAT(1, 2); LOG(\"x\"); LOG(\"%d\", 1, 2);\n";
+        assert_eq!(preprocess(&mut pp, text),
+                   Ok("\"a + \\\"b\\\"\" ; var1 = 12 ; printf ( \"x\" ) ; \
+                       printf ( \"%d\" , 1 , 2 ) ;"
+                       .to_string()));
+        let diagnostics: Vec<String> = pp.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(diagnostics,
+                   vec!["1:1: warning: macro `STR` uses stringizing (#) which has no Rust equivalent, \
+                         its uses are expanded in place",
+                        "2:1: warning: macro `CAT` uses token pasting (##) which has no Rust equivalent, \
+                         its uses are expanded in place"]);
+    }
+
+    #[test]
+    fn test_passed_through() {
+        let mut pp = Preprocessor::new();
+        let text = "#include <stdio.h>\n#pragma once\n#pragma pack(1)\n#define X \\\n  42\nint x = X;\n";
+        assert_eq!(preprocess(&mut pp, text),
+                   Ok("# include < stdio . h > # pragma pack ( 1 ) # define X 42 int x = X ;".to_string()));
+    }
+
+    #[test]
+    fn test_constants() {
+        let mut pp = Preprocessor::new();
+        let text = "#define SIZE 16\n#define MASK (SIZE - 1) // low bits\n#define START x\n\
+                    #define SIZE 16\n#if SIZE > 8\nint v[SIZE];\n#endif\n\
+                    int f() { \n#define LOCAL 2\nreturn LOCAL + START; }\n";
+        assert_eq!(preprocess(&mut pp, text),
+                   Ok("# define SIZE 16 # define MASK ( SIZE - 1 ) int v [ SIZE ] ; \
+                       int f ( ) { return 2 + x ; }"
+                       .to_string()));
+
+        let text = "#define OFFSET base.off\n#define MIN(a, b) ((a) < (b) ? (a) : (b))\n\
+                    #define AT(i) (OFFSET + i)\n#define SWAP(a, b) do { int t = a; a = b; b = t; } while (0)\n\
+                    #define BAD(a) a; a\nSWAP(x, y); int m = MIN(x, AT(1)); BAD(z);\n";
+        assert_eq!(preprocess(&mut pp, text),
+                   Ok("# define MIN ( a , b ) ( ( a ) < ( b ) ? ( a ) : ( b ) ) \
+                       # define AT ( i ) ( base . off + i ) \
+                       # define SWAP ( a , b ) do { int t = a ; a = b ; b = t ; } while ( 0 ) \
+                       SWAP ( x , y ) ; int m = MIN ( x , AT ( 1 ) ) ; z ; z ;"
+                       .to_string()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |text: &str| preprocess(&mut Preprocessor::new(), text);
+        assert_eq!(error("#if 1\nint x;\n"),
+                   Err("1:1: error: unterminated conditional directive".to_string()));
+        assert_eq!(error("int x;\n#endif\n"),
+                   Err("2:1: error: #endif without #if".to_string()));
+        assert_eq!(error("#if 0\n#else\n#else\n#endif\n"),
+                   Err("3:1: error: #else after #else".to_string()));
+        assert_eq!(error("#error no support\n"),
+                   Err("1:1: error: #error no support".to_string()));
+        assert_eq!(error("{\n#define F(a, b) a\nF(1);\n}\n"),
+                   Err("3:1: error: macro \"F\" requires 2 arguments, but 1 given".to_string()));
+        assert_eq!(error("#if 1 +\n#endif\n"),
+                   Err("1:1: error: expected value in expression".to_string()));
+        assert_eq!(error("#define S(x) #y\n"),
+                   Err("1:1: error: '#' is not followed by a macro parameter".to_string()));
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = env::temp_dir().join(format!("crust_include_{}", process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("b.h"), "#pragma once\n#define B 2\nint b;\n").unwrap();
+        fs::write(dir.join("a.h"), "#include \"b.h\"\n#include \"a.h\"\nint a;\n").unwrap();
+        let text = "#include \"a.h\"\n#include \"b.h\"\n#include \"c.h\"\nint x = B;\n";
+        let tokens = lexer::Tokenizer::new(text).tokenize().unwrap();
+
+        let mut pp = Preprocessor::new();
+        pp.add_include_path(&dir.join("sub"));
+        let result = pp.run_file(&tokens, &dir.join("main.c")).unwrap();
+        let result: Vec<String> = result.iter().map(|t| t.get_token_value()).collect();
+        assert_eq!(result.join(" "), "# include \"c.h\" int x = B ;");
+
+        let b = dir.join("sub").join("b.h").canonicalize().unwrap();
+        let a = dir.join("a.h").canonicalize().unwrap();
+        let paths: Vec<&PathBuf> = pp.headers.iter().map(|h| &h.path).collect();
+        assert_eq!(paths, vec![&b, &a]);
+        assert_eq!(pp.headers[1].uses, vec![b.clone(), a.clone()]);
+        assert_eq!(pp.uses, vec![a, b]);
+        let header: Vec<String> = pp.headers[0].tokens.iter().map(|t| t.get_token_value()).collect();
+        assert_eq!(header.join(" "), "# define B 2 int b ;");
+        assert_eq!(pp.diagnostics.len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+use library::ast::*;
+
+// variables an expression reads and writes before its next sequence point
+#[derive(Debug, Default)]
+struct Access {
+    reads: Vec<String>,
+    writes: Vec<String>,
+}
+
+impl Access {
+    fn read(name: &str) -> Access {
+        Access {
+            reads: vec![name.to_string()],
+            writes: Vec::new(),
+        }
+    }
+
+    // variables one side writes and the other reads or writes
+    fn conflicts(&self, other: &Access) -> Vec<String> {
+        let touches = |a: &Access, name: &String| a.reads.contains(name) || a.writes.contains(name);
+        self.writes
+            .iter()
+            .filter(|name| touches(other, name))
+            .chain(other.writes.iter().filter(|name| touches(self, name)))
+            .cloned()
+            .collect()
+    }
+
+    fn join(&mut self, other: Access) {
+        self.reads.extend(other.reads);
+        self.writes.extend(other.writes);
+    }
+}
+
+// collects the variables modified and accessed without a sequence point
+struct Checker {
+    found: Vec<String>,
+}
+
+
+/**
+ * check:
+ * warns about full expressions which modify a variable and read or
+ * modify it again without a sequence point in between, like `i = i++`
+ * or `a[i] = i++`. C leaves their behaviour undefined
+ */
+pub fn check(program: &mut Program) {
+    let mut exprs: Vec<&Expr> = Vec::new();
+    for decl in &program.decls {
+        full_exprs_decl(decl, &mut exprs);
+    }
+    let mut diagnostics = Vec::new();
+    for expr in exprs {
+        let mut checker = Checker { found: Vec::new() };
+        checker.access(expr);
+        for name in checker.found {
+            diagnostics.push(Diagnostic {
+                msg: format!("`{}` is modified and accessed without a sequence point in between, the behaviour \
+                              is undefined in C",
+                             name),
+                pos: expr.pos,
+            });
+        }
+    }
+    program.diagnostics.append(&mut diagnostics);
+}
+
+
+// expressions whose evaluation ends in a sequence point
+fn full_exprs_decl<'a>(decl: &'a Decl, exprs: &mut Vec<&'a Expr>) {
+    match *decl {
+        Decl::Var(ref vars) => exprs.extend(vars.iter().filter_map(|v| v.init.as_ref())),
+        Decl::Function(ref func) => {
+            for stmt in func.body.iter().flatten() {
+                full_exprs(stmt, exprs);
+            }
+        }
+        Decl::Record(ref record) => {
+            for stmt in record.methods.iter().flat_map(|m| m.func.body.iter().flatten()) {
+                full_exprs(stmt, exprs);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn full_exprs<'a>(stmt: &'a Stmt, exprs: &mut Vec<&'a Expr>) {
+    match stmt.kind {
+        StmtKind::Decl(ref decl) => full_exprs_decl(decl, exprs),
+        StmtKind::Expr(ref e) | StmtKind::Return(Some(ref e)) => exprs.push(e),
+        StmtKind::Block(ref stmts) => {
+            for s in stmts {
+                full_exprs(s, exprs);
+            }
+        }
+        StmtKind::If(ref c, ref t, ref o) => {
+            exprs.push(c);
+            full_exprs(t, exprs);
+            if let Some(ref o) = *o {
+                full_exprs(o, exprs);
+            }
+        }
+        StmtKind::While(ref c, ref body) | StmtKind::DoWhile(ref body, ref c) => {
+            exprs.push(c);
+            full_exprs(body, exprs);
+        }
+        StmtKind::For(ref init, ref c, ref u, ref body) => {
+            if let Some(ref init) = *init {
+                full_exprs(init, exprs);
+            }
+            exprs.extend(c.iter().chain(u.iter()));
+            full_exprs(body, exprs);
+        }
+        StmtKind::Switch(ref c, ref cases) => {
+            exprs.push(c);
+            for s in cases.iter().flat_map(|case| &case.body) {
+                full_exprs(s, exprs);
+            }
+        }
+        _ => {}
+    }
+}
+
+
+impl Checker {
+    fn report(&mut self, name: &str) {
+        if !self.found.iter().any(|n| n == name) {
+            self.found.push(name.to_string());
+        }
+    }
+
+    // joins accesses C evaluates in no particular order
+    fn unsequenced(&mut self, parts: Vec<Access>) -> Access {
+        let mut all = Access::default();
+        for part in parts {
+            for name in all.conflicts(&part) {
+                self.report(&name);
+            }
+            all.join(part);
+        }
+        all
+    }
+
+    fn access(&mut self, expr: &Expr) -> Access {
+        match expr.kind {
+            ExprKind::Ident(ref name) => Access::read(name),
+            ExprKind::Unary(UnOp::PreInc, ref target) | ExprKind::Unary(UnOp::PreDec, ref target) |
+            ExprKind::Unary(UnOp::PostInc, ref target) | ExprKind::Unary(UnOp::PostDec, ref target) => {
+                let mut access = self.access(target);
+                if let ExprKind::Ident(ref name) = target.kind {
+                    access.writes.push(name.clone());
+                }
+                access
+            }
+            // the address is not a read
+            ExprKind::Unary(UnOp::AddrOf, ref e) if matches!(e.kind, ExprKind::Ident(_)) => Access::default(),
+            ExprKind::Unary(_, ref e) | ExprKind::Cast(_, ref e) | ExprKind::Member(ref e, _) => self.access(e),
+            // the left operand of &&, || and the condition of ?: are sequenced
+            // before the rest, so are the operands of a comma
+            ExprKind::Binary(BinOp::And, ref l, ref r) | ExprKind::Binary(BinOp::Or, ref l, ref r) => {
+                let mut access = self.access(l);
+                access.join(self.access(r));
+                access
+            }
+            ExprKind::Cond(ref c, ref t, ref o) => {
+                let mut access = self.access(c);
+                access.join(self.access(t));
+                access.join(self.access(o));
+                access
+            }
+            ExprKind::Comma(ref list) | ExprKind::InitList(ref list) => {
+                let mut access = Access::default();
+                for e in list {
+                    access.join(self.access(e));
+                }
+                access
+            }
+            ExprKind::Binary(_, ref l, ref r) | ExprKind::Index(ref l, ref r) => {
+                let parts = vec![self.access(l), self.access(r)];
+                self.unsequenced(parts)
+            }
+            // the store follows the value of both sides, the variable
+            // assigned is written and read by a compound assignment
+            ExprKind::Assign(op, ref l, ref r) => {
+                let (target, place) = match l.kind {
+                    ExprKind::Ident(ref name) => (Some(name), Access::default()),
+                    _ => (None, self.access(l)),
+                };
+                let value = self.access(r);
+                let mut access = self.unsequenced(vec![place, value]);
+                if let Some(name) = target {
+                    if access.writes.contains(name) {
+                        self.report(name);
+                    }
+                    if op.is_some() {
+                        access.reads.push(name.clone());
+                    }
+                    access.writes.push(name.clone());
+                }
+                access
+            }
+            ExprKind::Call(ref f, ref args) => {
+                let parts = std::iter::once(&**f).chain(args).map(|e| self.access(e)).collect();
+                self.unsequenced(parts)
+            }
+            _ => Access::default(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use library::lexer;
+    use library::parser::{self, Options};
+
+    #[test]
+    fn test_check() {
+        let source = "int f(int x, int y) { return x; }\n\
+                      int main() {\n\
+                      int i = 0; int j = 0; int a[4];\n\
+                      a[i++] = a[++j];\n\
+                      i = i++;\n\
+                      j = f(j++, j);\n\
+                      i = i + 1;\n\
+                      a[i] = i++;\n\
+                      if (i++ && i) j += i;\n\
+                      return i++ + i;\n\
+                      }";
+        let tokens = lexer::Tokenizer::new(source).tokenize().unwrap();
+        let program = parser::init_parser(&tokens, &Options::default());
+        let lines: Vec<(u32, &str)> = program.diagnostics
+            .iter()
+            .map(|d| (d.pos.line, &d.msg[..d.msg.find(' ').unwrap()]))
+            .collect();
+        assert_eq!(lines, vec![(5, "`i`"), (6, "`j`"), (8, "`i`"), (10, "`i`")]);
+    }
+}
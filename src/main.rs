@@ -8,20 +8,27 @@ mod library;
 
 use std::process::Command;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs::File;
 use std::io::Read;
 use std::io::Write;
 use std::io::BufReader;
 
 struct Settings {
-    strict: bool,
+    mutability: codegen::Mutability,
     project_name: Option<String>,
     files: Vec<String>,
+    defines: Vec<String>, // -D NAME[=VALUE]
+    undefines: Vec<String>, // -U NAME
+    includes: Vec<String>, // -I DIR
+    naming: bool,
 }
 
 use library::lexer;
+use library::preprocessor;
 use library::parser;
+use library::codegen;
+use library::ast;
 
 fn print_usage(program: &str, opts: Options) {
     let brief = format!("Usage: {} FILE [options]", program);
@@ -34,7 +41,12 @@ fn main() {
 
     let mut opts = Options::new();
     opts.optflag("s", "strict", "Strict mode (immutable)");
+    opts.optflag("l", "loose", "Loose mode (mutable)");
     opts.optopt("p", "project-name", "Cargo project name", "NAME");
+    opts.optmulti("D", "define", "Define a preprocessor macro", "NAME[=VALUE]");
+    opts.optmulti("U", "undefine", "Undefine a preprocessor macro", "NAME");
+    opts.optmulti("I", "include", "Add a directory to the header search path", "DIR");
+    opts.optflag("n", "rust-names", "Rename items to follow the Rust naming conventions");
     opts.optflag("h", "help", "show this help message");
 
     let matches = match opts.parse(&args[1..]) {
@@ -49,8 +61,18 @@ fn main() {
         get_settings_interactively()
     } else {
         Settings {
-            strict: matches.opt_present("s"),
+            mutability: if matches.opt_present("s") {
+                codegen::Mutability::Strict
+            } else if matches.opt_present("l") {
+                codegen::Mutability::Loose
+            } else {
+                codegen::Mutability::Inferred
+            },
             project_name: matches.opt_str("p"),
+            defines: matches.opt_strs("D"),
+            undefines: matches.opt_strs("U"),
+            includes: matches.opt_strs("I"),
+            naming: matches.opt_present("n"),
             files: matches.free,
         }
     };
@@ -65,15 +87,16 @@ fn get_settings_interactively() -> Settings {
     io::stdout().flush().ok().expect("FATAL : Buffer flush failed");
     io::stdin().read_line(&mut input).expect("Unable to read");
 
-    let mut strict = String::new();
+    let mut mode = String::new();
 
-    print!("Enter the translation mode [(S/s)trict/(L/l)oose] : ");
+    print!("Enter the translation mode [(S/s)trict/(L/l)oose/(I/i)nferred] : ");
     io::stdout().flush().ok().expect("FATAL : Buffer flush failed");
-    io::stdin().read_line(&mut strict).expect("Unable to read");
-    let strict = strict.trim();
-    let strict: bool = match &strict[..] {
-        "S" | "Strict" | "s" => true,
-        _ => false,
+    io::stdin().read_line(&mut mode).expect("Unable to read");
+    let mode = mode.trim();
+    let mutability = match &mode[..] {
+        "S" | "Strict" | "s" => codegen::Mutability::Strict,
+        "L" | "Loose" | "l" => codegen::Mutability::Loose,
+        _ => codegen::Mutability::Inferred,
     };
 
     let mut cargo = String::new();
@@ -96,102 +119,260 @@ fn get_settings_interactively() -> Settings {
     }
 
     Settings {
-        strict: strict,
+        mutability: mutability,
         project_name: project_name,
         files: vec![input.trim().to_owned()],
+        defines: Vec::new(),
+        undefines: Vec::new(),
+        includes: Vec::new(),
+        naming: false,
     }
 }
 
-fn invoke(settings: &Settings) {
-    for input in settings.files.iter() {
+// a source file and the headers it includes, parsed
+struct Translation {
+    input: String,
+    program: ast::Program,
+    // headers come before the files including them
+    headers: Vec<(preprocessor::Header, ast::Program)>,
+    // the headers the file includes itself
+    uses: Vec<PathBuf>,
+}
 
-        let file = match File::open(input) {
-            Ok(f) => f,
-            Err(err) => {
-                println!("Unable to open input source file '{}': {}.", input, err);
-                std::process::exit(1);
-            }
-        };
-        // get the reader
-        let mut reader = BufReader::new(&file);
-        let mut text: String = String::new();
-        let size = reader.read_to_string(&mut text).expect("Unable to read file.");
-
-        println!("Input file size : {}bytes ", size);
-
-        let mut tok = lexer::Tokenizer::new(&text);
-        print!("Tokenizing");
-
-        let mut out: Vec<String> = Vec::new();
-        let tokens = tok.tokenize();
-        // tok.tokenize();
-        let mut ln = 0;
-        for i in &tokens {
-            let mut temp = i.get_token_value();
-            if i.get_token_ln() != ln {
-                temp = "\n".to_string() + &temp[..];
-            }
-            ln = i.get_token_ln();
-            out.push(temp);
+// a file name as a module name
+fn module_ident(stem: &str) -> String {
+    let mut name: String = stem.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+// name of the module a source file other than the first is translated into
+fn source_module(input: &str) -> String {
+    module_ident(&Path::new(input.trim()).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default())
+}
+
+/**
+ * module_name:
+ * name of the module a header is translated into, the file stem
+ * unless it clashes with one of the translated source files
+ */
+fn module_name(header: &Path, settings: &Settings) -> String {
+    let stem = header.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut name = module_ident(&stem);
+    let clash = name == "main" ||
+                settings.files.iter().any(|f| Path::new(f).file_stem() == header.file_stem());
+    if clash {
+        name.push_str("_h");
+    }
+    name
+}
+
+/**
+ * parse_file:
+ * preprocesses and parses a source file along with the headers it
+ * includes, reporting their diagnostics
+ */
+fn parse_file(input: &str, settings: &Settings) -> Translation {
+    let file = match File::open(input) {
+        Ok(f) => f,
+        Err(err) => {
+            println!("Unable to open input source file '{}': {}.", input, err);
+            std::process::exit(1);
         }
+    };
+    // get the reader
+    let mut reader = BufReader::new(&file);
+    let mut text: String = String::new();
+    let size = reader.read_to_string(&mut text).expect("Unable to read file.");
 
-        for _ in 0..7 {
-            print!(".");
-            io::stdout().flush().ok().expect("Buffer cleaning error");
-            //    std::thread::sleep(std::time::Duration::from_millis(500));
+    println!("Input file size : {}bytes ", size);
 
+    let mut tok = lexer::Tokenizer::new(&text);
+    print!("Tokenizing");
+
+    let mut out: Vec<String> = Vec::new();
+    let tokens = match tok.tokenize() {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            println!();
+            println!("{}:{}", input.trim(), err);
+            std::process::exit(1);
+        }
+    };
+    let mut preprocessor = preprocessor::Preprocessor::new();
+    for definition in &settings.defines {
+        if let Err(err) = preprocessor.define(definition) {
+            println!();
+            println!("-D{}: error: {}", definition, err.msg);
+            std::process::exit(1);
+        }
+    }
+    for name in &settings.undefines {
+        preprocessor.undefine(name);
+    }
+    for dir in &settings.includes {
+        preprocessor.add_include_path(Path::new(dir));
+    }
+    let tokens = match preprocessor.run_file(&tokens, Path::new(input.trim())) {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            println!();
+            println!("{}:{}", input.trim(), err);
+            std::process::exit(1);
+        }
+    };
+    if !preprocessor.diagnostics.is_empty() {
+        println!();
+        for diagnostic in &preprocessor.diagnostics {
+            println!("{}:{}", input.trim(), diagnostic);
+        }
+    }
+    let mut ln = 0;
+    for i in &tokens {
+        let mut temp = i.get_token_value();
+        if i.get_token_ln() != ln {
+            temp = "\n".to_string() + &temp[..];
         }
+        ln = i.get_token_ln();
+        out.push(temp);
+    }
+
+    for _ in 0..7 {
+        print!(".");
+        io::stdout().flush().ok().expect("Buffer cleaning error");
+        //    std::thread::sleep(std::time::Duration::from_millis(500));
+
+    }
 
-        //    file.write_all(output.as_bytes()).expect("Unable to write to file");
+    //    file.write_all(output.as_bytes()).expect("Unable to write to file");
 
-        println!("\t:DONE");
-        print!("Invoking Parser .");
+    println!("\t:DONE");
+    print!("Invoking Parser .");
 
-        for _ in 0..7 {
-            print!(".");
-            io::stdout().flush().ok().expect("Buffer cleaning error");
-            //    std::thread::sleep(std::time::Duration::from_millis(600));
+    for _ in 0..7 {
+        print!(".");
+        io::stdout().flush().ok().expect("Buffer cleaning error");
+        //    std::thread::sleep(std::time::Duration::from_millis(600));
 
+    }
+    // headers come before the files including them
+    let mut options = parser::Options {
+        naming: settings.naming,
+        ..Default::default()
+    };
+    let mut headers = Vec::new();
+    for header in &preprocessor.headers {
+        let program = parser::init_parser(&header.tokens, &options);
+        options.type_names.extend(program.type_names());
+        options.renames.extend(program.renames.iter().cloned());
+        for diagnostic in header.diagnostics.iter().chain(&program.diagnostics) {
+            println!();
+            println!("{}:{}", header.path.display(), diagnostic);
         }
-		let mode = if settings.strict { "Strict" } else { "Loose" };
-        let rust_lexeme = parser::init_parser(&tokens, settings.strict);
-        //regenerate the code from lexemes
-        let mut o: String = String::new();
-        for i in rust_lexeme {
-            o = o + " ";
-            o = o + &i[..];
+        headers.push(program);
+    }
+    let program = parser::init_parser(&tokens, &options);
+    if !program.diagnostics.is_empty() {
+        println!();
+        for diagnostic in &program.diagnostics {
+            println!("{}:{}", input.trim(), diagnostic);
         }
+    }
+    println!("\t:DONE");
+    Translation {
+        input: input.to_string(),
+        program,
+        headers: preprocessor.headers.into_iter().zip(headers).collect(),
+        uses: preprocessor.uses,
+    }
+}
 
-        println!("\t:DONE");
-        let mut fname = PathBuf::from(input);
-        fname.set_extension("rs");
+fn invoke(settings: &Settings) {
+    let translations: Vec<Translation> = settings.files.iter().map(|input| parse_file(input, settings)).collect();
+    let mode = format!("{:?}", settings.mutability);
 
-        if let Some(ref project_name) = settings.project_name {
-            let child = Command::new("cargo")
-                .args(&["new", "--bin"])
-                .arg(&project_name[..])
-                .status()
-                .expect("Failed to create project");
-            if child.code().unwrap() == 101 {
-                println!("Project already exist with the name : {}, it will be overwritten by \
-                          the `crust`.",
-                         project_name);
-
-                fname = PathBuf::from(project_name.clone() + "/src/main.rs");
-            }
-            if child.success() {
-                fname = PathBuf::from(project_name.clone() + "/src/main.rs");
+    // the first file is the crate root, the others become its modules
+    let mut root = PathBuf::from(&settings.files[0]);
+    root.set_extension("rs");
+    if let Some(ref project_name) = settings.project_name {
+        let child = Command::new("cargo")
+            .args(&["new", "--bin"])
+            .arg(&project_name[..])
+            .status()
+            .expect("Failed to create project");
+        if child.code().unwrap() == 101 {
+            println!("Project already exist with the name : {}, it will be overwritten by \
+                      the `crust`.",
+                     project_name);
+
+            root = PathBuf::from(project_name.clone() + "/src/main.rs");
+        }
+        if child.success() {
+            root = PathBuf::from(project_name.clone() + "/src/main.rs");
+        }
+        println!("child code {} ", child.code().unwrap());
+    }
+    let sources: Vec<String> = settings.files[1..].iter().map(|input| source_module(input)).collect();
+    // every module is declared once, by the crate root
+    let mut mods: Vec<(String, bool)> = Vec::new();
+    for translation in &translations {
+        for &(ref header, ref program) in &translation.headers {
+            let name = module_name(&header.path, settings);
+            let macro_use = program.decls.iter().any(|decl| match *decl {
+                ast::Decl::Macro(ref def) => def.signature.is_none(),
+                _ => false,
+            });
+            match mods.iter_mut().find(|m| m.0 == name) {
+                Some(m) => m.1 |= macro_use,
+                None => mods.push((name, macro_use)),
             }
-            println!("child code {} ", child.code().unwrap());
         }
+    }
+    mods.extend(sources.iter().map(|name| (name.clone(), false)));
+    // definitions any of the files declares are used across the crate
+    let exported: Vec<String> = translations.iter().flat_map(|t| t.program.declarations()).collect();
+
+    // header modules already written in this session
+    let mut written: Vec<PathBuf> = Vec::new();
+    for (i, translation) in translations.iter().enumerate() {
+        let input = &translation.input;
+        let mut fname = if i == 0 { root.clone() } else { root.with_file_name(&sources[i - 1]) };
+        fname.set_extension("rs");
+        // the other files define what their headers declare
+        let mut uses: Vec<String> = translation.uses.iter().map(|path| module_name(path, settings)).collect();
+        uses.extend(sources.iter().enumerate().filter(|&(j, _)| j + 1 != i).map(|(_, name)| name.clone()));
+        //regenerate the code from the syntax tree
+        let unit = codegen::Unit {
+            public: false,
+            mods: if i == 0 { mods.clone() } else { Vec::new() },
+            uses,
+            imported: translation.headers.iter().map(|h| &h.1).collect(),
+            exported: exported.clone(),
+        };
+        let o = codegen::generate(&translation.program, settings.mutability, &unit);
 
-        let mut file = File::create(&fname).expect("Unable to open file to write");
-        file.write_all(o.as_bytes()).expect("Unable to write to file");
-        Command::new("rustfmt")
-            .arg("--")
-            .arg(&fname)
-            .output()
-            .expect("Failed to format the translated code");
+        let headers: Vec<&ast::Program> = translation.headers.iter().map(|h| &h.1).collect();
+        for (i, &(ref header, ref program)) in translation.headers.iter().enumerate() {
+            let mut path = root.with_file_name(module_name(&header.path, settings));
+            path.set_extension("rs");
+            if written.contains(&path) {
+                continue;
+            }
+            let unit = codegen::Unit {
+                public: true,
+                uses: header.uses.iter().map(|path| module_name(path, settings)).collect(),
+                imported: headers[..i].to_vec(),
+                ..Default::default()
+            };
+            write_source(&path, &codegen::generate(program, settings.mutability, &unit));
+            println!("Header `{}` is translated into the module `{}`",
+                     header.path.display(),
+                     path.display());
+            written.push(path);
+        }
+        write_source(&fname, &o);
         println!("Rust equivalent of source of `{}` in [{} mode ], is generated successfully, \n\
 		View the rust code in file : `{}`",
                  input.trim(),
@@ -199,3 +380,14 @@ fn invoke(settings: &Settings) {
                  fname.display());
     }
 }
+
+// writes the translated code and formats it
+fn write_source(path: &Path, code: &str) {
+    let mut file = File::create(path).expect("Unable to open file to write");
+    file.write_all(code.as_bytes()).expect("Unable to write to file");
+    Command::new("rustfmt")
+        .arg("--")
+        .arg(path)
+        .output()
+        .expect("Failed to format the translated code");
+}